@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_curves::{
+    bn256::{Fr, G1Affine, G1},
+    group::{ff::Field, Curve, Group},
+};
+use plonk_verifier::util::msm::multi_scalar_multiplication;
+use rand::rngs::OsRng;
+
+// Number of pairs in the final MSM of `succinct_verify` for a single snark of
+// standard plonk, and for `AggregationCircuit` with 2 and 8 snarks.
+const SIZES: [usize; 4] = [32, 64, 256, 1024];
+
+fn serial_msm(scalars: &[Fr], bases: &[G1Affine]) -> G1Affine {
+    scalars
+        .iter()
+        .zip(bases.iter())
+        .map(|(scalar, base)| base * scalar)
+        .reduce(|acc, value| acc + value)
+        .unwrap()
+        .to_affine()
+}
+
+fn bench_msm(c: &mut Criterion) {
+    let mut group = c.benchmark_group("msm");
+    for size in SIZES {
+        let scalars = (0..size).map(|_| Fr::random(OsRng)).collect::<Vec<_>>();
+        let bases = (0..size).map(|_| G1::random(OsRng).to_affine()).collect::<Vec<_>>();
+
+        group.bench_with_input(BenchmarkId::new("serial", size), &size, |b, _| {
+            b.iter(|| serial_msm(&scalars, &bases))
+        });
+        group.bench_with_input(BenchmarkId::new("pippenger", size), &size, |b, _| {
+            b.iter(|| multi_scalar_multiplication(&scalars, &bases).to_affine())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_msm);
+criterion_main!(benches);
@@ -1,7 +1,5 @@
 use application::StandardPlonk;
 use ark_std::{end_timer, start_timer};
-use ethereum_types::Address;
-use foundry_evm::executor::{fork::MultiFork, Backend, ExecutorBuilder};
 use halo2_curves::bn256::{Bn256, Fq, Fr, G1Affine};
 use halo2_proofs::{
     dev::MockProver,
@@ -23,34 +21,27 @@ use halo2_proofs::{
 use itertools::Itertools;
 use plonk_verifier::{
     loader::{
-        evm::{encode_calldata, EvmLoader},
+        evm::{runtime::deploy_and_call, EvmLoader},
         native::NativeLoader,
     },
     pcs::kzg::{Gwc19, Kzg, LimbsEncoding},
     system::halo2::{
         aggregation::{
-            self, create_snark_shplonk, gen_pk, gen_srs, write_bytes, AggregationCircuit, Snark,
-            TargetCircuit,
+            self, create_snark_shplonk, encode_calldata_with_committed_instances, gen_pk, gen_srs,
+            write_bytes, AggregationCircuit, InstanceHash, Snark, TargetCircuit, TargetInstances,
         },
         compile,
         transcript::evm::EvmTranscript,
-        Config,
+        Config, DefaultVerifierCircuitParams, Halo2VerifierCircuitParams,
     },
     verifier::{self, PlonkVerifier},
 };
 use rand::rngs::OsRng;
-use std::{
-    fs::{self, File},
-    io::Cursor,
-    rc::Rc,
-};
-
-const LIMBS: usize = 3;
-const BITS: usize = 88;
+use std::{fs, io::Cursor, rc::Rc};
 
 type Pcs = Kzg<Bn256, Gwc19>;
 // type As = KzgAs<Pcs>;
-type Plonk = verifier::Plonk<Pcs, LimbsEncoding<LIMBS, BITS>>;
+type Plonk<P = DefaultVerifierCircuitParams> = verifier::Plonk<Pcs, LimbsEncoding<P>>;
 
 mod application {
     use halo2_curves::bn256::Fr;
@@ -211,7 +202,7 @@ fn gen_proof<
             pk.get_vk(),
             Config::kzg(aggregation::KZG_QUERY_INSTANCE)
                 .with_num_instance(vec![instances[0].len()])
-                .with_accumulator_indices(aggregation::AggregationCircuit::accumulator_indices()),
+                .with_accumulator_indices(<aggregation::AggregationCircuit>::accumulator_indices()),
         );
         let mut transcript = Blake2bRead::<_, G1Affine, _>::init(proof.as_slice());
         let instances = &[instances[0].to_vec()];
@@ -252,12 +243,15 @@ fn gen_proof<
     proof
 }
 
-fn gen_aggregation_evm_verifier(
+/// Generates EVM verifier of `AggregationCircuit<P>`, which decodes the
+/// accumulator in instances by limbs of `P`, and checks the exposed
+/// commitment against committed instances following the proof if any.
+fn gen_aggregation_evm_verifier<P: Halo2VerifierCircuitParams>(
     params: &ParamsKZG<Bn256>,
     vk: &VerifyingKey<G1Affine>,
-    num_instance: Vec<usize>,
-    accumulator_indices: Vec<(usize, usize)>,
+    agg_circuit: &AggregationCircuit<P>,
 ) -> Vec<u8> {
+    let num_instance = agg_circuit.num_instance();
     let svk = params.get_g()[0].into();
     let dk = (params.g2(), params.s_g2()).into();
     let protocol = compile(
@@ -265,48 +259,42 @@ fn gen_aggregation_evm_verifier(
         vk,
         Config::kzg(aggregation::KZG_QUERY_INSTANCE)
             .with_num_instance(num_instance.clone())
-            .with_accumulator_indices(accumulator_indices),
+            .with_accumulator_indices(AggregationCircuit::<P>::accumulator_indices()),
     );
 
     let loader = EvmLoader::new::<Fq, Fr>();
     let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(loader.clone());
 
     let instances = transcript.load_instances(num_instance);
-    let proof = Plonk::read_proof(&svk, &protocol, &instances, &mut transcript).unwrap();
-    Plonk::verify(&svk, &dk, &protocol, &instances, &proof).unwrap();
+    let proof = Plonk::<P>::read_proof(&svk, &protocol, &instances, &mut transcript).unwrap();
+    Plonk::<P>::verify(&svk, &dk, &protocol, &instances, &proof).unwrap();
+
+    if let TargetInstances::Commitment { hash, .. } = agg_circuit.target_instances() {
+        let committed_instances =
+            transcript.load_instances(vec![agg_circuit.committed_instances().len()]);
+        hash.assert_commitment_on_evm(
+            &loader,
+            &committed_instances[0],
+            &instances[0][AggregationCircuit::<P>::commitment_index()],
+        );
+    }
 
     loader.deployment_code()
 }
 
-fn evm_verify(deployment_code: Vec<u8>, instances: Vec<Vec<Fr>>, proof: Vec<u8>) {
-    let calldata = encode_calldata(&instances, &proof);
+fn evm_verify(
+    deployment_code: Vec<u8>,
+    instances: Vec<Vec<Fr>>,
+    proof: Vec<u8>,
+    committed_instances: &[Fr],
+) {
+    let calldata =
+        encode_calldata_with_committed_instances(&instances, &proof, committed_instances);
     fs::write("./data/verifier_calldata.dat", hex::encode(&calldata)).unwrap();
-    let success = {
-        let mut evm = ExecutorBuilder::default()
-            .with_gas_limit(u64::MAX.into())
-            .build(Backend::new(MultiFork::new().0, None));
-
-        let caller = Address::from_low_u64_be(0xfe);
-        let verifier = evm.deploy(caller, deployment_code.into(), 0.into(), None).unwrap();
-        dbg!(verifier.gas);
-        let verifier = verifier.address;
-        let result = evm.call_raw(caller, verifier, calldata.into(), 0.into()).unwrap();
-
-        dbg!(result.gas);
+    let output = deploy_and_call(deployment_code, calldata);
+    dbg!(output.gas_used);
 
-        !result.reverted
-    };
-    assert!(success);
-}
-
-pub fn load_verify_circuit_degree() -> u32 {
-    let path = std::env::var("VERIFY_CONFIG").expect("export VERIFY_CONFIG with config path");
-    let params: plonk_verifier::system::halo2::Halo2VerifierCircuitConfigParams =
-        serde_json::from_reader(
-            File::open(path.clone()).expect(format!("{} file should exist", path).as_str()),
-        )
-        .unwrap();
-    params.degree
+    assert!(output.success);
 }
 
 impl TargetCircuit for StandardPlonk {
@@ -319,8 +307,7 @@ impl TargetCircuit for StandardPlonk {
 }
 
 fn main() {
-    std::env::set_var("VERIFY_CONFIG", "./configs/verify_circuit.config");
-    let k = load_verify_circuit_degree();
+    let k = <AggregationCircuit>::config_params().degree;
     let params = gen_srs(k);
 
     let params_app = {
@@ -337,16 +324,17 @@ fn main() {
     );
     let snarks = vec![snark];
 
-    let agg_circuit = AggregationCircuit::new(&params, snarks, true);
+    let agg_circuit: AggregationCircuit = AggregationCircuit::builder(&params)
+        .with_snarks(snarks)
+        .set_target_instances(TargetInstances::Commitment {
+            hash: InstanceHash::Poseidon,
+            indices: None,
+        })
+        .build();
     let pk = gen_pk(&params, &agg_circuit, "standard_plonk_agg_circuit");
 
     let deploy_time = start_timer!(|| "generate aggregation evm verifier code");
-    let deployment_code = gen_aggregation_evm_verifier(
-        &params,
-        pk.get_vk(),
-        agg_circuit.num_instance(),
-        AggregationCircuit::accumulator_indices(),
-    );
+    let deployment_code = gen_aggregation_evm_verifier(&params, pk.get_vk(), &agg_circuit);
     end_timer!(deploy_time);
     fs::write("./data/verifier_bytecode.dat", hex::encode(&deployment_code)).unwrap();
 
@@ -360,7 +348,10 @@ fn main() {
         None,
     );
     let snarks = vec![snark];
-    let agg_circuit = AggregationCircuit::new(&params, snarks, true); */
+    let agg_circuit: AggregationCircuit = AggregationCircuit::builder(&params)
+        .with_snarks(snarks)
+        .set_expose_target_instances(true)
+        .build(); */
     let proof_time = start_timer!(|| "create agg_circuit proof");
     let proof = gen_proof::<_, _, EvmTranscript<G1Affine, _, _, _>, EvmTranscript<G1Affine, _, _, _>>(
         &params,
@@ -371,6 +362,6 @@ fn main() {
     end_timer!(proof_time);
 
     let verify_time = start_timer!(|| "on-chain verification");
-    evm_verify(deployment_code, agg_circuit.instances(), proof);
+    evm_verify(deployment_code, agg_circuit.instances(), proof, agg_circuit.committed_instances());
     end_timer!(verify_time);
 }
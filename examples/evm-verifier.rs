@@ -1,5 +1,3 @@
-use ethereum_types::Address;
-use foundry_evm::executor::{fork::MultiFork, Backend, ExecutorBuilder};
 use halo2_curves::bn256::{Bn256, Fq, Fr, G1Affine};
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
@@ -23,7 +21,7 @@ use halo2_proofs::{
 };
 use itertools::Itertools;
 use plonk_verifier::{
-    loader::evm::{encode_calldata, EvmLoader},
+    loader::evm::{encode_calldata, runtime::deploy_and_call, EvmLoader},
     pcs::kzg::{Gwc19, Kzg},
     system::halo2::{
         aggregation::KZG_QUERY_INSTANCE, compile, transcript::evm::EvmTranscript, Config,
@@ -250,20 +248,10 @@ fn gen_evm_verifier(
 
 fn evm_verify(deployment_code: Vec<u8>, instances: Vec<Vec<Fr>>, proof: Vec<u8>) {
     let calldata = encode_calldata(&instances, &proof);
-    let success = {
-        let mut evm = ExecutorBuilder::default()
-            .with_gas_limit(u64::MAX.into())
-            .build(Backend::new(MultiFork::new().0, None));
+    let output = deploy_and_call(deployment_code, calldata);
+    dbg!(output.gas_used);
 
-        let caller = Address::from_low_u64_be(0xfe);
-        let verifier = evm.deploy(caller, deployment_code.into(), 0.into(), None).unwrap().address;
-        let result = evm.call_raw(caller, verifier, calldata.into(), 0.into()).unwrap();
-
-        dbg!(result.gas);
-
-        !result.reverted
-    };
-    assert!(success);
+    assert!(output.success);
 }
 
 fn main() {
@@ -27,7 +27,9 @@ use itertools::Itertools;
 use plonk_verifier::{
     loader::{self, native::NativeLoader, ScalarLoader},
     pcs::{
-        kzg::{Gwc19, Kzg, KzgAccumulator, KzgAs, KzgSuccinctVerifyingKey, LimbsEncoding},
+        kzg::{
+            ConstLimbs, Gwc19, Kzg, KzgAccumulator, KzgAs, KzgSuccinctVerifyingKey, LimbsEncoding,
+        },
         AccumulationScheme, AccumulationSchemeProver,
     },
     system::{
@@ -55,7 +57,7 @@ const R_P: usize = 60;
 type Pcs = Kzg<Bn256, Gwc19>;
 type Svk = KzgSuccinctVerifyingKey<G1Affine>;
 type As = KzgAs<Pcs>;
-type Plonk = verifier::Plonk<Pcs, LimbsEncoding<LIMBS, BITS>>;
+type Plonk = verifier::Plonk<Pcs, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>;
 type Poseidon<L> = hash::Poseidon<Fr, L, T, RATE>;
 type PoseidonTranscript<L, S> =
     system::halo2::transcript::halo2::PoseidonTranscript<G1Affine, L, S, T, RATE, R_F, R_P>;
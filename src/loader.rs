@@ -209,16 +209,69 @@ pub trait ScalarLoader<F: PrimeField> {
 pub trait Loader<C: CurveAffine>:
     EcPointLoader<C> + ScalarLoader<C::ScalarExt> + Clone + Debug
 {
+    /// Returns `a` if `sel` is `1` and `b` if `sel` is `0`, where `sel` is
+    /// expected to be constrained by [`Loader::assert_bool`] already.
     fn ec_point_select(
         &self,
-        _a: &Self::LoadedEcPoint,
-        _b: &Self::LoadedEcPoint,
-        _sel: &Self::LoadedScalar,
-    ) -> Result<Self::LoadedEcPoint, Error> {
-        todo!()
+        a: &Self::LoadedEcPoint,
+        b: &Self::LoadedEcPoint,
+        sel: &Self::LoadedScalar,
+    ) -> Result<Self::LoadedEcPoint, Error>;
+
+    /// Returns `a` if `sel` is `1` and `b` if `sel` is `0` as `b + sel * (a -
+    /// b)`, where `sel` is expected to be constrained by
+    /// [`Loader::assert_bool`] already.
+    fn scalar_select(
+        &self,
+        a: &Self::LoadedScalar,
+        b: &Self::LoadedScalar,
+        sel: &Self::LoadedScalar,
+    ) -> Result<Self::LoadedScalar, Error> {
+        Ok(LoadedScalar::mul_add(sel, &(a.clone() - b), b))
+    }
+
+    /// Asserts `scalar` is `0` or `1` by `scalar * scalar == scalar`.
+    fn assert_bool(&self, annotation: &str, scalar: &Self::LoadedScalar) -> Result<(), Error> {
+        self.assert_eq(annotation, &scalar.square(), scalar)
+    }
+
+    // Point arithmetic is on loader instead of as operator bounds on
+    // `LoadedEcPoint`, since native points are `CurveAffine` which can't
+    // implement operators outputting themselves. Defaults compute by
+    // `multi_scalar_multiplication`, which loaders could override with cheaper
+    // dedicated operations.
+
+    fn ec_point_add(
+        &self,
+        lhs: &Self::LoadedEcPoint,
+        rhs: &Self::LoadedEcPoint,
+    ) -> Self::LoadedEcPoint {
+        Self::LoadedEcPoint::multi_scalar_multiplication([
+            (self.load_one(), lhs.clone()),
+            (self.load_one(), rhs.clone()),
+        ])
+    }
+
+    fn ec_point_sub(
+        &self,
+        lhs: &Self::LoadedEcPoint,
+        rhs: &Self::LoadedEcPoint,
+    ) -> Self::LoadedEcPoint {
+        Self::LoadedEcPoint::multi_scalar_multiplication([
+            (self.load_one(), lhs.clone()),
+            (-self.load_one(), rhs.clone()),
+        ])
+    }
+
+    fn ec_point_neg(&self, ec_point: &Self::LoadedEcPoint) -> Self::LoadedEcPoint {
+        Self::LoadedEcPoint::multi_scalar_multiplication([(-self.load_one(), ec_point.clone())])
     }
 
     fn start_cost_metering(&self, _: &str) {}
 
     fn end_cost_metering(&self) {}
+
+    fn start_annotation(&self, _: &str) {}
+
+    fn end_annotation(&self) {}
 }
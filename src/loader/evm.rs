@@ -1,14 +1,32 @@
 mod code;
+mod gas;
 pub(crate) mod loader;
+mod source_map;
+mod target;
 mod util;
 
+#[cfg(feature = "loader_halo2")]
+mod poseidon;
+
+#[cfg(feature = "evm_runtime")]
+pub mod runtime;
+
 #[cfg(test)]
 mod test;
 
-pub use loader::{EcPoint, EvmLoader, Scalar};
-pub use util::{encode_calldata, estimate_gas, fe_to_u256, modulus, u256_to_fe, MemoryChunk};
+pub use gas::{calldata_gas, estimate_runtime_gas, GasEstimation, GasReport, INTRINSIC_GAS};
+pub use loader::{AccumulatorOutput, EcPoint, EvmLoader, Scalar};
+pub use source_map::{disassemble, revert_pc, Annotation, Instruction, SourceMap};
+pub use target::{EvmTarget, GasSchedule, Precompiles};
+pub use util::{
+    encode_calldata, estimate_gas, estimate_gas_for_target, fe_to_u256, modulus, u256_to_fe,
+    MemoryChunk,
+};
 
 pub use ethereum_types::U256;
 
+#[cfg(feature = "loader_halo2")]
+pub use poseidon::PoseidonSponge;
+
 #[cfg(test)]
 pub use test::execute;
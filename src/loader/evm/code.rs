@@ -1,8 +1,10 @@
 use crate::util::Itertools;
 use ethereum_types::U256;
-use std::{collections::HashMap, iter};
+use std::{collections::HashMap, iter, ops::Range};
 
+#[derive(Clone, Copy, Debug)]
 pub enum Precompiled {
+    Sha256 = 0x02,
     BigModExp = 0x05,
     Bn254Add = 0x6,
     Bn254ScalarMul = 0x7,
@@ -14,14 +16,20 @@ pub struct Code {
     code: Vec<u8>,
     constants: HashMap<U256, usize>,
     stack_len: usize,
+    push0: bool,
+    annotations: Vec<(String, Range<usize>)>,
+    open_annotations: Vec<(String, usize)>,
 }
 
 impl Code {
-    pub fn new(constants: impl IntoIterator<Item = U256>) -> Self {
+    pub fn new(constants: impl IntoIterator<Item = U256>, push0: bool) -> Self {
         let mut code = Self {
             code: Vec::new(),
             constants: HashMap::new(),
             stack_len: 0,
+            push0,
+            annotations: Vec::new(),
+            open_annotations: Vec::new(),
         };
         let constants = constants.into_iter().collect_vec();
         for constant in constants.iter() {
@@ -75,12 +83,105 @@ impl Code {
         self.code.is_empty()
     }
 
+    /// Returns closed annotations with the code range they cover, ordered by
+    /// the end of range.
+    pub fn annotations(&self) -> &[(String, Range<usize>)] {
+        &self.annotations
+    }
+
+    /// Starts annotating the code generated after this call with `label`,
+    /// annotations can be nested.
+    pub fn start_annotation(&mut self, label: impl Into<String>) -> &mut Self {
+        self.open_annotations.push((label.into(), self.code.len()));
+        self
+    }
+
+    /// Ends the last started annotation.
+    pub fn end_annotation(&mut self) -> &mut Self {
+        let (label, start) = self.open_annotations.pop().expect("No annotation to end");
+        self.annotations.push((label, start..self.code.len()));
+        self
+    }
+
+    /// Reverts immediately if the `success` on top of stack is false, with the
+    /// program counter of this check as 32 bytes revert data.
+    pub fn revert_if_failed(&mut self) -> &mut Self {
+        let pc = self.code.len();
+        // [..., success]
+        self.dup(0);
+        // [..., success, success]
+        let dst = self.code.len() + 4 + 11;
+        assert!(dst < 1 << 16);
+        self.code.extend([PUSH1 + 1, (dst >> 8) as u8, dst as u8, JUMPI]);
+        self.stack_len -= 1;
+        // [..., success]
+        self.code.extend([
+            PUSH1 + 1,
+            (pc >> 8) as u8,
+            pc as u8,
+            PUSH1,
+            0,
+            MSTORE,
+            PUSH1,
+            0x20,
+            PUSH1,
+            0,
+            REVERT,
+            JUMPDEST,
+        ]);
+        self
+    }
+
+    /// Generates a subroutine by `body` which is jumped over when reached in
+    /// sequence, and returns its entry for [`Code::call`].
+    ///
+    /// On entry the return destination is on top of stack, which `body` should
+    /// leave as it is.
+    pub fn subroutine(&mut self, body: impl FnOnce(&mut Self)) -> usize {
+        let skip = self.code.len() + 1;
+        self.code.extend([PUSH1 + 1, 0, 0, JUMP]);
+        let entry = self.code.len();
+        self.code.push(JUMPDEST);
+        // [..., return_dst]
+        self.stack_len += 1;
+        body(self);
+        self.jump();
+        let dst = self.code.len();
+        assert!(dst < 1 << 16);
+        self.code[skip] = (dst >> 8) as u8;
+        self.code[skip + 1] = dst as u8;
+        self.jumpdest();
+        entry
+    }
+
+    /// Jumps to subroutine at `entry` generated by [`Code::subroutine`], which
+    /// returns to right after this call.
+    pub fn call(&mut self, entry: usize) -> &mut Self {
+        let dst = self.code.len() + 7;
+        assert!(dst < 1 << 16);
+        self.code.extend([
+            PUSH1 + 1,
+            (dst >> 8) as u8,
+            dst as u8,
+            PUSH1 + 1,
+            (entry >> 8) as u8,
+            entry as u8,
+            JUMP,
+            JUMPDEST,
+        ]);
+        self
+    }
+
     pub fn push<T: Into<U256>>(&mut self, value: T) -> &mut Self {
         let value = value.into();
         match self.constants.get(&value) {
             Some(idx) if (0..16).contains(&(self.stack_len - idx - 1)) => {
                 self.dup(self.stack_len - idx - 1);
             }
+            _ if value.is_zero() && self.push0 => {
+                self.code.push(PUSH0);
+                self.stack_len += 1;
+            }
             _ => {
                 let mut bytes = vec![0; 32];
                 value.to_big_endian(&mut bytes);
@@ -196,6 +297,7 @@ impl_opcodes!(
     msize -> (MSIZE, 1)
     gas -> (GAS, 1)
     jumpdest -> (JUMPDEST, 0)
+    mcopy -> (MCOPY, -3)
     log0 -> (LOG0, -2)
     log1 -> (LOG1, -3)
     log2 -> (LOG2, -4)
@@ -212,84 +314,89 @@ impl_opcodes!(
     selfdestruct -> (SELFDESTRUCT, -1)
 );
 
-const STOP: u8 = 0x00;
-const ADD: u8 = 0x01;
-const MUL: u8 = 0x02;
-const SUB: u8 = 0x03;
-const DIV: u8 = 0x04;
-const SDIV: u8 = 0x05;
-const MOD: u8 = 0x06;
-const SMOD: u8 = 0x07;
-const ADDMOD: u8 = 0x08;
-const MULMOD: u8 = 0x09;
-const EXP: u8 = 0x0A;
-const SIGNEXTEND: u8 = 0x0B;
-const LT: u8 = 0x10;
-const GT: u8 = 0x11;
-const SLT: u8 = 0x12;
-const SGT: u8 = 0x13;
-const EQ: u8 = 0x14;
-const ISZERO: u8 = 0x15;
-const AND: u8 = 0x16;
-const OR: u8 = 0x17;
-const XOR: u8 = 0x18;
-const NOT: u8 = 0x19;
-const BYTE: u8 = 0x1A;
-const SHL: u8 = 0x1B;
-const SHR: u8 = 0x1C;
-const SAR: u8 = 0x1D;
-const SHA3: u8 = 0x20;
-const ADDRESS: u8 = 0x30;
-const BALANCE: u8 = 0x31;
-const ORIGIN: u8 = 0x32;
-const CALLER: u8 = 0x33;
-const CALLVALUE: u8 = 0x34;
-const CALLDATALOAD: u8 = 0x35;
-const CALLDATASIZE: u8 = 0x36;
-const CALLDATACOPY: u8 = 0x37;
-const CODESIZE: u8 = 0x38;
-const CODECOPY: u8 = 0x39;
-const GASPRICE: u8 = 0x3A;
-const EXTCODESIZE: u8 = 0x3B;
-const EXTCODECOPY: u8 = 0x3C;
-const RETURNDATASIZE: u8 = 0x3D;
-const RETURNDATACOPY: u8 = 0x3E;
-const EXTCODEHASH: u8 = 0x3F;
-const BLOCKHASH: u8 = 0x40;
-const COINBASE: u8 = 0x41;
-const TIMESTAMP: u8 = 0x42;
-const NUMBER: u8 = 0x43;
-const DIFFICULTY: u8 = 0x44;
-const GASLIMIT: u8 = 0x45;
-const CHAINID: u8 = 0x46;
-const SELFBALANCE: u8 = 0x47;
-const BASEFEE: u8 = 0x48;
-const POP: u8 = 0x50;
-const MLOAD: u8 = 0x51;
-const MSTORE: u8 = 0x52;
-const MSTORE8: u8 = 0x53;
-const SLOAD: u8 = 0x54;
-const SSTORE: u8 = 0x55;
-const JUMP: u8 = 0x56;
-const JUMPI: u8 = 0x57;
-const PC: u8 = 0x58;
-const MSIZE: u8 = 0x59;
-const GAS: u8 = 0x5A;
-const JUMPDEST: u8 = 0x5B;
-const PUSH1: u8 = 0x60;
-const DUP1: u8 = 0x80;
-const SWAP1: u8 = 0x90;
-const LOG0: u8 = 0xA0;
-const LOG1: u8 = 0xA1;
-const LOG2: u8 = 0xA2;
-const LOG3: u8 = 0xA3;
-const LOG4: u8 = 0xA4;
-const CREATE: u8 = 0xF0;
-const CALL: u8 = 0xF1;
-const CALLCODE: u8 = 0xF2;
-const RETURN: u8 = 0xF3;
-const DELEGATECALL: u8 = 0xF4;
-const CREATE2: u8 = 0xF5;
-const STATICCALL: u8 = 0xFA;
-const REVERT: u8 = 0xFD;
-const SELFDESTRUCT: u8 = 0xFF;
+pub(crate) const STOP: u8 = 0x00;
+pub(crate) const ADD: u8 = 0x01;
+pub(crate) const MUL: u8 = 0x02;
+pub(crate) const SUB: u8 = 0x03;
+pub(crate) const DIV: u8 = 0x04;
+pub(crate) const SDIV: u8 = 0x05;
+pub(crate) const MOD: u8 = 0x06;
+pub(crate) const SMOD: u8 = 0x07;
+pub(crate) const ADDMOD: u8 = 0x08;
+pub(crate) const MULMOD: u8 = 0x09;
+pub(crate) const EXP: u8 = 0x0A;
+pub(crate) const SIGNEXTEND: u8 = 0x0B;
+pub(crate) const LT: u8 = 0x10;
+pub(crate) const GT: u8 = 0x11;
+pub(crate) const SLT: u8 = 0x12;
+pub(crate) const SGT: u8 = 0x13;
+pub(crate) const EQ: u8 = 0x14;
+pub(crate) const ISZERO: u8 = 0x15;
+pub(crate) const AND: u8 = 0x16;
+pub(crate) const OR: u8 = 0x17;
+pub(crate) const XOR: u8 = 0x18;
+pub(crate) const NOT: u8 = 0x19;
+pub(crate) const BYTE: u8 = 0x1A;
+pub(crate) const SHL: u8 = 0x1B;
+pub(crate) const SHR: u8 = 0x1C;
+pub(crate) const SAR: u8 = 0x1D;
+pub(crate) const SHA3: u8 = 0x20;
+pub(crate) const ADDRESS: u8 = 0x30;
+pub(crate) const BALANCE: u8 = 0x31;
+pub(crate) const ORIGIN: u8 = 0x32;
+pub(crate) const CALLER: u8 = 0x33;
+pub(crate) const CALLVALUE: u8 = 0x34;
+pub(crate) const CALLDATALOAD: u8 = 0x35;
+pub(crate) const CALLDATASIZE: u8 = 0x36;
+pub(crate) const CALLDATACOPY: u8 = 0x37;
+pub(crate) const CODESIZE: u8 = 0x38;
+pub(crate) const CODECOPY: u8 = 0x39;
+pub(crate) const GASPRICE: u8 = 0x3A;
+pub(crate) const EXTCODESIZE: u8 = 0x3B;
+pub(crate) const EXTCODECOPY: u8 = 0x3C;
+pub(crate) const RETURNDATASIZE: u8 = 0x3D;
+pub(crate) const RETURNDATACOPY: u8 = 0x3E;
+pub(crate) const EXTCODEHASH: u8 = 0x3F;
+pub(crate) const BLOCKHASH: u8 = 0x40;
+pub(crate) const COINBASE: u8 = 0x41;
+pub(crate) const TIMESTAMP: u8 = 0x42;
+pub(crate) const NUMBER: u8 = 0x43;
+pub(crate) const DIFFICULTY: u8 = 0x44;
+pub(crate) const GASLIMIT: u8 = 0x45;
+pub(crate) const CHAINID: u8 = 0x46;
+pub(crate) const SELFBALANCE: u8 = 0x47;
+pub(crate) const BASEFEE: u8 = 0x48;
+pub(crate) const POP: u8 = 0x50;
+pub(crate) const MLOAD: u8 = 0x51;
+pub(crate) const MSTORE: u8 = 0x52;
+pub(crate) const MSTORE8: u8 = 0x53;
+pub(crate) const SLOAD: u8 = 0x54;
+pub(crate) const SSTORE: u8 = 0x55;
+pub(crate) const JUMP: u8 = 0x56;
+pub(crate) const JUMPI: u8 = 0x57;
+pub(crate) const PC: u8 = 0x58;
+pub(crate) const MSIZE: u8 = 0x59;
+pub(crate) const GAS: u8 = 0x5A;
+pub(crate) const JUMPDEST: u8 = 0x5B;
+pub(crate) const MCOPY: u8 = 0x5E;
+pub(crate) const PUSH0: u8 = 0x5F;
+pub(crate) const PUSH1: u8 = 0x60;
+pub(crate) const PUSH32: u8 = 0x7F;
+pub(crate) const DUP1: u8 = 0x80;
+pub(crate) const DUP16: u8 = 0x8F;
+pub(crate) const SWAP1: u8 = 0x90;
+pub(crate) const SWAP16: u8 = 0x9F;
+pub(crate) const LOG0: u8 = 0xA0;
+pub(crate) const LOG1: u8 = 0xA1;
+pub(crate) const LOG2: u8 = 0xA2;
+pub(crate) const LOG3: u8 = 0xA3;
+pub(crate) const LOG4: u8 = 0xA4;
+pub(crate) const CREATE: u8 = 0xF0;
+pub(crate) const CALL: u8 = 0xF1;
+pub(crate) const CALLCODE: u8 = 0xF2;
+pub(crate) const RETURN: u8 = 0xF3;
+pub(crate) const DELEGATECALL: u8 = 0xF4;
+pub(crate) const CREATE2: u8 = 0xF5;
+pub(crate) const STATICCALL: u8 = 0xFA;
+pub(crate) const REVERT: u8 = 0xFD;
+pub(crate) const SELFDESTRUCT: u8 = 0xFF;
@@ -0,0 +1,420 @@
+use crate::{
+    loader::evm::{code::*, EvmLoader, EvmTarget},
+    util::Itertools,
+    Error,
+};
+use ethereum_types::U256;
+use std::{collections::HashMap, rc::Rc};
+
+#[cfg(feature = "evm_runtime")]
+use crate::loader::evm::runtime;
+
+/// Intrinsic gas of a transaction.
+pub const INTRINSIC_GAS: u64 = 21000;
+
+/// Gas spent by a verifier broken down by stage.
+///
+/// Each stage is measured by the `GAS` difference around the code emitted for
+/// it, so `total` also contains the instrumentation overhead (around 800 gas
+/// per metered stage) and whatever is not covered by any stage.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasReport {
+    /// Reading the proof from calldata and squeezing challenges.
+    pub transcript: u64,
+    /// Evaluating common polynomials, constraints and linearization.
+    pub evaluations: u64,
+    /// Multi-open verification and MSM (including folding of accumulators).
+    pub msm: u64,
+    /// Pairing check.
+    pub pairing: u64,
+    /// Calldata cost by EIP-2028 (4 per zero byte and 16 per non-zero byte).
+    pub calldata: u64,
+    /// Total gas used by the transaction, including intrinsic gas.
+    pub total: u64,
+}
+
+impl GasReport {
+    pub fn new(identifiers: &[String], costs: &[u64], calldata: &[u8], total: u64) -> Self {
+        assert_eq!(identifiers.len(), costs.len());
+
+        let mut report = Self { calldata: calldata_gas(calldata), total, ..Default::default() };
+        for (identifier, cost) in identifiers.iter().zip(costs) {
+            match identifier.as_str() {
+                "transcript" => report.transcript += cost,
+                "evaluations" => report.evaluations += cost,
+                "msm" | "accumulation" => report.msm += cost,
+                "pairing" => report.pairing += cost,
+                _ => {}
+            }
+        }
+        report
+    }
+
+    /// Gas not attributed to any stage, calldata nor intrinsic gas.
+    pub fn other(&self) -> u64 {
+        self.total.saturating_sub(
+            INTRINSIC_GAS
+                + self.calldata
+                + self.transcript
+                + self.evaluations
+                + self.msm
+                + self.pairing,
+        )
+    }
+}
+
+pub fn calldata_gas(calldata: &[u8]) -> u64 {
+    calldata.iter().map(|byte| if *byte == 0 { 4 } else { 16 }).sum()
+}
+
+#[cfg(feature = "evm_runtime")]
+impl EvmLoader {
+    /// Executes the runtime code of an instrumented verifier (built by a loader
+    /// from [`EvmLoader::new_with_gas_metering`]) with `calldata` on a local
+    /// EVM, and returns the gas spent by each stage.
+    pub fn gas_report(self: &Rc<Self>, calldata: Vec<u8>) -> Result<GasReport, Error> {
+        assert!(self.is_gas_metering(), "EvmLoader is not created with gas metering");
+
+        let output = runtime::call(self.runtime_code(), calldata.clone());
+        if !output.success {
+            return Err(Error::AssertionFailure("Verifier reverted".to_string()));
+        }
+
+        let costs = output
+            .logs
+            .iter()
+            .filter(|log| log.data.is_empty())
+            .map(|log| log.topics[0].as_u64())
+            .collect_vec();
+
+        Ok(GasReport::new(&self.gas_metering_ids(), &costs, &calldata, output.gas_used))
+    }
+}
+
+/// Statically estimated gas of a verifier.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasEstimation {
+    /// Intrinsic gas of the target.
+    pub intrinsic: u64,
+    /// Gas of executing the runtime code.
+    pub execution: u64,
+    /// Expected length of calldata, derived from the largest offset read.
+    pub calldata_len: usize,
+    /// Calldata cost assuming all bytes are non-zero, which is an upper bound
+    /// and is exact for most of random field elements and points.
+    pub calldata: u64,
+}
+
+impl GasEstimation {
+    pub fn total(&self) -> u64 {
+        self.intrinsic + self.execution + self.calldata
+    }
+}
+
+impl EvmLoader {
+    /// Estimates gas of the verifier without executing it.
+    ///
+    /// Since the generated code for a [`Protocol`](crate::Protocol) with chosen
+    /// multi-open scheme and transcript is straight-line, the estimation
+    /// interprets the runtime code symbolically, tracking constants on stack
+    /// and in memory to price memory expansion, `KECCAK256` and precompile
+    /// calls exactly, assuming the verification succeeds.
+    ///
+    /// Returns an error if the code has an opcode not supported by the
+    /// interpreter or underflows the stack.
+    pub fn estimate_gas(self: &Rc<Self>) -> Result<GasEstimation, Error> {
+        estimate_runtime_gas(&self.runtime_code(), self.target())
+    }
+}
+
+/// Estimates gas of executing straight-line `code`, see
+/// [`EvmLoader::estimate_gas`].
+pub fn estimate_runtime_gas(code: &[u8], target: &EvmTarget) -> Result<GasEstimation, Error> {
+    let mut interpreter = Interpreter {
+        target,
+        gas: 0,
+        stack: Vec::new(),
+        memory: HashMap::new(),
+        memory_words: 0,
+        calldata_len: 0,
+    };
+    interpreter.run(code)?;
+    Ok(GasEstimation {
+        intrinsic: target.gas_schedule.intrinsic,
+        execution: interpreter.gas,
+        calldata_len: interpreter.calldata_len,
+        calldata: target.gas_schedule.calldata_non_zero_byte * interpreter.calldata_len as u64,
+    })
+}
+
+struct Interpreter<'a> {
+    target: &'a EvmTarget,
+    gas: u64,
+    stack: Vec<Option<U256>>,
+    memory: HashMap<usize, U256>,
+    memory_words: u64,
+    calldata_len: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    fn run(&mut self, code: &[u8]) -> Result<(), Error> {
+        let mut pc = 0;
+        while pc < code.len() {
+            let opcode = code[pc];
+            pc += 1;
+            match opcode {
+                STOP => return Ok(()),
+                PUSH0 => {
+                    self.gas += 2;
+                    self.stack.push(Some(U256::zero()));
+                }
+                PUSH1..=PUSH32 => {
+                    let len = (opcode - PUSH1 + 1) as usize;
+                    self.gas += 3;
+                    // Missing bytes at the end of code are read as zero
+                    let mut bytes = [0; 32];
+                    let available = len.min(code.len() - pc);
+                    bytes[..available].copy_from_slice(&code[pc..pc + available]);
+                    self.stack.push(Some(U256::from_big_endian(&bytes[..len])));
+                    pc += len;
+                }
+                DUP1..=DUP16 => {
+                    self.gas += 3;
+                    let depth = (opcode - DUP1) as usize + 1;
+                    self.check_depth(depth)?;
+                    let value = self.stack[self.stack.len() - depth];
+                    self.stack.push(value);
+                }
+                SWAP1..=SWAP16 => {
+                    self.gas += 3;
+                    let depth = (opcode - SWAP1) as usize + 2;
+                    self.check_depth(depth)?;
+                    let len = self.stack.len();
+                    self.stack.swap(len - 1, len - depth);
+                }
+                JUMP | JUMPI => {
+                    self.gas += if opcode == JUMP { 8 } else { 10 };
+                    let dst = self.pop()?;
+                    if opcode == JUMPI {
+                        self.pop()?;
+                    }
+                    // Assume the verification succeeds and every jump is taken.
+                    match dst {
+                        Some(dst) => pc = dst.as_usize(),
+                        None => return Ok(()),
+                    }
+                }
+                RETURN | REVERT => {
+                    let [offset, size] = self.pop_n()?;
+                    self.expand(offset, size);
+                    return Ok(());
+                }
+                MLOAD => {
+                    self.gas += 3;
+                    let offset = self.pop()?;
+                    self.expand(offset, Some(32.into()));
+                    let value = offset.and_then(|offset| self.memory.get(&offset.as_usize()));
+                    self.stack.push(value.cloned());
+                }
+                MSTORE | MSTORE8 => {
+                    self.gas += 3;
+                    let [offset, value] = self.pop_n()?;
+                    let size = if opcode == MSTORE { 32 } else { 1 };
+                    self.expand(offset, Some(size.into()));
+                    self.write(offset, size, value.filter(|_| opcode == MSTORE));
+                }
+                SHA3 => {
+                    let [offset, size] = self.pop_n()?;
+                    self.gas += 30 + 6 * words(size);
+                    self.expand(offset, size);
+                    self.stack.push(None);
+                }
+                CALLDATALOAD => {
+                    self.gas += 3;
+                    if let Some(offset) = self.pop()? {
+                        self.calldata_len = self.calldata_len.max(offset.as_usize() + 32);
+                    }
+                    self.stack.push(None);
+                }
+                CALLDATACOPY | CODECOPY | RETURNDATACOPY | MCOPY => {
+                    let [dst, src, size] = self.pop_n()?;
+                    self.gas += 3 + 3 * words(size);
+                    self.expand(dst, size);
+                    if opcode == MCOPY {
+                        self.expand(src, size);
+                    }
+                    self.write(dst, size.map_or(usize::MAX, |size| size.as_usize()), None);
+                }
+                LOG0..=LOG4 => {
+                    let [offset, size] = self.pop_n()?;
+                    let num_topic = (opcode - LOG0) as u64;
+                    for _ in 0..num_topic {
+                        self.pop()?;
+                    }
+                    self.gas += 375 + 375 * num_topic + 8 * size.map_or(0, |size| size.as_u64());
+                    self.expand(offset, size);
+                }
+                STATICCALL => {
+                    let [_, address, cd_ptr, cd_len, rd_ptr, rd_len] = self.pop_n()?;
+                    self.expand(cd_ptr, cd_len);
+                    self.expand(rd_ptr, rd_len);
+                    self.gas += self.target.gas_schedule.warm_call
+                        + self.precompile_gas(address, cd_ptr, cd_len);
+                    self.write(rd_ptr, rd_len.map_or(usize::MAX, |size| size.as_usize()), None);
+                    self.stack.push(None);
+                }
+                SLOAD => {
+                    self.gas += 2100;
+                    self.pop()?;
+                    self.stack.push(None);
+                }
+                SSTORE => {
+                    self.gas += 22100;
+                    self.pop_n::<2>()?;
+                }
+                EXP => {
+                    let [_, exp] = self.pop_n()?;
+                    let exp_bytes = exp.map_or(32, |exp| (exp.bits() as u64 + 7) / 8);
+                    self.gas += 10 + 50 * exp_bytes;
+                    self.stack.push(None);
+                }
+                _ => {
+                    let (gas, num_input, num_output) = static_gas(opcode).ok_or_else(|| {
+                        Error::AssertionFailure(format!(
+                            "Unsupported opcode {:#x} at {} in gas estimation",
+                            opcode,
+                            pc - 1
+                        ))
+                    })?;
+                    self.gas += gas;
+                    let inputs =
+                        (0..num_input).map(|_| self.pop()).collect::<Result<Vec<_>, _>>()?;
+                    let output = match (opcode, inputs.as_slice()) {
+                        (ADD, [Some(a), Some(b)]) => Some(a.overflowing_add(*b).0),
+                        (SUB, [Some(a), Some(b)]) => Some(a.overflowing_sub(*b).0),
+                        (SHL, [Some(shift), Some(value)]) if *shift < 256.into() => {
+                            Some(*value << shift.as_usize())
+                        }
+                        _ => None,
+                    };
+                    if num_output == 1 {
+                        self.stack.push(output);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<(), Error> {
+        if self.stack.len() < depth {
+            return Err(Error::AssertionFailure("Stack underflow in gas estimation".to_string()));
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<U256>, Error> {
+        self.check_depth(1)?;
+        Ok(self.stack.pop().unwrap())
+    }
+
+    fn pop_n<const N: usize>(&mut self) -> Result<[Option<U256>; N], Error> {
+        self.check_depth(N)?;
+        Ok([(); N].map(|_| self.stack.pop().unwrap()))
+    }
+
+    fn expand(&mut self, offset: Option<U256>, size: Option<U256>) {
+        let (offset, size) = match (offset, size) {
+            (_, Some(size)) if size.is_zero() => return,
+            (Some(offset), Some(size)) => (offset.as_u64(), size.as_u64()),
+            _ => return,
+        };
+        let memory_words = (offset + size + 31) / 32;
+        if memory_words > self.memory_words {
+            self.gas += memory_gas(memory_words) - memory_gas(self.memory_words);
+            self.memory_words = memory_words;
+        }
+    }
+
+    fn write(&mut self, offset: Option<U256>, size: usize, value: Option<U256>) {
+        match offset {
+            Some(offset) => {
+                let offset = offset.as_usize();
+                self.memory
+                    .retain(|ptr, _| ptr + 32 <= offset || *ptr >= offset.saturating_add(size));
+                if let Some(value) = value {
+                    self.memory.insert(offset, value);
+                }
+            }
+            None => self.memory.clear(),
+        }
+    }
+
+    fn precompile_gas(
+        &self,
+        address: Option<U256>,
+        cd_ptr: Option<U256>,
+        cd_len: Option<U256>,
+    ) -> u64 {
+        let cd_len = cd_len.map_or(0, |cd_len| cd_len.as_u64());
+        let word = |idx: u64| {
+            cd_ptr.and_then(|cd_ptr| self.memory.get(&(cd_ptr.as_usize() + 32 * idx as usize)))
+        };
+        let precompiles = &self.target.precompiles;
+        let schedule = &self.target.gas_schedule;
+        let address = match address {
+            Some(address) => Some(address.as_usize()),
+            None => return 0,
+        };
+        match address {
+            _ if address == precompiles.sha256 => {
+                schedule.sha256_base + schedule.sha256_per_word * ((cd_len + 31) / 32)
+            }
+            _ if address == precompiles.big_mod_exp => {
+                let [base_len, exp_len, mod_len] =
+                    [0, 1, 2].map(|idx| word(idx).map_or(32, |len| len.as_u64()));
+                let exp_bits = if exp_len <= 32 && base_len == 32 {
+                    word(4).map_or(exp_len * 8, |exp| exp.bits() as u64)
+                } else {
+                    exp_len * 8
+                };
+                let multiplication_complexity = ((base_len.max(mod_len) + 7) / 8).pow(2);
+                let iteration_count = exp_bits.saturating_sub(1).max(1);
+                (multiplication_complexity * iteration_count / 3).max(schedule.big_mod_exp_min)
+            }
+            _ if address == precompiles.bn254_add => schedule.bn254_add,
+            _ if address == precompiles.bn254_scalar_mul => schedule.bn254_scalar_mul,
+            _ if address == precompiles.bn254_pairing => {
+                schedule.bn254_pairing_base + schedule.bn254_pairing_per_pair * (cd_len / 192)
+            }
+            _ => 0,
+        }
+    }
+}
+
+fn words(size: Option<U256>) -> u64 {
+    size.map_or(0, |size| (size.as_u64() + 31) / 32)
+}
+
+fn memory_gas(words: u64) -> u64 {
+    3 * words + words * words / 512
+}
+
+/// Returns gas, number of inputs and outputs of `opcode` with static gas, or
+/// `None` if it's not supported.
+fn static_gas(opcode: u8) -> Option<(u64, usize, usize)> {
+    let gas = match opcode {
+        ADD | SUB | LT | GT | SLT | SGT | EQ | AND | OR | XOR | BYTE | SHL | SHR | SAR => (3, 2, 1),
+        NOT | ISZERO => (3, 1, 1),
+        MUL | DIV | SDIV | MOD | SMOD | SIGNEXTEND => (5, 2, 1),
+        ADDMOD | MULMOD => (8, 3, 1),
+        ADDRESS | ORIGIN | CALLER | CALLVALUE | CALLDATASIZE | CODESIZE | GASPRICE
+        | RETURNDATASIZE | COINBASE | TIMESTAMP | NUMBER | DIFFICULTY | GASLIMIT | CHAINID
+        | BASEFEE | PC | MSIZE | GAS => (2, 0, 1),
+        SELFBALANCE => (5, 0, 1),
+        POP => (2, 1, 0),
+        JUMPDEST => (1, 0, 0),
+        _ => return None,
+    };
+    Some(gas)
+}
@@ -1,18 +1,18 @@
 use crate::{
     loader::evm::{
         code::{Code, Precompiled},
-        fe_to_u256, modulus,
+        fe_to_u256, modulus, EvmTarget,
     },
     loader::{evm::u256_to_fe, EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader},
     util::{
-        arithmetic::{CurveAffine, FieldOps, PrimeField},
+        arithmetic::{CurveAffine, FieldOps, LimbsDescriptor, PrimeField},
         Itertools,
     },
     Error,
 };
 use ethereum_types::{U256, U512};
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::HashMap,
     fmt::{self, Debug},
     iter,
@@ -46,47 +46,123 @@ impl<T: Debug> Value<T> {
     }
 }
 
+/// What a generated verifier does with the final KZG accumulator `(lhs, rhs)`,
+/// encoded as 4 words `lhs.x, lhs.y, rhs.x, rhs.y`.
+///
+/// Except [`AccumulatorOutput::Pairing`], the pairing is left to the caller,
+/// e.g. a contract batching many verifications with a single pairing, which
+/// could fold accumulators by
+/// [`fold_accumulators`](crate::pcs::kzg::fold_accumulators).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AccumulatorOutput {
+    /// Runs the pairing check inline.
+    #[default]
+    Pairing,
+    /// Returns the accumulator as return data.
+    Return,
+    /// Stores the accumulator in 4 consecutive storage slots from `slot`.
+    Store { slot: U256 },
+    /// Emits the accumulator as data of a log with single `topic`.
+    Emit { topic: U256 },
+}
+
 #[derive(Clone, Debug)]
 pub struct EvmLoader {
+    target: EvmTarget,
     base_modulus: U256,
     scalar_modulus: U256,
     code: RefCell<Code>,
     ptr: RefCell<usize>,
     cache: RefCell<HashMap<String, usize>>,
-    #[cfg(test)]
-    gas_metering_ids: RefCell<Vec<String>>,
+    gas_metering_ids: Option<RefCell<Vec<String>>>,
+    fail_fast: Cell<bool>,
+    accumulator_output: Cell<AccumulatorOutput>,
+    return_data: Cell<Option<(usize, usize)>>,
+    subroutines: RefCell<HashMap<(String, usize), usize>>,
 }
 
 impl EvmLoader {
     pub fn new<Base, Scalar>() -> Rc<Self>
+    where
+        Base: PrimeField<Repr = [u8; 32]>,
+        Scalar: PrimeField<Repr = [u8; 32]>,
+    {
+        Self::new_inner::<Base, Scalar>(EvmTarget::default(), false)
+    }
+
+    /// Returns a loader generating code for `target`, or an error if the
+    /// target is not supported, see [`EvmTarget::validate`].
+    pub fn new_with_target<Base, Scalar>(target: EvmTarget) -> Result<Rc<Self>, Error>
+    where
+        Base: PrimeField<Repr = [u8; 32]>,
+        Scalar: PrimeField<Repr = [u8; 32]>,
+    {
+        target.validate()?;
+        Ok(Self::new_inner::<Base, Scalar>(target, false))
+    }
+
+    /// Returns a loader whose generated code is instrumented to emit the gas
+    /// cost of each metered stage as `LOG1`, see [`EvmLoader::gas_report`].
+    pub fn new_with_gas_metering<Base, Scalar>() -> Rc<Self>
+    where
+        Base: PrimeField<Repr = [u8; 32]>,
+        Scalar: PrimeField<Repr = [u8; 32]>,
+    {
+        Self::new_inner::<Base, Scalar>(EvmTarget::default(), true)
+    }
+
+    fn new_inner<Base, Scalar>(target: EvmTarget, gas_metering: bool) -> Rc<Self>
     where
         Base: PrimeField<Repr = [u8; 32]>,
         Scalar: PrimeField<Repr = [u8; 32]>,
     {
         let base_modulus = modulus::<Base>();
         let scalar_modulus = modulus::<Scalar>();
-        let code = Code::new([1.into(), base_modulus, scalar_modulus - 1, scalar_modulus])
-            .push(1)
-            .to_owned();
+        let code =
+            Code::new([1.into(), base_modulus, scalar_modulus - 1, scalar_modulus], target.push0)
+                .push(1)
+                .to_owned();
         Rc::new(Self {
+            target,
             base_modulus,
             scalar_modulus,
             code: RefCell::new(code),
             ptr: Default::default(),
             cache: Default::default(),
-            #[cfg(test)]
-            gas_metering_ids: RefCell::new(Vec::new()),
+            gas_metering_ids: gas_metering.then(Default::default),
+            fail_fast: Cell::new(false),
+            accumulator_output: Default::default(),
+            return_data: Default::default(),
+            subroutines: Default::default(),
         })
     }
 
     pub fn deployment_code(self: &Rc<Self>) -> Vec<u8> {
-        Code::deployment(self.runtime_code())
+        let runtime_code = self.runtime_code();
+        if let Some(code_size_limit) = self.target.code_size_limit {
+            assert!(
+                runtime_code.len() <= code_size_limit,
+                "Contract size {} exceeds the limit {} of target",
+                runtime_code.len(),
+                code_size_limit
+            );
+        }
+        Code::deployment(runtime_code)
+    }
+
+    pub fn target(&self) -> &EvmTarget {
+        &self.target
     }
 
     pub fn runtime_code(self: &Rc<Self>) -> Vec<u8> {
         let mut code = self.code.borrow().clone();
-        let dst = code.len() + 9;
-        code.push(dst).jumpi().push(0).push(0).revert().jumpdest().stop().to_owned().into()
+        let dst = code.len() + if self.target.push0 { 7 } else { 9 };
+        code.push(dst).jumpi().push(0).push(0).revert().jumpdest();
+        match self.return_data.get() {
+            Some((ptr, len)) => code.push(len).push(ptr).r#return(),
+            None => code.stop(),
+        };
+        code.into()
     }
 
     pub fn allocate(self: &Rc<Self>, size: usize) -> usize {
@@ -107,6 +183,26 @@ impl EvmLoader {
         self.code.borrow_mut()
     }
 
+    /// Calls the subroutine identified by `identifier`, which is generated by
+    /// `body` at the first call at the current stack depth, see
+    /// [`Code::subroutine`].
+    pub(crate) fn call_subroutine(self: &Rc<Self>, identifier: &str, body: impl FnOnce(&mut Code)) {
+        let mut code = self.code.borrow_mut();
+        // Constants are pushed by position on stack, so the subroutine is only
+        // reused at the same stack depth, which differs e.g. within a metered
+        // stage.
+        let key = (identifier.to_string(), code.stack_len());
+        let some_entry = self.subroutines.borrow().get(&key).cloned();
+        let entry = if let Some(entry) = some_entry {
+            entry
+        } else {
+            let entry = code.subroutine(body);
+            self.subroutines.borrow_mut().insert(key, entry);
+            entry
+        };
+        code.call(entry);
+    }
+
     pub(crate) fn scalar(self: &Rc<Self>, value: Value<U256>) -> Scalar {
         let value = if matches!(value, Value::Constant(_) | Value::Memory(_) | Value::Negated(_)) {
             value
@@ -131,7 +227,7 @@ impl EvmLoader {
         EcPoint { loader: self.clone(), value }
     }
 
-    fn push(self: &Rc<Self>, scalar: &Scalar) {
+    pub(crate) fn push(self: &Rc<Self>, scalar: &Scalar) {
         match scalar.value.clone() {
             Value::Constant(constant) => {
                 self.code.borrow_mut().push(constant);
@@ -171,6 +267,54 @@ impl EvmLoader {
         self.scalar(Value::Memory(ptr))
     }
 
+    /// Loads a scalar from calldata in little-endian, which is the encoding of
+    /// `PrimeField::to_repr` of `halo2curves`.
+    pub fn calldataload_scalar_le(self: &Rc<Self>, offset: usize) -> Scalar {
+        let ptr = self.allocate(0x20);
+        self.code.borrow_mut().push(self.scalar_modulus).push(offset).calldataload();
+        self.reverse_bytes();
+        self.code.borrow_mut().r#mod().push(ptr).mstore();
+        self.scalar(Value::Memory(ptr))
+    }
+
+    /// Reverses bytes of the word on top of stack by swapping adjacent chunks
+    /// of 1, 2, 4, 8 and 16 bytes.
+    fn reverse_bytes(self: &Rc<Self>) {
+        let mut code = self.code.borrow_mut();
+        for bits in [8, 16, 32, 64] {
+            let chunk = (U256::one() << bits) - 1;
+            let mask = (0..256 / (2 * bits))
+                .fold(U256::zero(), |mask, idx| mask | (chunk << (2 * bits * idx)));
+            code
+                // [..., word]
+                .dup(0)
+                // [..., word, word]
+                .push(bits)
+                .shr()
+                .push(mask)
+                .and()
+                // [..., word, (word >> bits) & mask]
+                .swap(1)
+                .push(mask)
+                .and()
+                .push(bits)
+                .shl()
+                // [..., (word >> bits) & mask, (word & mask) << bits]
+                .or();
+        }
+        code
+            // [..., word]
+            .dup(0)
+            .push(128)
+            .shr()
+            // [..., word, word >> 128]
+            .swap(1)
+            .push(128)
+            .shl()
+            // [..., word >> 128, word << 128]
+            .or();
+    }
+
     pub fn calldataload_ec_point(self: &Rc<Self>, offset: usize) -> EcPoint {
         let ptr = self.allocate(0x40);
         self.code
@@ -200,20 +344,187 @@ impl EvmLoader {
         self.ec_point(Value::Memory(ptr))
     }
 
-    pub fn ec_point_from_limbs<const LIMBS: usize, const BITS: usize>(
+    /// Loads a compressed point from calldata, which is `x` in big-endian with
+    /// the most significant bit set when `y` is odd.
+    ///
+    /// It costs around 1.7k more gas than [`EvmLoader::calldataload_ec_point`]
+    /// to compute the square root by `BigModExp`, while it saves 32 bytes of
+    /// calldata (512 gas), so it only pays off when calldata is relatively
+    /// expensive (e.g. on rollups).
+    pub fn calldataload_ec_point_compressed(self: &Rc<Self>, offset: usize) -> EcPoint {
+        self.calldataload_ec_point_compressed_inner(offset, false)
+    }
+
+    /// Loads a compressed point from calldata in the encoding of
+    /// `halo2curves`, which is `x` in little-endian with the most significant
+    /// bit set when `y` is odd.
+    pub fn calldataload_ec_point_compressed_le(self: &Rc<Self>, offset: usize) -> EcPoint {
+        self.calldataload_ec_point_compressed_inner(offset, true)
+    }
+
+    fn calldataload_ec_point_compressed_inner(
+        self: &Rc<Self>,
+        offset: usize,
+        little_endian: bool,
+    ) -> EcPoint {
+        let ptr = self.allocate(0x40);
+        // Use unallocated memory as scratch space for the square root
+        let scratch_ptr = self.ptr();
+        let sqrt_exp = (self.base_modulus + 1) / 4;
+        self.code
+            .borrow_mut()
+            // [..., success]
+            .push(offset)
+            // [..., success, x_cd_ptr]
+            .calldataload();
+        // [..., success, word]
+        if little_endian {
+            self.reverse_bytes();
+        }
+        self.code
+            .borrow_mut()
+            // [..., success, word]
+            .dup(0)
+            // [..., success, word, word]
+            .push(255)
+            // [..., success, word, word, 255]
+            .shr()
+            // [..., success, word, sign]
+            .push(ptr + 0x20)
+            // [..., success, word, sign, y_ptr]
+            .mstore()
+            // [..., success, word]
+            .push(1)
+            // [..., success, word, 1]
+            .shl()
+            // [..., success, word << 1]
+            .push(1)
+            // [..., success, word << 1, 1]
+            .shr()
+            // [..., success, x]
+            .dup(0)
+            // [..., success, x, x]
+            .push(ptr)
+            // [..., success, x, x, x_ptr]
+            .mstore()
+            // [..., success, x]
+            .push(self.base_modulus)
+            // [..., success, x, p]
+            .push(3)
+            // [..., success, x, p, 3]
+            .push(self.base_modulus)
+            // [..., success, x, p, 3, p]
+            .dup(3)
+            // [..., success, x, p, 3, p, x]
+            .push(self.base_modulus)
+            // [..., success, x, p, 3, p, x, p]
+            .dup(1)
+            // [..., success, x, p, 3, p, x, p, x]
+            .dup(0)
+            // [..., success, x, p, 3, p, x, p, x, x]
+            .mulmod()
+            // [..., success, x, p, 3, p, x, x_square]
+            .mulmod()
+            // [..., success, x, p, 3, x_cube]
+            .addmod();
+        // [..., success, x, x_cube_plus_3]
+        if self.target.precompiles.big_mod_exp.is_some() {
+            self.code
+                .borrow_mut()
+                .push(scratch_ptr + 0x60)
+                // [..., success, x, x_cube_plus_3, base_ptr]
+                .mstore()
+                // [..., success, x]
+                .pop();
+            // [..., success]
+            for (idx, value) in [
+                (0, 0x20.into()),
+                (1, 0x20.into()),
+                (2, 0x20.into()),
+                (4, sqrt_exp),
+                (5, self.base_modulus),
+            ] {
+                self.code.borrow_mut().push(value).push(scratch_ptr + idx * 0x20).mstore();
+            }
+            self.staticcall(Precompiled::BigModExp, scratch_ptr, scratch_ptr + 0xc0);
+        } else {
+            self.pow_mod(sqrt_exp, self.base_modulus);
+            self.code
+                .borrow_mut()
+                // [..., success, x, root]
+                .push(scratch_ptr + 0xc0)
+                // [..., success, x, root, root_ptr]
+                .mstore()
+                // [..., success, x]
+                .pop();
+        }
+        self.code
+            .borrow_mut()
+            // [..., success]
+            .push(scratch_ptr + 0xc0)
+            // [..., success, root_ptr]
+            .mload()
+            // [..., success, root]
+            .push(ptr + 0x20)
+            // [..., success, root, y_ptr]
+            .mload()
+            // [..., success, root, sign]
+            .dup(1)
+            // [..., success, root, sign, root]
+            .push(1)
+            // [..., success, root, sign, root, 1]
+            .and()
+            // [..., success, root, sign, root_is_odd]
+            .xor()
+            // [..., success, root, flip]
+            .dup(1)
+            // [..., success, root, flip, root]
+            .push(self.base_modulus)
+            // [..., success, root, flip, root, p]
+            .sub()
+            // [..., success, root, flip, p_minus_root]
+            .dup(2)
+            // [..., success, root, flip, p_minus_root, root]
+            .xor()
+            // [..., success, root, flip, p_minus_root_xor_root]
+            .mul()
+            // [..., success, root, flip * p_minus_root_xor_root]
+            .xor()
+            // [..., success, y]
+            .dup(0)
+            // [..., success, y, y]
+            .push(ptr + 0x20)
+            // [..., success, y, y, y_ptr]
+            .mstore()
+            // [..., success, y]
+            .push(ptr)
+            // [..., success, y, x_ptr]
+            .mload()
+            // [..., success, y, x]
+            .swap(1);
+        // [..., success, x, y]
+        self.validate_ec_point();
+        self.ec_point(Value::Memory(ptr))
+    }
+
+    /// Composes coordinates from limbs split as `descriptor`.
+    pub fn ec_point_from_limbs(
         self: &Rc<Self>,
-        x_limbs: [Scalar; LIMBS],
-        y_limbs: [Scalar; LIMBS],
+        descriptor: LimbsDescriptor,
+        x_limbs: &[Scalar],
+        y_limbs: &[Scalar],
     ) -> EcPoint {
+        assert!(x_limbs.len() == descriptor.num_limbs && y_limbs.len() == descriptor.num_limbs);
+
         let ptr = self.allocate(0x40);
         for (ptr, limbs) in [(ptr, x_limbs), (ptr + 0x20, y_limbs)] {
-            for (idx, limb) in limbs.into_iter().enumerate() {
-                self.push(&limb);
+            for (idx, limb) in limbs.iter().enumerate() {
+                self.push(limb);
                 // [..., success, acc]
                 if idx > 0 {
                     self.code
                         .borrow_mut()
-                        .push(idx * BITS)
+                        .push(idx * descriptor.limb_bits)
                         // [..., success, acc, limb_i, shift]
                         .shl()
                         // [..., success, acc, limb_i << shift]
@@ -307,6 +618,7 @@ impl EvmLoader {
             .pop()
             // [..., success, valid]
             .and();
+        self.revert_if_failed();
     }
 
     pub fn keccak256(self: &Rc<Self>, ptr: usize, len: usize) -> usize {
@@ -315,6 +627,14 @@ impl EvmLoader {
         hash_ptr
     }
 
+    /// Returns pointer to SHA-256 digest of memory `[ptr, ptr + len)` computed
+    /// by the precompile.
+    pub fn sha256(self: &Rc<Self>, ptr: usize, len: usize) -> usize {
+        let hash_ptr = self.allocate(0x20);
+        self.staticcall_with_len(Precompiled::Sha256, ptr, len, hash_ptr, 0x20);
+        hash_ptr
+    }
+
     pub fn copy_scalar(self: &Rc<Self>, scalar: &Scalar, ptr: usize) {
         self.push(scalar);
         self.code.borrow_mut().push(ptr).mstore();
@@ -326,12 +646,56 @@ impl EvmLoader {
         self.scalar(Value::Memory(ptr))
     }
 
+    /// Pushes `x` of `ec_point` when `idx` is `0` and `y` when `idx` is `1`.
+    fn push_coordinate(code: &mut Code, ec_point: &EcPoint, idx: usize) {
+        match ec_point.value {
+            Value::Constant((x, y)) => {
+                code.push([x, y][idx]);
+            }
+            Value::Memory(ptr) => {
+                code.push(ptr + idx * 0x20).mload();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Selects coordinates by `b ^ (sel * (a ^ b))` without branching, which
+    /// assumes `sel` to be `0` or `1`.
+    fn select_ec_point(self: &Rc<Self>, a: &EcPoint, b: &EcPoint, sel: &Scalar) -> EcPoint {
+        let ptr = self.allocate(0x40);
+        for idx in 0..2 {
+            {
+                let mut code = self.code.borrow_mut();
+                Self::push_coordinate(&mut code, b, idx);
+                Self::push_coordinate(&mut code, a, idx);
+                // [..., b, a]
+                code.dup(1).xor();
+                // [..., b, a ^ b]
+            }
+            self.push(sel);
+            self.code.borrow_mut().mul().xor().push(ptr + idx * 0x20).mstore();
+        }
+        self.ec_point(Value::Memory(ptr))
+    }
+
+    /// Constrains `scalar` to be `0` or `1` by `scalar < 2`.
+    fn assert_bit(self: &Rc<Self>, scalar: &Scalar) {
+        self.code.borrow_mut().push(2);
+        self.push(scalar);
+        // [..., success, 2, scalar]
+        self.code.borrow_mut().lt().and();
+        self.revert_if_failed();
+    }
+
     pub fn dup_ec_point(self: &Rc<Self>, value: &EcPoint) -> EcPoint {
         let ptr = self.allocate(0x40);
         match value.value {
             Value::Constant((x, y)) => {
                 self.code.borrow_mut().push(x).push(ptr).mstore().push(y).push(ptr + 0x20).mstore();
             }
+            Value::Memory(src_ptr) if self.target.mcopy => {
+                self.code.borrow_mut().push(0x40).push(src_ptr).push(ptr).mcopy();
+            }
             Value::Memory(src_ptr) => {
                 self.code
                     .borrow_mut()
@@ -357,21 +721,67 @@ impl EvmLoader {
             Precompiled::Bn254Add => (0x80, 0x40),
             Precompiled::Bn254ScalarMul => (0x60, 0x40),
             Precompiled::Bn254Pairing => (0x180, 0x20),
+            Precompiled::Sha256 => unreachable!("Sha256 takes input of variable length"),
         };
+        self.staticcall_with_len(precompile, cd_ptr, cd_len, rd_ptr, rd_len);
+    }
+
+    fn staticcall_with_len(
+        self: &Rc<Self>,
+        precompile: Precompiled,
+        cd_ptr: usize,
+        cd_len: usize,
+        rd_ptr: usize,
+        rd_len: usize,
+    ) {
+        let address = self
+            .target
+            .precompiles
+            .address(precompile)
+            .expect("precompiles without fallback are checked by EvmTarget::validate");
         self.code
             .borrow_mut()
             .push(rd_len)
             .push(rd_ptr)
             .push(cd_len)
             .push(cd_ptr)
-            .push(precompile as usize)
+            .push(address)
             .gas()
             .staticcall()
             .and();
+        self.revert_if_failed();
+    }
+
+    /// Computes `base^exp mod modulus` by unrolled square-and-multiply, as the
+    /// fallback when `BigModExp` is not available.
+    fn pow_mod(self: &Rc<Self>, exp: U256, modulus: U256) {
+        assert!(!exp.is_zero());
+
+        let mut code = self.code.borrow_mut();
+        // [..., base]
+        code.dup(0);
+        // [..., base, acc]
+        for idx in (0..exp.bits() - 1).rev() {
+            code.push(modulus).swap(1).dup(0).mulmod();
+            // [..., base, acc * acc]
+            if exp.bit(idx) {
+                code.push(modulus).swap(1).dup(2).mulmod();
+                // [..., base, acc * base]
+            }
+        }
+        code.swap(1).pop();
+        // [..., base^exp]
     }
 
     fn invert(self: &Rc<Self>, scalar: &Scalar) -> Scalar {
         let rd_ptr = self.allocate(0x20);
+        if self.target.precompiles.big_mod_exp.is_none() {
+            self.push(scalar);
+            self.pow_mod(self.scalar_modulus - 2, self.scalar_modulus);
+            self.code.borrow_mut().push(rd_ptr).mstore();
+            return self.scalar(Value::Memory(rd_ptr));
+        }
+
         let [cd_ptr, ..] = [
             &self.scalar(Value::Constant(0x20.into())),
             &self.scalar(Value::Constant(0x20.into())),
@@ -392,6 +802,36 @@ impl EvmLoader {
         self.ec_point(Value::Memory(rd_ptr))
     }
 
+    /// Returns `-ec_point` with `y` replaced by `(p - y) % p`, which keeps the
+    /// identity `(0, 0)` as is.
+    fn ec_point_neg(self: &Rc<Self>, ec_point: &EcPoint) -> EcPoint {
+        match ec_point.value {
+            Value::Constant((x, y)) => {
+                let y = (self.base_modulus - y) % self.base_modulus;
+                self.ec_point(Value::Constant((x, y)))
+            }
+            Value::Memory(ptr) => {
+                let rd_ptr = self.allocate(0x40);
+                self.code
+                    .borrow_mut()
+                    .push(ptr)
+                    .mload()
+                    .push(rd_ptr)
+                    .mstore()
+                    .push(self.base_modulus)
+                    .push(ptr + 0x20)
+                    .mload()
+                    .push(self.base_modulus)
+                    .sub()
+                    .r#mod()
+                    .push(rd_ptr + 0x20)
+                    .mstore();
+                self.ec_point(Value::Memory(rd_ptr))
+            }
+            _ => unreachable!(),
+        }
+    }
+
     fn ec_point_scalar_mul(self: &Rc<Self>, ec_point: &EcPoint, scalar: &Scalar) -> EcPoint {
         let rd_ptr = self.dup_ec_point(ec_point).ptr();
         self.dup_scalar(scalar);
@@ -440,6 +880,7 @@ impl EvmLoader {
             .mstore();
         self.staticcall(Precompiled::Bn254Pairing, rd_ptr, rd_ptr);
         self.code.borrow_mut().push(rd_ptr).mload().and();
+        self.revert_if_failed();
     }
 
     fn add(self: &Rc<Self>, lhs: &Scalar, rhs: &Scalar) -> Scalar {
@@ -480,19 +921,66 @@ impl EvmLoader {
     }
 }
 
-#[cfg(test)]
 impl EvmLoader {
-    fn start_gas_metering(self: &Rc<Self>, identifier: &str) {
-        self.gas_metering_ids.borrow_mut().push(identifier.to_string());
-        self.code.borrow_mut().gas().swap(1);
+    pub fn is_fail_fast(&self) -> bool {
+        self.fail_fast.get()
+    }
+
+    /// Makes the code generated after this call revert as soon as a check
+    /// fails, instead of at the end, with the program counter of the failed
+    /// check as revert data. It costs a few more gas per check, and is meant
+    /// to locate the failure with [`Self::source_map`] and
+    /// [`revert_pc`](crate::loader::evm::revert_pc).
+    pub fn set_fail_fast(&self, fail_fast: bool) {
+        self.fail_fast.set(fail_fast);
+    }
+
+    pub fn accumulator_output(&self) -> AccumulatorOutput {
+        self.accumulator_output.get()
+    }
+
+    /// Sets what to do with the final KZG accumulator, see
+    /// [`AccumulatorOutput`].
+    pub fn set_accumulator_output(&self, accumulator_output: AccumulatorOutput) {
+        self.accumulator_output.set(accumulator_output);
+    }
+
+    /// Returns memory `[ptr, ptr + len)` when the verification succeeds.
+    pub(crate) fn set_return_data(&self, ptr: usize, len: usize) {
+        assert!(self.return_data.replace(Some((ptr, len))).is_none());
     }
 
-    fn end_gas_metering(self: &Rc<Self>) {
-        self.code.borrow_mut().swap(1).push(9).gas().swap(2).sub().sub().push(0).push(0).log1();
+    fn revert_if_failed(&self) {
+        if self.fail_fast.get() {
+            self.code.borrow_mut().revert_if_failed();
+        }
+    }
+
+    pub fn is_gas_metering(&self) -> bool {
+        self.gas_metering_ids.is_some()
+    }
+
+    pub fn gas_metering_ids(&self) -> Vec<String> {
+        self.gas_metering_ids.as_ref().map(|ids| ids.borrow().clone()).unwrap_or_default()
+    }
+
+    pub(crate) fn start_gas_metering(self: &Rc<Self>, identifier: &str) {
+        self.code.borrow_mut().start_annotation(identifier);
+        if let Some(ids) = self.gas_metering_ids.as_ref() {
+            ids.borrow_mut().push(identifier.to_string());
+            self.code.borrow_mut().gas().swap(1);
+        }
+    }
+
+    pub(crate) fn end_gas_metering(self: &Rc<Self>) {
+        if self.gas_metering_ids.is_some() {
+            self.code.borrow_mut().swap(1).push(9).gas().swap(2).sub().sub().push(0).push(0).log1();
+        }
+        self.code.borrow_mut().end_annotation();
     }
 
     pub fn print_gas_metering(self: &Rc<Self>, costs: Vec<u64>) {
-        for (identifier, cost) in self.gas_metering_ids.borrow().iter().zip(costs) {
+        for (identifier, cost) in self.gas_metering_ids().iter().zip(costs) {
             println!("{}: {}", identifier, cost);
         }
     }
@@ -545,14 +1033,21 @@ where
     }
 
     fn multi_scalar_multiplication(pairs: impl IntoIterator<Item = (Scalar, EcPoint)>) -> Self {
-        pairs
-            .into_iter()
-            .map(|(scalar, ec_point)| match scalar.value {
+        let mut acc = None;
+        for (idx, (scalar, ec_point)) in pairs.into_iter().enumerate() {
+            let loader = ec_point.loader.clone();
+            loader.code.borrow_mut().start_annotation(format!("msm term {}", idx));
+            let term = match scalar.value {
                 Value::Constant(constant) if constant == U256::one() => ec_point,
-                _ => ec_point.loader.ec_point_scalar_mul(&ec_point, &scalar),
-            })
-            .reduce(|acc, ec_point| acc.loader.ec_point_add(&acc, &ec_point))
-            .unwrap()
+                _ => loader.ec_point_scalar_mul(&ec_point, &scalar),
+            };
+            acc = Some(match acc {
+                Some(acc) => loader.ec_point_add(&acc, &term),
+                None => term,
+            });
+            loader.code.borrow_mut().end_annotation();
+        }
+        acc.unwrap()
     }
 }
 
@@ -779,8 +1274,18 @@ where
         self.ec_point(Value::Constant((x, y)))
     }
 
-    fn ec_point_assert_eq(&self, _: &str, _: &EcPoint, _: &EcPoint) -> Result<(), Error> {
-        unimplemented!()
+    fn ec_point_assert_eq(&self, _: &str, lhs: &EcPoint, rhs: &EcPoint) -> Result<(), Error> {
+        {
+            let mut code = self.code.borrow_mut();
+            for idx in 0..2 {
+                EvmLoader::push_coordinate(&mut code, lhs, idx);
+                EvmLoader::push_coordinate(&mut code, rhs, idx);
+                // [..., success, lhs, rhs]
+                code.eq().and();
+            }
+        }
+        self.revert_if_failed();
+        Ok(())
     }
 }
 
@@ -791,8 +1296,15 @@ impl<F: PrimeField<Repr = [u8; 0x20]>> ScalarLoader<F> for Rc<EvmLoader> {
         self.scalar(Value::Constant(fe_to_u256(*value)))
     }
 
-    fn assert_eq(&self, _: &str, _: &Scalar, _: &Scalar) -> Result<(), Error> {
-        unimplemented!()
+    /// Constrains `lhs` and `rhs` to be equal, which assumes both reduced by
+    /// the scalar modulus.
+    fn assert_eq(&self, _: &str, lhs: &Scalar, rhs: &Scalar) -> Result<(), Error> {
+        self.push(lhs);
+        self.push(rhs);
+        // [..., success, lhs, rhs]
+        self.code.borrow_mut().eq().and();
+        self.revert_if_failed();
+        Ok(())
     }
 
     fn sum_with_coeff_and_constant(&self, values: &[(F, &Scalar)], constant: F) -> Scalar {
@@ -928,13 +1440,58 @@ where
     C: CurveAffine,
     C::Scalar: PrimeField<Repr = [u8; 0x20]>,
 {
-    #[cfg(test)]
+    fn ec_point_select(&self, a: &EcPoint, b: &EcPoint, sel: &Scalar) -> Result<EcPoint, Error> {
+        match sel.value {
+            Value::Constant(sel) if sel.is_zero() => Ok(b.clone()),
+            Value::Constant(sel) if sel == U256::one() => Ok(a.clone()),
+            Value::Constant(_) => {
+                Err(Error::AssertionFailure("Selector should be 0 or 1".to_string()))
+            }
+            // Non-bit `sel` would silently mix coordinates of `a` and `b`, so
+            // it's asserted here too for just a few more gas
+            _ => {
+                self.assert_bit(sel);
+                Ok(self.select_ec_point(a, b, sel))
+            }
+        }
+    }
+
+    fn assert_bool(&self, annotation: &str, scalar: &Scalar) -> Result<(), Error> {
+        match scalar.value {
+            Value::Constant(constant) if constant < U256::from(2) => Ok(()),
+            Value::Constant(_) => Err(Error::AssertionFailure(annotation.to_string())),
+            _ => {
+                self.assert_bit(scalar);
+                Ok(())
+            }
+        }
+    }
+
+    fn ec_point_add(&self, lhs: &EcPoint, rhs: &EcPoint) -> EcPoint {
+        EvmLoader::ec_point_add(self, lhs, rhs)
+    }
+
+    fn ec_point_sub(&self, lhs: &EcPoint, rhs: &EcPoint) -> EcPoint {
+        EvmLoader::ec_point_add(self, lhs, &EvmLoader::ec_point_neg(self, rhs))
+    }
+
+    fn ec_point_neg(&self, ec_point: &EcPoint) -> EcPoint {
+        EvmLoader::ec_point_neg(self, ec_point)
+    }
+
     fn start_cost_metering(&self, identifier: &str) {
         self.start_gas_metering(identifier)
     }
 
-    #[cfg(test)]
     fn end_cost_metering(&self) {
         self.end_gas_metering()
     }
+
+    fn start_annotation(&self, label: &str) {
+        self.code.borrow_mut().start_annotation(label);
+    }
+
+    fn end_annotation(&self) {
+        self.code.borrow_mut().end_annotation();
+    }
 }
@@ -0,0 +1,225 @@
+use crate::{
+    loader::evm::{
+        code::Code,
+        fe_to_u256,
+        loader::{EvmLoader, Scalar, Value},
+    },
+    util::{
+        arithmetic::{FieldExt, PrimeField},
+        hash::TranscriptHash,
+        Itertools,
+    },
+};
+use ethereum_types::U256;
+use poseidon::{Spec, State};
+use std::rc::Rc;
+
+/// Poseidon sponge generating code for [`EvmLoader`], which computes the same
+/// as [`PoseidonChip`](crate::loader::halo2::poseidon_chip::PoseidonChip) with
+/// the optimized constants of `poseidon::Spec`, so a proof with
+/// `PoseidonTranscript` could be verified both in circuit and on chain.
+///
+/// The state is kept in a fixed memory region updated in place. Except adding
+/// inputs, the permutation is generated unrolled only once as a subroutine
+/// jumped to by every permutation, but it still takes tens of kilobytes of
+/// code (mostly the sparse matrices of partial rounds), so a verifier with it
+/// usually needs a target without code size limit.
+pub struct PoseidonSponge<F: PrimeField + FieldExt, const T: usize, const RATE: usize> {
+    loader: Rc<EvmLoader>,
+    spec: Spec<F, T, RATE>,
+    state_ptr: usize,
+    absorbing: Vec<Scalar>,
+}
+
+impl<F, const T: usize, const RATE: usize> PoseidonSponge<F, T, RATE>
+where
+    F: PrimeField<Repr = [u8; 0x20]> + FieldExt,
+{
+    pub fn new(loader: Rc<EvmLoader>, r_f: usize, r_p: usize) -> Self {
+        let state_ptr = loader.allocate(T * 0x20);
+        for (idx, word) in State::<F, T>::default().words().iter().enumerate() {
+            loader.code_mut().push(fe_to_u256(*word)).push(state_ptr + idx * 0x20).mstore();
+        }
+        Self { loader, spec: Spec::new(r_f, r_p), state_ptr, absorbing: Vec::new() }
+    }
+
+    pub fn update(&mut self, elements: &[Scalar]) {
+        self.absorbing.extend_from_slice(elements);
+    }
+
+    pub fn squeeze(&mut self) -> Scalar {
+        let input_elements = std::mem::take(&mut self.absorbing);
+
+        let mut padding_offset = 0;
+        for chunk in input_elements.chunks(RATE) {
+            padding_offset = RATE - chunk.len();
+            self.permutation(chunk);
+        }
+        if padding_offset == 0 {
+            self.permutation(&[]);
+        }
+
+        // Copy out since the state will be updated in place
+        self.loader.dup_scalar(&self.loader.scalar(Value::Memory(self.state_ptr + 0x20)))
+    }
+
+    fn permutation(&mut self, inputs: &[Scalar]) {
+        assert!(inputs.len() < T);
+        let scalar_modulus = self.loader.scalar_modulus();
+
+        // Adding inputs and padding with the first constants is generated
+        // inline since it depends on number of inputs.
+        let pre_constants = &self.spec.constants().start()[0];
+        for (idx, constant) in pre_constants.iter().enumerate() {
+            let ptr = self.state_ptr + idx * 0x20;
+            let constant = if idx == inputs.len() + 1 { F::one() + constant } else { *constant };
+            self.loader
+                .code_mut()
+                .push(scalar_modulus)
+                .push(fe_to_u256(constant))
+                .push(ptr)
+                .mload()
+                .addmod();
+            if (1..=inputs.len()).contains(&idx) {
+                self.loader.code_mut().push(scalar_modulus).swap(1);
+                self.loader.push(&inputs[idx - 1]);
+                self.loader.code_mut().addmod();
+            }
+            self.loader.code_mut().push(ptr).mstore();
+        }
+
+        let identifier = format!(
+            "poseidon_{}_{}_{}_{:#x}",
+            T,
+            self.spec.r_f(),
+            self.spec.constants().partial().len(),
+            self.state_ptr
+        );
+        let (spec, state_ptr) = (&self.spec, self.state_ptr);
+        self.loader.call_subroutine(&identifier, |code| {
+            Self::rounds(code, spec, scalar_modulus, state_ptr)
+        });
+    }
+
+    fn rounds(code: &mut Code, spec: &Spec<F, T, RATE>, scalar_modulus: U256, state_ptr: usize) {
+        let sbox_full = |code: &mut Code, constants: &[F; T]| {
+            for (idx, constant) in constants.iter().enumerate() {
+                sbox(code, scalar_modulus, state_ptr + idx * 0x20, fe_to_u256(*constant));
+            }
+        };
+        let apply =
+            |code: &mut Code, rows: &[Vec<U256>]| mix(code, scalar_modulus, state_ptr, rows);
+
+        let r_f = spec.r_f() / 2;
+        let mds = matrix(spec.mds_matrices().mds().rows());
+
+        let constants = spec.constants().start();
+        for constants in constants.iter().skip(1).take(r_f - 1) {
+            sbox_full(code, constants);
+            apply(code, &mds);
+        }
+
+        sbox_full(code, constants.last().unwrap());
+        apply(code, &matrix(spec.mds_matrices().pre_sparse_mds().rows()));
+
+        let sparse_matrices = spec.mds_matrices().sparse_matrices();
+        for (constant, sparse_mds) in spec.constants().partial().iter().zip(sparse_matrices.iter())
+        {
+            sbox(code, scalar_modulus, state_ptr, fe_to_u256(*constant));
+            apply(code, &sparse_rows(sparse_mds.row(), sparse_mds.col_hat()));
+        }
+
+        for constants in spec.constants().end().iter() {
+            sbox_full(code, constants);
+            apply(code, &mds);
+        }
+        sbox_full(code, &[F::zero(); T]);
+        apply(code, &mds);
+    }
+}
+
+impl<F, const T: usize, const RATE: usize> TranscriptHash<F, Scalar> for PoseidonSponge<F, T, RATE>
+where
+    F: PrimeField<Repr = [u8; 0x20]> + FieldExt,
+{
+    fn update(&mut self, elements: &[Scalar]) {
+        PoseidonSponge::update(self, elements)
+    }
+
+    fn squeeze(&mut self) -> Scalar {
+        PoseidonSponge::squeeze(self)
+    }
+}
+
+fn matrix<F: PrimeField<Repr = [u8; 0x20]>, const T: usize>(rows: [[F; T]; T]) -> Vec<Vec<U256>> {
+    rows.iter().map(|row| row.iter().cloned().map(fe_to_u256).collect()).collect()
+}
+
+/// Returns rows of a sparse matrix, which are `row` and `col_hat[i - 1] * e_0 + e_i`.
+fn sparse_rows<F: PrimeField<Repr = [u8; 0x20]>>(row: &[F], col_hat: &[F]) -> Vec<Vec<U256>> {
+    let mut rows = vec![row.iter().cloned().map(fe_to_u256).collect_vec()];
+    for (idx, coeff) in col_hat.iter().enumerate() {
+        let mut row = vec![U256::zero(); row.len()];
+        row[0] = fe_to_u256(*coeff);
+        row[idx + 1] = U256::one();
+        rows.push(row);
+    }
+    rows
+}
+
+/// Updates `x` at `ptr` to `x^5 + constant`.
+fn sbox(code: &mut Code, scalar_modulus: U256, ptr: usize, constant: U256) {
+    code.push(ptr)
+        .mload()
+        // [..., x]
+        .push(scalar_modulus)
+        .dup(1)
+        .dup(0)
+        .mulmod()
+        // [..., x, x^2]
+        .push(scalar_modulus)
+        .swap(1)
+        .dup(0)
+        .mulmod()
+        // [..., x, x^4]
+        .push(scalar_modulus)
+        .swap(2)
+        .mulmod();
+    // [..., x^5]
+    if !constant.is_zero() {
+        code.push(scalar_modulus).swap(1).push(constant).addmod();
+    }
+    code.push(ptr).mstore();
+}
+
+/// Updates state at `state_ptr` to `rows * state`, where coefficients `0` and
+/// `1` are specialized since the sparse matrices are mostly them.
+fn mix(code: &mut Code, scalar_modulus: U256, state_ptr: usize, rows: &[Vec<U256>]) {
+    for row in rows {
+        let mut terms = row.iter().enumerate().filter(|(_, coeff)| !coeff.is_zero());
+        let push_term = |code: &mut Code, idx: usize, coeff: &U256| {
+            if *coeff == U256::one() {
+                code.push(state_ptr + idx * 0x20).mload();
+            } else {
+                code.push(scalar_modulus)
+                    .push(*coeff)
+                    .push(state_ptr + idx * 0x20)
+                    .mload()
+                    .mulmod();
+            }
+        };
+        let (idx, coeff) = terms.next().unwrap();
+        push_term(code, idx, coeff);
+        // [..., acc]
+        for (idx, coeff) in terms {
+            code.push(scalar_modulus).swap(1);
+            push_term(code, idx, coeff);
+            // [..., r, acc, term]
+            code.addmod();
+        }
+    }
+    // [..., new_s_0, ..., new_s_t-1]
+    for idx in (0..rows.len()).rev() {
+        code.push(state_ptr + idx * 0x20).mstore();
+    }
+}
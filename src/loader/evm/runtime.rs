@@ -0,0 +1,152 @@
+//! Minimal local EVM to deploy and call generated verifiers, backed by `revm`.
+
+use crate::util::Itertools;
+use ethereum_types::{Address, U256};
+use revm::{
+    primitives::{
+        AccountInfo, Address as RevmAddress, Bytecode, CreateScheme, ExecutionResult, Output,
+        TransactTo, TxEnv, U256 as RevmU256,
+    },
+    InMemoryDB, EVM,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<U256>,
+    pub data: Vec<u8>,
+}
+
+/// Outcome of a deployment or a call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionOutput {
+    pub success: bool,
+    /// Gas used by the transaction, including intrinsic gas and calldata.
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    pub return_data: Vec<u8>,
+}
+
+impl ExecutionOutput {
+    fn failed(gas_used: u64, return_data: Vec<u8>) -> Self {
+        Self { success: false, gas_used, logs: Vec::new(), return_data }
+    }
+}
+
+pub struct Runtime {
+    evm: EVM<InMemoryDB>,
+    caller: Address,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        let mut evm = EVM::new();
+        evm.database(InMemoryDB::default());
+        Self { evm, caller: Address::from_low_u64_be(0xfe) }
+    }
+
+    /// Deploys `deployment_code` (e.g. from [`EvmLoader::deployment_code`]),
+    /// returns the address of created contract and the deployment output.
+    ///
+    /// [`EvmLoader::deployment_code`]: crate::loader::evm::EvmLoader::deployment_code
+    pub fn deploy(&mut self, deployment_code: Vec<u8>) -> (Option<Address>, ExecutionOutput) {
+        let (address, output) =
+            self.transact(TransactTo::Create(CreateScheme::Create), deployment_code);
+        (address.filter(|_| output.success), output)
+    }
+
+    /// Inserts `runtime_code` at `address` directly without running any
+    /// deployment code.
+    pub fn insert(&mut self, address: Address, runtime_code: Vec<u8>) {
+        let code = Bytecode::new_raw(runtime_code.into());
+        let info = AccountInfo::new(RevmU256::ZERO, 1, code.hash_slow(), code);
+        self.evm.db().unwrap().insert_account_info(RevmAddress::from(address.0), info);
+    }
+
+    pub fn call(&mut self, address: Address, calldata: Vec<u8>) -> ExecutionOutput {
+        self.transact(TransactTo::Call(RevmAddress::from(address.0)), calldata).1
+    }
+
+    fn transact(
+        &mut self,
+        transact_to: TransactTo,
+        data: Vec<u8>,
+    ) -> (Option<Address>, ExecutionOutput) {
+        self.evm.env.tx = TxEnv {
+            caller: RevmAddress::from(self.caller.0),
+            gas_limit: u64::MAX,
+            transact_to,
+            data: data.into(),
+            ..Default::default()
+        };
+
+        // Invalid transaction or environment fails without executing anything
+        let result = match self.evm.transact_commit() {
+            Ok(result) => result,
+            Err(_) => return (None, ExecutionOutput::failed(0, Vec::new())),
+        };
+        match result {
+            ExecutionResult::Success { gas_used, logs, output, .. } => {
+                let logs = logs
+                    .into_iter()
+                    .map(|log| Log {
+                        address: Address::from_slice(log.address.as_slice()),
+                        topics: log
+                            .topics
+                            .iter()
+                            .map(|topic| U256::from_big_endian(topic.as_slice()))
+                            .collect_vec(),
+                        data: log.data.to_vec(),
+                    })
+                    .collect();
+                let (address, return_data) = match output {
+                    Output::Call(return_data) => (None, return_data),
+                    Output::Create(return_data, address) => (
+                        address.map(|address| Address::from_slice(address.as_slice())),
+                        return_data,
+                    ),
+                };
+                let output = ExecutionOutput {
+                    success: true,
+                    gas_used,
+                    logs,
+                    return_data: return_data.to_vec(),
+                };
+                (address, output)
+            }
+            ExecutionResult::Revert { gas_used, output } => {
+                (None, ExecutionOutput::failed(gas_used, output.to_vec()))
+            }
+            ExecutionResult::Halt { gas_used, .. } => {
+                (None, ExecutionOutput::failed(gas_used, Vec::new()))
+            }
+        }
+    }
+}
+
+/// Deploys `deployment_code` on a fresh [`Runtime`] and calls it with
+/// `calldata`, returns the output of the call, or the output of deployment if
+/// it fails.
+pub fn deploy_and_call(deployment_code: Vec<u8>, calldata: Vec<u8>) -> ExecutionOutput {
+    let mut runtime = Runtime::new();
+    let (address, output) = runtime.deploy(deployment_code);
+    match address {
+        Some(address) => runtime.call(address, calldata),
+        None => output,
+    }
+}
+
+/// Calls `runtime_code` with `calldata` on a fresh [`Runtime`] without
+/// deploying it first, which also skips the contract size limit.
+pub fn call(runtime_code: Vec<u8>, calldata: Vec<u8>) -> ExecutionOutput {
+    let mut runtime = Runtime::new();
+    let address = Address::from_low_u64_be(0xff);
+    runtime.insert(address, runtime_code);
+    runtime.call(address, calldata)
+}
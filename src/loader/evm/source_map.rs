@@ -0,0 +1,252 @@
+//! Annotated disassembly of generated verifiers.
+
+use crate::{
+    loader::evm::{code::*, EvmLoader},
+    util::Itertools,
+};
+use ethereum_types::U256;
+use std::{fmt::Write, ops::Range, rc::Rc};
+
+/// Verifier step which generated a range of runtime code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Annotation {
+    pub label: String,
+    /// Number of annotations enclosing this one.
+    pub depth: usize,
+    pub range: Range<usize>,
+}
+
+/// Annotations of runtime code, ordered by the start of range and outer first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    annotations: Vec<Annotation>,
+}
+
+impl SourceMap {
+    pub(crate) fn new(annotations: &[(String, Range<usize>)]) -> Self {
+        let annotations = annotations
+            .iter()
+            .sorted_by_key(|(_, range)| (range.start, usize::MAX - range.end))
+            .cloned()
+            .collect_vec();
+        let mut stack: Vec<Range<usize>> = Vec::new();
+        let annotations = annotations
+            .into_iter()
+            .map(|(label, range)| {
+                while let Some(outer) = stack.last() {
+                    if range.start < outer.end && range.end <= outer.end {
+                        break;
+                    }
+                    stack.pop();
+                }
+                let depth = stack.len();
+                stack.push(range.clone());
+                Annotation { label, depth, range }
+            })
+            .collect();
+        Self { annotations }
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// Returns annotations covering `pc`, outer first.
+    pub fn lookup(&self, pc: usize) -> Vec<&Annotation> {
+        self.annotations.iter().filter(|annotation| annotation.range.contains(&pc)).collect()
+    }
+
+    /// Returns the innermost step covering `pc` with its enclosing steps, e.g.
+    /// `msm / msm term 3`.
+    pub fn describe(&self, pc: usize) -> Option<String> {
+        let annotations = self.lookup(pc);
+        (!annotations.is_empty())
+            .then(|| annotations.iter().map(|annotation| annotation.label.as_str()).join(" / "))
+    }
+
+    /// Returns disassembly of `code` with annotations as comments before the
+    /// first instruction of each step.
+    pub fn listing(&self, code: &[u8]) -> String {
+        let mut annotations = self.annotations.iter().peekable();
+        let mut listing = String::new();
+        for instruction in disassemble(code) {
+            while let Some(annotation) =
+                annotations.next_if(|annotation| annotation.range.start <= instruction.pc)
+            {
+                if annotation.range.is_empty() {
+                    continue;
+                }
+                writeln!(
+                    listing,
+                    "{:8}{}; {}",
+                    "",
+                    "  ".repeat(annotation.depth),
+                    annotation.label
+                )
+                .unwrap();
+            }
+            let depth =
+                self.lookup(instruction.pc).last().map_or(0, |annotation| annotation.depth + 1);
+            writeln!(listing, "{:#06x}  {}{}", instruction.pc, "  ".repeat(depth), instruction)
+                .unwrap();
+        }
+        listing
+    }
+}
+
+/// Decoded instruction, with immediate value if it's a `PUSH`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub pc: usize,
+    pub opcode: u8,
+    pub immediate: Option<U256>,
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match mnemonic(self.opcode) {
+            Some(mnemonic) => write!(f, "{}", mnemonic)?,
+            None => write!(f, "INVALID({:#04x})", self.opcode)?,
+        }
+        if let Some(immediate) = self.immediate {
+            write!(f, " {:#x}", immediate)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes `code` into instructions, truncated `PUSH` at the end is padded
+/// with zeros like the EVM does.
+pub fn disassemble(code: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        let immediate = (PUSH1..=PUSH32).contains(&opcode).then(|| {
+            let len = (opcode - PUSH1 + 1) as usize;
+            let mut bytes = code[pc + 1..(pc + 1 + len).min(code.len())].to_vec();
+            bytes.resize(len, 0);
+            U256::from_big_endian(&bytes)
+        });
+        instructions.push(Instruction { pc, opcode, immediate });
+        pc += 1 + immediate.map_or(0, |_| (opcode - PUSH1 + 1) as usize);
+    }
+    instructions
+}
+
+/// Returns program counter of the failed check from revert data of verifier
+/// generated with [`EvmLoader::set_fail_fast`].
+pub fn revert_pc(return_data: &[u8]) -> Option<usize> {
+    (return_data.len() == 0x20).then(|| U256::from_big_endian(return_data).low_u64() as usize)
+}
+
+impl EvmLoader {
+    /// Returns source map of [`EvmLoader::runtime_code`].
+    pub fn source_map(self: &Rc<Self>) -> SourceMap {
+        SourceMap::new(self.code_mut().annotations())
+    }
+}
+
+fn mnemonic(opcode: u8) -> Option<&'static str> {
+    const PUSH: [&str; 32] = [
+        "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9", "PUSH10",
+        "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18", "PUSH19",
+        "PUSH20", "PUSH21", "PUSH22", "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28",
+        "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+    ];
+    const DUP: [&str; 16] = [
+        "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11",
+        "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+    ];
+    const SWAP: [&str; 16] = [
+        "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10",
+        "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+    ];
+
+    Some(match opcode {
+        STOP => "STOP",
+        ADD => "ADD",
+        MUL => "MUL",
+        SUB => "SUB",
+        DIV => "DIV",
+        SDIV => "SDIV",
+        MOD => "MOD",
+        SMOD => "SMOD",
+        ADDMOD => "ADDMOD",
+        MULMOD => "MULMOD",
+        EXP => "EXP",
+        SIGNEXTEND => "SIGNEXTEND",
+        LT => "LT",
+        GT => "GT",
+        SLT => "SLT",
+        SGT => "SGT",
+        EQ => "EQ",
+        ISZERO => "ISZERO",
+        AND => "AND",
+        OR => "OR",
+        XOR => "XOR",
+        NOT => "NOT",
+        BYTE => "BYTE",
+        SHL => "SHL",
+        SHR => "SHR",
+        SAR => "SAR",
+        SHA3 => "SHA3",
+        ADDRESS => "ADDRESS",
+        BALANCE => "BALANCE",
+        ORIGIN => "ORIGIN",
+        CALLER => "CALLER",
+        CALLVALUE => "CALLVALUE",
+        CALLDATALOAD => "CALLDATALOAD",
+        CALLDATASIZE => "CALLDATASIZE",
+        CALLDATACOPY => "CALLDATACOPY",
+        CODESIZE => "CODESIZE",
+        CODECOPY => "CODECOPY",
+        GASPRICE => "GASPRICE",
+        EXTCODESIZE => "EXTCODESIZE",
+        EXTCODECOPY => "EXTCODECOPY",
+        RETURNDATASIZE => "RETURNDATASIZE",
+        RETURNDATACOPY => "RETURNDATACOPY",
+        EXTCODEHASH => "EXTCODEHASH",
+        BLOCKHASH => "BLOCKHASH",
+        COINBASE => "COINBASE",
+        TIMESTAMP => "TIMESTAMP",
+        NUMBER => "NUMBER",
+        DIFFICULTY => "DIFFICULTY",
+        GASLIMIT => "GASLIMIT",
+        CHAINID => "CHAINID",
+        SELFBALANCE => "SELFBALANCE",
+        BASEFEE => "BASEFEE",
+        POP => "POP",
+        MLOAD => "MLOAD",
+        MSTORE => "MSTORE",
+        MSTORE8 => "MSTORE8",
+        SLOAD => "SLOAD",
+        SSTORE => "SSTORE",
+        JUMP => "JUMP",
+        JUMPI => "JUMPI",
+        PC => "PC",
+        MSIZE => "MSIZE",
+        GAS => "GAS",
+        JUMPDEST => "JUMPDEST",
+        MCOPY => "MCOPY",
+        PUSH0 => "PUSH0",
+        PUSH1..=PUSH32 => PUSH[(opcode - PUSH1) as usize],
+        DUP1..=DUP16 => DUP[(opcode - DUP1) as usize],
+        SWAP1..=SWAP16 => SWAP[(opcode - SWAP1) as usize],
+        LOG0 => "LOG0",
+        LOG1 => "LOG1",
+        LOG2 => "LOG2",
+        LOG3 => "LOG3",
+        LOG4 => "LOG4",
+        CREATE => "CREATE",
+        CALL => "CALL",
+        CALLCODE => "CALLCODE",
+        RETURN => "RETURN",
+        DELEGATECALL => "DELEGATECALL",
+        CREATE2 => "CREATE2",
+        STATICCALL => "STATICCALL",
+        REVERT => "REVERT",
+        SELFDESTRUCT => "SELFDESTRUCT",
+        _ => return None,
+    })
+}
@@ -0,0 +1,161 @@
+use crate::{loader::evm::code::Precompiled, Error};
+
+/// Addresses of precompiles used by generated verifiers, `None` if the target
+/// doesn't have it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Precompiles {
+    pub sha256: Option<usize>,
+    pub big_mod_exp: Option<usize>,
+    pub bn254_add: Option<usize>,
+    pub bn254_scalar_mul: Option<usize>,
+    pub bn254_pairing: Option<usize>,
+}
+
+impl Precompiles {
+    pub(crate) fn address(&self, precompile: Precompiled) -> Option<usize> {
+        match precompile {
+            Precompiled::Sha256 => self.sha256,
+            Precompiled::BigModExp => self.big_mod_exp,
+            Precompiled::Bn254Add => self.bn254_add,
+            Precompiled::Bn254ScalarMul => self.bn254_scalar_mul,
+            Precompiled::Bn254Pairing => self.bn254_pairing,
+        }
+    }
+}
+
+impl Default for Precompiles {
+    fn default() -> Self {
+        Self {
+            sha256: Some(0x02),
+            big_mod_exp: Some(0x05),
+            bn254_add: Some(0x06),
+            bn254_scalar_mul: Some(0x07),
+            bn254_pairing: Some(0x08),
+        }
+    }
+}
+
+/// Gas prices that differ between targets, only used for estimation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasSchedule {
+    pub intrinsic: u64,
+    pub calldata_zero_byte: u64,
+    pub calldata_non_zero_byte: u64,
+    /// Cost of `STATICCALL` to a warm address (EIP-2929).
+    pub warm_call: u64,
+    pub sha256_base: u64,
+    pub sha256_per_word: u64,
+    /// Minimal cost of `BigModExp`, the rest is priced by EIP-2565.
+    pub big_mod_exp_min: u64,
+    pub bn254_add: u64,
+    pub bn254_scalar_mul: u64,
+    pub bn254_pairing_base: u64,
+    pub bn254_pairing_per_pair: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self {
+            intrinsic: 21000,
+            calldata_zero_byte: 4,
+            calldata_non_zero_byte: 16,
+            warm_call: 100,
+            sha256_base: 60,
+            sha256_per_word: 12,
+            big_mod_exp_min: 200,
+            bn254_add: 150,
+            bn254_scalar_mul: 6000,
+            bn254_pairing_base: 45000,
+            bn254_pairing_per_pair: 34000,
+        }
+    }
+}
+
+/// Description of the chain a verifier is generated for.
+///
+/// Code generation depends on it to choose strategies, e.g. `MCOPY` is used to
+/// copy points when available, and `BigModExp` is replaced by unrolled
+/// square-and-multiply with `MULMOD` when the precompile is missing. Other
+/// precompiles have no bytecode fallback, so a target without them is rejected
+/// by [`EvmTarget::validate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EvmTarget {
+    pub precompiles: Precompiles,
+    pub gas_schedule: GasSchedule,
+    /// Maximal size of runtime code, `None` for unlimited.
+    pub code_size_limit: Option<usize>,
+    /// Availability of `PUSH0` (EIP-3855).
+    pub push0: bool,
+    /// Availability of `MCOPY` (EIP-5656).
+    pub mcopy: bool,
+}
+
+impl Default for EvmTarget {
+    fn default() -> Self {
+        Self::london()
+    }
+}
+
+impl EvmTarget {
+    /// Ethereum mainnet before Shanghai, which is supported by most of chains.
+    pub fn london() -> Self {
+        Self {
+            precompiles: Precompiles::default(),
+            gas_schedule: GasSchedule::default(),
+            code_size_limit: Some(0x6000),
+            push0: false,
+            mcopy: false,
+        }
+    }
+
+    /// Ethereum mainnet since Cancun.
+    pub fn cancun() -> Self {
+        Self { push0: true, mcopy: true, ..Self::london() }
+    }
+
+    pub fn with_precompiles(mut self, precompiles: Precompiles) -> Self {
+        self.precompiles = precompiles;
+        self
+    }
+
+    pub fn with_gas_schedule(mut self, gas_schedule: GasSchedule) -> Self {
+        self.gas_schedule = gas_schedule;
+        self
+    }
+
+    pub fn with_code_size_limit(mut self, code_size_limit: Option<usize>) -> Self {
+        self.code_size_limit = code_size_limit;
+        self
+    }
+
+    pub fn set_push0(mut self, push0: bool) -> Self {
+        self.push0 = push0;
+        self
+    }
+
+    pub fn set_mcopy(mut self, mcopy: bool) -> Self {
+        self.mcopy = mcopy;
+        self
+    }
+
+    /// Checks that every precompile without bytecode fallback is available,
+    /// which are all except `BigModExp`.
+    pub fn validate(&self) -> Result<(), Error> {
+        let missing = [
+            (Precompiled::Sha256, self.precompiles.sha256),
+            (Precompiled::Bn254Add, self.precompiles.bn254_add),
+            (Precompiled::Bn254ScalarMul, self.precompiles.bn254_scalar_mul),
+            (Precompiled::Bn254Pairing, self.precompiles.bn254_pairing),
+        ]
+        .into_iter()
+        .filter_map(|(precompile, address)| address.is_none().then_some(precompile))
+        .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(Error::AssertionFailure(format!(
+                "Precompiles {:?} without bytecode fallback are missing on target",
+                missing
+            )));
+        }
+        Ok(())
+    }
+}
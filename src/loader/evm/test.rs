@@ -24,6 +24,14 @@ pub fn execute(code: Vec<u8>, calldata: Vec<u8>) -> (bool, u64, Vec<u64>) {
     );
 
     let debug = debug();
+
+    #[cfg(feature = "evm_runtime")]
+    if !debug {
+        let output = crate::loader::evm::runtime::call(code, calldata);
+        let costs = output.logs.iter().map(|log| log.topics[0].as_u64()).collect_vec();
+        return (output.success, output.gas_used, costs);
+    }
+
     let caller = Address::from_low_u64_be(0xfe);
     let callee = Address::from_low_u64_be(0xff);
 
@@ -1,5 +1,6 @@
 use crate::{
     cost::Cost,
+    loader::evm::EvmTarget,
     util::{arithmetic::PrimeField, Itertools},
 };
 use ethereum_types::U256;
@@ -81,12 +82,24 @@ where
         .collect()
 }
 
+/// Rough estimation by counting commitments, evaluations and MSM only, see
+/// [`EvmLoader::estimate_gas`](crate::loader::evm::EvmLoader::estimate_gas)
+/// for an accurate one.
 pub fn estimate_gas(cost: Cost) -> usize {
+    estimate_gas_for_target(cost, &EvmTarget::default())
+}
+
+/// Same as [`estimate_gas`] but priced by the gas schedule of `target`.
+pub fn estimate_gas_for_target(cost: Cost, target: &EvmTarget) -> usize {
+    let schedule = &target.gas_schedule;
     let proof_size = cost.num_commitment * 64 + (cost.num_evaluation + cost.num_instance) * 32;
 
-    let intrinsic_cost = 21000;
-    let calldata_cost = (proof_size as f64 * 15.25).ceil() as usize;
-    let ec_operation_cost = 113100 + (cost.num_msm - 2) * 6350;
+    let intrinsic_cost = schedule.intrinsic as usize;
+    let calldata_cost =
+        (proof_size as f64 * 15.25 * schedule.calldata_non_zero_byte as f64 / 16.0).ceil() as usize;
+    let ec_operation_cost =
+        (schedule.bn254_pairing_base + 2 * schedule.bn254_pairing_per_pair + 100) as usize
+            + (cost.num_msm - 2) * (schedule.bn254_scalar_mul + schedule.bn254_add + 200) as usize;
 
     intrinsic_cost + calldata_cost + ec_operation_cost
 }
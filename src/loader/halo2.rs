@@ -1,10 +1,17 @@
 pub(crate) mod loader;
+pub mod bits;
+pub mod blake2b_chip;
+pub mod keccak_chip;
 pub mod poseidon_chip;
+pub mod recorder;
+pub mod sha256_chip;
 
 #[cfg(test)]
 pub(crate) mod test;
 
-pub use loader::{EcPoint, Halo2Loader, Scalar};
+pub use loader::{
+    CircuitStats, EcPoint, Halo2Loader, MsmConfig, MsmCost, MsmStrategy, Scalar, StageStats,
+};
 pub use util::Valuetools;
 
 mod util {
@@ -0,0 +1,185 @@
+use crate::{
+    loader::{LoadedScalar, ScalarLoader},
+    util::{arithmetic::PrimeField, Itertools},
+};
+use num_bigint::BigUint;
+
+/// Bit as [`LoadedScalar`] constrained to be `0` or `1`, or a constant which
+/// is folded in operations, since hash functions working on bits usually have
+/// plenty of constants (round constants, padding and initial state).
+#[derive(Clone, Debug)]
+pub enum Bit<L> {
+    Constant(bool),
+    Assigned(L),
+}
+
+impl<L: Clone> Bit<L> {
+    /// Returns `num_bits` little-endian constant bits of `value`.
+    pub fn from_u64(value: u64, num_bits: usize) -> Vec<Self> {
+        (0..num_bits).map(|idx| Bit::Constant(idx < 64 && (value >> idx) & 1 == 1)).collect()
+    }
+
+    /// Returns little-endian constant bits of `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Vec<Self> {
+        bytes.iter().flat_map(|byte| Self::from_u64(*byte as u64, 8)).collect()
+    }
+
+    pub fn constant(&self) -> Option<bool> {
+        match self {
+            Bit::Constant(constant) => Some(*constant),
+            Bit::Assigned(_) => None,
+        }
+    }
+
+    pub fn not<F: PrimeField>(&self) -> Self
+    where
+        L: LoadedScalar<F>,
+    {
+        match self {
+            Bit::Constant(constant) => Bit::Constant(!constant),
+            Bit::Assigned(bit) => Bit::Assigned(
+                bit.loader().sum_with_coeff_and_constant(&[(-F::one(), bit)], F::one()),
+            ),
+        }
+    }
+
+    pub fn and<F: PrimeField>(&self, other: &Self) -> Self
+    where
+        L: LoadedScalar<F>,
+    {
+        match (self, other) {
+            (Bit::Constant(false), _) | (_, Bit::Constant(false)) => Bit::Constant(false),
+            (Bit::Constant(true), bit) | (bit, Bit::Constant(true)) => bit.clone(),
+            (Bit::Assigned(lhs), Bit::Assigned(rhs)) => Bit::Assigned(lhs.clone() * rhs),
+        }
+    }
+
+    /// Returns `self ^ other` as `self + other - 2 * self * other`.
+    pub fn xor<F: PrimeField>(&self, other: &Self) -> Self
+    where
+        L: LoadedScalar<F>,
+    {
+        match (self, other) {
+            (Bit::Constant(false), bit) | (bit, Bit::Constant(false)) => bit.clone(),
+            (Bit::Constant(true), bit) | (bit, Bit::Constant(true)) => bit.not(),
+            (Bit::Assigned(lhs), Bit::Assigned(rhs)) => {
+                let loader = lhs.loader();
+                let one = loader.load_one();
+                Bit::Assigned(loader.sum_products_with_coeff_and_constant(
+                    &[(F::one(), lhs, &one), (F::one(), rhs, &one), (-F::from(2), lhs, rhs)],
+                    F::zero(),
+                ))
+            }
+        }
+    }
+
+    /// Returns `self ^ (!lhs & rhs)`, the non-linear step `χ` of Keccak.
+    pub fn xor_andn<F: PrimeField>(&self, lhs: &Self, rhs: &Self) -> Self
+    where
+        L: LoadedScalar<F>,
+    {
+        match (lhs, rhs) {
+            (Bit::Assigned(lhs), Bit::Assigned(rhs)) => {
+                let loader = lhs.loader();
+                let one = loader.load_one();
+                let andn = loader.sum_products_with_coeff_and_constant(
+                    &[(F::one(), rhs, &one), (-F::one(), lhs, rhs)],
+                    F::zero(),
+                );
+                self.xor(&Bit::Assigned(andn))
+            }
+            _ => self.xor(&lhs.not().and(rhs)),
+        }
+    }
+}
+
+/// Returns `Σ 2^i * bits[i]` in the scalar field, which is reduced by the
+/// modulus if `bits` is longer than `F::NUM_BITS`.
+pub fn compose<F, L>(loader: &L::Loader, bits: &[Bit<L>]) -> L
+where
+    F: PrimeField,
+    L: LoadedScalar<F>,
+{
+    let powers_of_two = std::iter::successors(Some(F::one()), |power| Some(power.double()))
+        .take(bits.len())
+        .collect_vec();
+    let constant = bits
+        .iter()
+        .zip(powers_of_two.iter())
+        .filter(|(bit, _)| bit.constant() == Some(true))
+        .fold(F::zero(), |acc, (_, power)| acc + power);
+    let assigned = bits
+        .iter()
+        .zip(powers_of_two.iter())
+        .filter_map(|(bit, power)| match bit {
+            Bit::Assigned(bit) => Some((*power, bit)),
+            Bit::Constant(_) => None,
+        })
+        .collect_vec();
+    if assigned.is_empty() {
+        return loader.load_const(&constant);
+    }
+    loader.sum_with_coeff_and_constant(&assigned, constant)
+}
+
+/// Constrains little-endian `bits` to be less than `bound`, which is needed
+/// for a decomposition to be canonical.
+pub fn assert_less_than<F, L>(loader: &L::Loader, bits: &[Bit<L>], bound: &BigUint)
+where
+    F: PrimeField,
+    L: LoadedScalar<F>,
+{
+    assert!(bound.bits() as usize <= bits.len());
+
+    // Scan from the most significant bit, `is_less` and `is_equal` of prefixes
+    // are exclusive so their disjunction could be done by xor.
+    let mut is_less = Bit::Constant(false);
+    let mut is_equal = Bit::Constant(true);
+    for (idx, bit) in bits.iter().enumerate().rev() {
+        if bound.bit(idx as u64) {
+            is_less = is_less.xor(&is_equal.and(&bit.not()));
+            is_equal = is_equal.and(bit);
+        } else {
+            is_equal = is_equal.and(&bit.not());
+        }
+    }
+
+    match is_less {
+        Bit::Constant(is_less) => assert!(is_less),
+        Bit::Assigned(is_less) => loader
+            .assert_eq("bits should be less than bound", &is_less, &loader.load_one())
+            .unwrap(),
+    }
+}
+
+/// Returns little-endian `lhs + rhs` modulo `2^n` where `n` is the length of
+/// both by ripple-carry.
+pub fn add<F, L>(lhs: &[Bit<L>], rhs: &[Bit<L>]) -> Vec<Bit<L>>
+where
+    F: PrimeField,
+    L: LoadedScalar<F>,
+{
+    assert_eq!(lhs.len(), rhs.len());
+
+    let mut carry = Bit::Constant(false);
+    lhs.iter()
+        .zip(rhs.iter())
+        .map(|(lhs, rhs)| {
+            let xor = lhs.xor(rhs);
+            let sum = xor.xor(&carry);
+            // Both products can't be 1 at the same time, so the carry is just
+            // their sum.
+            let next_carry = match (lhs, rhs, &carry, &xor) {
+                (
+                    Bit::Assigned(lhs),
+                    Bit::Assigned(rhs),
+                    Bit::Assigned(carry),
+                    Bit::Assigned(xor),
+                ) => Bit::Assigned(lhs.loader().sum_products(&[(lhs, rhs), (carry, xor)])),
+                _ => lhs.and(rhs).xor(&carry.and(&xor)),
+            };
+            carry = next_carry;
+            sum
+        })
+        .collect()
+}
@@ -0,0 +1,140 @@
+use crate::{
+    loader::{
+        halo2::bits::{self, Bit},
+        LoadedScalar,
+    },
+    util::{arithmetic::PrimeField, Itertools},
+};
+use std::marker::PhantomData;
+
+const BLOCK: usize = 1024;
+const ROUNDS: usize = 12;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+type Word<L> = Vec<Bit<L>>;
+
+fn rotate_right<L: Clone>(word: &[Bit<L>], offset: usize) -> Word<L> {
+    (0..64).map(|idx| word[(idx + offset) % 64].clone()).collect()
+}
+
+fn xor<F: PrimeField, L: LoadedScalar<F>>(lhs: &[Bit<L>], rhs: &[Bit<L>]) -> Word<L> {
+    lhs.iter().zip(rhs.iter()).map(|(lhs, rhs)| lhs.xor(rhs)).collect()
+}
+
+/// Blake2b with 64 bytes output over bits, as used by halo2's `Blake2bRead`
+/// and `Blake2bWrite`.
+///
+/// Message and digest are bytes in order, each byte as 8 little-endian bits,
+/// so a word is exactly the little-endian bits of 8 bytes. Like
+/// `blake2b_simd::State`, squeezing doesn't reset the state, so it can be
+/// updated and squeezed again without hashing from the start.
+pub struct Blake2bChip<F: PrimeField, L: LoadedScalar<F>> {
+    h: Vec<Word<L>>,
+    buf: Vec<Bit<L>>,
+    /// Number of bytes compressed into `h`.
+    counter: u128,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, L: LoadedScalar<F>> Blake2bChip<F, L> {
+    pub fn new(personalization: &[u8; 16]) -> Self {
+        let mut h = IV;
+        // Parameter block with digest length 64, fanout 1 and depth 1
+        h[0] ^= 0x01010040;
+        h[6] ^= u64::from_le_bytes(personalization[..8].try_into().unwrap());
+        h[7] ^= u64::from_le_bytes(personalization[8..].try_into().unwrap());
+        Self {
+            h: h.iter().map(|word| Bit::from_u64(*word, 64)).collect(),
+            buf: Vec::new(),
+            counter: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn update(&mut self, bits: &[Bit<L>]) {
+        assert_eq!(bits.len() % 8, 0);
+        self.buf.extend_from_slice(bits);
+        // Last block is kept until more bits come, since it needs to be
+        // compressed with the finalization flag if it's the last one.
+        while self.buf.len() > BLOCK {
+            let block = self.buf.drain(..BLOCK).collect_vec();
+            self.counter += (BLOCK / 8) as u128;
+            Self::compress(&mut self.h, &block, self.counter, false);
+        }
+    }
+
+    /// Returns 512 bits of digest of all bits updated so far.
+    pub fn squeeze(&self) -> Vec<Bit<L>> {
+        let mut h = self.h.clone();
+        let mut block = self.buf.clone();
+        block.resize(BLOCK, Bit::Constant(false));
+        let counter = self.counter + (self.buf.len() / 8) as u128;
+        Self::compress(&mut h, &block, counter, true);
+        h.concat()
+    }
+
+    fn compress(h: &mut [Word<L>], block: &[Bit<L>], counter: u128, is_last: bool) {
+        let m = block.chunks(64).map(<[_]>::to_vec).collect_vec();
+        let mut v =
+            h.iter().cloned().chain(IV.iter().map(|word| Bit::from_u64(*word, 64))).collect_vec();
+        v[12] = xor(&v[12], &Bit::from_u64(counter as u64, 64));
+        v[13] = xor(&v[13], &Bit::from_u64((counter >> 64) as u64, 64));
+        if is_last {
+            v[14] = v[14].iter().map(Bit::not).collect();
+        }
+
+        for round in 0..ROUNDS {
+            let s = &SIGMA[round % 10];
+            for (idx, [a, b, c, d]) in [
+                [0, 4, 8, 12],
+                [1, 5, 9, 13],
+                [2, 6, 10, 14],
+                [3, 7, 11, 15],
+                [0, 5, 10, 15],
+                [1, 6, 11, 12],
+                [2, 7, 8, 13],
+                [3, 4, 9, 14],
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let (x, y) = (&m[s[2 * idx]], &m[s[2 * idx + 1]]);
+                v[a] = bits::add(&bits::add(&v[a], &v[b]), x);
+                v[d] = rotate_right(&xor(&v[d], &v[a]), 32);
+                v[c] = bits::add(&v[c], &v[d]);
+                v[b] = rotate_right(&xor(&v[b], &v[c]), 24);
+                v[a] = bits::add(&bits::add(&v[a], &v[b]), y);
+                v[d] = rotate_right(&xor(&v[d], &v[a]), 16);
+                v[c] = bits::add(&v[c], &v[d]);
+                v[b] = rotate_right(&xor(&v[b], &v[c]), 63);
+            }
+        }
+
+        for (idx, h) in h.iter_mut().enumerate() {
+            *h = xor(&xor(h, &v[idx]), &v[idx + 8]);
+        }
+    }
+}
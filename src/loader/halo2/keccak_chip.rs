@@ -0,0 +1,157 @@
+use crate::{
+    loader::{halo2::bits::Bit, LoadedScalar},
+    util::{arithmetic::PrimeField, Itertools},
+};
+use std::marker::PhantomData;
+
+const RATE: usize = 1088;
+const ROUNDS: usize = 24;
+
+// Rotation offsets of lane `x + 5 * y`.
+const RHO: [u32; 25] =
+    [0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14];
+
+const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808A,
+    0x8000000080008000,
+    0x000000000000808B,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008A,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000A,
+    0x000000008000808B,
+    0x800000000000008B,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800A,
+    0x800000008000000A,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+type Lane<L> = Vec<Bit<L>>;
+
+fn rotate_left<L: Clone>(lane: &[Bit<L>], offset: u32) -> Lane<L> {
+    let offset = offset as usize % 64;
+    (0..64).map(|idx| lane[(idx + 64 - offset) % 64].clone()).collect()
+}
+
+/// Keccak256 over bits, as computed by `SHA3` opcode.
+///
+/// Message and digest are bytes in order, each byte as 8 little-endian bits,
+/// so a lane of state is exactly the little-endian bits of 8 bytes.
+pub struct KeccakChip<F: PrimeField, L: LoadedScalar<F>> {
+    absorbing: Vec<Bit<L>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, L: LoadedScalar<F>> Default for KeccakChip<F, L> {
+    fn default() -> Self {
+        Self { absorbing: Vec::new(), _marker: PhantomData }
+    }
+}
+
+impl<F: PrimeField, L: LoadedScalar<F>> KeccakChip<F, L> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, bits: &[Bit<L>]) {
+        assert_eq!(bits.len() % 8, 0);
+        self.absorbing.extend_from_slice(bits);
+    }
+
+    /// Returns 256 bits of keccak256 of absorbed bits, then resets.
+    pub fn squeeze(&mut self) -> Vec<Bit<L>> {
+        let mut input = std::mem::take(&mut self.absorbing);
+
+        // Padding `0x01 || 0x00* || 0x80`, which collapses into `0x81` when
+        // there is only one byte left in the block.
+        input.extend(Bit::from_u64(0x01, 8));
+        while input.len() % RATE != 0 {
+            input.extend(Bit::from_u64(0, 8));
+        }
+        let last = input.len() - 1;
+        input[last] = Bit::Constant(true);
+
+        let mut state = (0..25).map(|_| Bit::from_u64(0, 64)).collect_vec();
+        for block in input.chunks(RATE) {
+            for (lane, chunk) in state.iter_mut().zip(block.chunks(64)) {
+                *lane = lane.iter().zip(chunk.iter()).map(|(lhs, rhs)| lhs.xor(rhs)).collect();
+            }
+            Self::permutation(&mut state);
+        }
+
+        state[..4].concat()
+    }
+
+    fn permutation(state: &mut [Lane<L>]) {
+        for round_constant in ROUND_CONSTANTS {
+            // θ
+            let parities = (0..5)
+                .map(|x| {
+                    (1..5).fold(state[x].clone(), |acc, y| {
+                        acc.iter()
+                            .zip(state[x + 5 * y].iter())
+                            .map(|(lhs, rhs)| lhs.xor(rhs))
+                            .collect()
+                    })
+                })
+                .collect_vec();
+            for x in 0..5 {
+                let rotated = rotate_left(&parities[(x + 1) % 5], 1);
+                let d = parities[(x + 4) % 5]
+                    .iter()
+                    .zip(rotated.iter())
+                    .map(|(lhs, rhs)| lhs.xor(rhs))
+                    .collect_vec();
+                for y in 0..5 {
+                    state[x + 5 * y] = state[x + 5 * y]
+                        .iter()
+                        .zip(d.iter())
+                        .map(|(lhs, rhs)| lhs.xor(rhs))
+                        .collect();
+                }
+            }
+
+            // ρ and π
+            let mut permuted = vec![Vec::new(); 25];
+            for x in 0..5 {
+                for y in 0..5 {
+                    permuted[y + 5 * ((2 * x + 3 * y) % 5)] =
+                        rotate_left(&state[x + 5 * y], RHO[x + 5 * y]);
+                }
+            }
+
+            // χ
+            for x in 0..5 {
+                for y in 0..5 {
+                    state[x + 5 * y] = (0..64)
+                        .map(|idx| {
+                            permuted[x + 5 * y][idx].xor_andn(
+                                &permuted[(x + 1) % 5 + 5 * y][idx],
+                                &permuted[(x + 2) % 5 + 5 * y][idx],
+                            )
+                        })
+                        .collect();
+                }
+            }
+
+            // ι
+            state[0] = state[0]
+                .iter()
+                .zip(Bit::from_u64(round_constant, 64).iter())
+                .map(|(lhs, rhs)| lhs.xor(rhs))
+                .collect();
+        }
+    }
+}
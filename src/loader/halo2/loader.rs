@@ -1,7 +1,16 @@
 use crate::{
-    loader::{EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader},
+    loader::{
+        halo2::{
+            bits::{self, Bit},
+            recorder::{CellMap, RecordedEcPoint, RecordedInteger, RecordedValue},
+        },
+        EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader,
+    },
     util::{
-        arithmetic::{Curve, CurveAffine, Field, FieldOps, PrimeCurveAffine, PrimeField},
+        arithmetic::{
+            modulus, Curve, CurveAffine, Field, FieldOps, LimbsDescriptor, PrimeCurveAffine,
+            PrimeField,
+        },
         Itertools,
     },
 };
@@ -14,14 +23,16 @@ use halo2_base::{
 };
 use halo2_ecc::{
     bigint::{CRTInteger, OverflowInteger},
-    ecc::{fixed::FixedEccPoint, EccChip, EccPoint},
+    ecc::{fixed::FixedEccPoint, pippenger, EccChip, EccPoint},
     fields::{fp::FpConfig, FieldChip},
 };
 use halo2_proofs::circuit;
 use num_bigint::{BigInt, BigUint};
+use serde::{Deserialize, Serialize};
 use std::{
     cell::RefCell,
     fmt::{self, Debug},
+    iter::Sum,
     ops::{Add, AddAssign, Deref, DerefMut, Mul, MulAssign, Neg, Sub, SubAssign},
     rc::Rc,
 };
@@ -38,17 +49,161 @@ pub enum Value<T, L> {
     Assigned(L),
 }
 
+/// Algorithm of variable-base MSM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MsmStrategy {
+    /// Straus' algorithm, which shares doublings across all bases.
+    Straus,
+    /// Pippenger's bucket method, which is cheaper when there are many bases.
+    Pippenger,
+}
+
+/// Configuration of in-circuit MSM, which only affects witness layout so it
+/// could be tuned without changing column configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsmConfig {
+    pub strategy: MsmStrategy,
+    /// Window bits of Straus, or clump factor of Pippenger whose radix is
+    /// derived from number of bases.
+    pub window_bits: usize,
+    /// Window bits of fixed-base scalar multiplication.
+    pub fixed_window_bits: usize,
+    /// Whether to multiply all constant bases in a single fixed-base MSM,
+    /// instead of one fixed-base scalar multiplication for each of them.
+    pub batch_fixed_base: bool,
+}
+
+impl Default for MsmConfig {
+    fn default() -> Self {
+        Self {
+            strategy: MsmStrategy::Straus,
+            window_bits: 4,
+            fixed_window_bits: 4,
+            batch_fixed_base: false,
+        }
+    }
+}
+
+/// Cells used by an in-circuit MSM.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsmCost {
+    pub num_bases: usize,
+    pub num_fixed_bases: usize,
+    pub advice_cells: usize,
+    pub lookup_cells: usize,
+}
+
+impl Add for MsmCost {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            num_bases: self.num_bases + rhs.num_bases,
+            num_fixed_bases: self.num_fixed_bases + rhs.num_fixed_bases,
+            advice_cells: self.advice_cells + rhs.advice_cells,
+            lookup_cells: self.lookup_cells + rhs.lookup_cells,
+        }
+    }
+}
+
+impl Sum for MsmCost {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// Cells used by a metered stage, which includes cells used by any stage
+/// nested in it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StageStats {
+    pub identifier: String,
+    pub advice_cells: usize,
+    pub lookup_cells: usize,
+    pub constants: usize,
+}
+
+/// Resources used by a circuit synthesized by `Halo2Loader`, returned by
+/// [`Halo2Loader::finalize`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitStats {
+    /// Max rows used by fixed columns.
+    pub max_fixed_rows: usize,
+    /// Total cells used by fixed columns, which are the distinct constants.
+    pub fixed_cells: usize,
+    /// Max rows used by special lookup advice columns.
+    pub max_lookup_rows: usize,
+    /// Max rows used by non-lookup advice columns.
+    pub max_advice_rows: usize,
+    /// Total non-lookup advice cells.
+    pub advice_cells: usize,
+    /// Total cells in special lookup advice columns.
+    pub lookup_cells: usize,
+    /// Cells of each metered stage (by `Loader::start_cost_metering`), where
+    /// stages with the same identifier are summed up in order of first start.
+    pub stages: Vec<StageStats>,
+    /// Total cost of all MSMs.
+    pub msm: MsmCost,
+}
+
+impl CircuitStats {
+    pub fn stage(&self, identifier: &str) -> Option<&StageStats> {
+        self.stages.iter().find(|stage| stage.identifier == identifier)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// Stats of regions laid out one after another, where max rows are summed up
+/// as an upper bound since regions might not share columns.
+impl Add for CircuitStats {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self.max_fixed_rows += rhs.max_fixed_rows;
+        self.fixed_cells += rhs.fixed_cells;
+        self.max_lookup_rows += rhs.max_lookup_rows;
+        self.max_advice_rows += rhs.max_advice_rows;
+        self.advice_cells += rhs.advice_cells;
+        self.lookup_cells += rhs.lookup_cells;
+        for rhs in rhs.stages {
+            match self.stages.iter_mut().find(|stage| stage.identifier == rhs.identifier) {
+                Some(stage) => {
+                    stage.advice_cells += rhs.advice_cells;
+                    stage.lookup_cells += rhs.lookup_cells;
+                    stage.constants += rhs.constants;
+                }
+                None => self.stages.push(rhs),
+            }
+        }
+        self.msm = self.msm + rhs.msm;
+        self
+    }
+}
+
+impl Sum for CircuitStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
 pub struct Halo2Loader<'a, 'b, C: CurveAffine> {
     pub ecc_chip: EccChip<'a, C::Scalar, BaseFieldChip<C>>,
     ctx: RefCell<Context<'b, C::Scalar>>,
     num_ec_point: RefCell<usize>,
     num_scalar: RefCell<usize>,
+    msm_config: MsmConfig,
+    msm_costs: RefCell<Vec<MsmCost>>,
+    metering: RefCell<Vec<(String, [usize; 3])>>,
+    stages: RefCell<Vec<StageStats>>,
 }
 impl<'a, 'b, C: CurveAffine> Debug for Halo2Loader<'a, 'b, C> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Halo2Loader")
             .field("num_ec_point", &self.num_ec_point)
             .field("num_scalar", &self.num_scalar)
+            .field("msm_config", &self.msm_config)
             .finish()
     }
 }
@@ -58,14 +213,67 @@ where
     C::Base: PrimeField,
 {
     pub fn new(field_chip: &'a BaseFieldChip<C>, ctx: Context<'b, C::Scalar>) -> Rc<Self> {
+        Self::with_msm_config(field_chip, ctx, MsmConfig::default())
+    }
+
+    pub fn with_msm_config(
+        field_chip: &'a BaseFieldChip<C>,
+        ctx: Context<'b, C::Scalar>,
+        msm_config: MsmConfig,
+    ) -> Rc<Self> {
         Rc::new(Self {
             ecc_chip: EccChip::construct(field_chip),
             ctx: RefCell::new(ctx),
             num_ec_point: RefCell::new(0),
             num_scalar: RefCell::new(0),
+            msm_config,
+            msm_costs: RefCell::new(Vec::new()),
+            metering: RefCell::new(Vec::new()),
+            stages: RefCell::new(Vec::new()),
         })
     }
 
+    pub fn msm_config(&self) -> MsmConfig {
+        self.msm_config
+    }
+
+    /// Returns cost of each MSM done so far, in order.
+    pub fn msm_costs(&self) -> Vec<MsmCost> {
+        self.msm_costs.borrow().clone()
+    }
+
+    /// Returns number of non-lookup advice cells, cells to lookup and
+    /// constants to assign used so far.
+    fn num_cells(&self) -> [usize; 3] {
+        let ctx = self.ctx.borrow();
+        [
+            ctx.advice_rows[&self.range().context_id].iter().sum(),
+            ctx.cells_to_lookup.len(),
+            ctx.constants_to_assign.len(),
+        ]
+    }
+
+    fn start_stage(&self, identifier: &str) {
+        self.metering.borrow_mut().push((identifier.to_string(), self.num_cells()));
+    }
+
+    fn end_stage(&self) {
+        let (identifier, start) =
+            self.metering.borrow_mut().pop().expect("end_cost_metering without start");
+        let [advice_cells, lookup_cells, constants] =
+            [0, 1, 2].map(|idx| self.num_cells()[idx] - start[idx]);
+
+        let mut stages = self.stages.borrow_mut();
+        match stages.iter_mut().find(|stage| stage.identifier == identifier) {
+            Some(stage) => {
+                stage.advice_cells += advice_cells;
+                stage.lookup_cells += lookup_cells;
+                stage.constants += constants;
+            }
+            None => stages.push(StageStats { identifier, advice_cells, lookup_cells, constants }),
+        }
+    }
+
     pub fn ecc_chip(&self) -> &EccChip<'a, C::Scalar, BaseFieldChip<C>> {
         &self.ecc_chip
     }
@@ -74,6 +282,12 @@ where
         &self.ecc_chip.field_chip
     }
 
+    /// Returns how base field elements are split into limbs by field chip,
+    /// which limbs passed to [`Self::assign_ec_point_from_limbs`] should follow.
+    pub fn limbs(&self) -> LimbsDescriptor {
+        LimbsDescriptor::new(self.field_chip().num_limbs, self.field_chip().limb_bits)
+    }
+
     pub fn range(&self) -> &RangeConfig<C::Scalar> {
         self.field_chip().range()
     }
@@ -90,20 +304,27 @@ where
         self.ctx.borrow_mut()
     }
 
-    pub fn finalize(&self) {
-        let stats = self
+    /// Assigns constants and copies cells to lookup, then returns resources
+    /// used by the circuit.
+    pub fn finalize(&self) -> CircuitStats {
+        let (max_fixed_rows, fixed_cells, max_lookup_rows) = self
             .field_chip()
             .finalize(&mut self.ctx_mut())
             .expect("finalizing constants and lookups");
-        println!("stats (max rows fixed, total fixed cells, max rows lookup) {:?}", stats);
-
-        let total_cells =
-            self.ctx.borrow().advice_rows[&self.range().context_id].iter().sum::<usize>();
-        println!("total non-lookup advice cells used: {}", total_cells);
-        println!(
-            "total cells used in special lookup advice columns: {}",
-            self.ctx.borrow().cells_to_lookup.len()
-        );
+        let [advice_cells, lookup_cells, _] = self.num_cells();
+        let max_advice_rows =
+            self.ctx().advice_rows[&self.range().context_id].iter().max().copied().unwrap_or(0);
+
+        CircuitStats {
+            max_fixed_rows,
+            fixed_cells,
+            max_lookup_rows,
+            max_advice_rows,
+            advice_cells,
+            lookup_cells,
+            stages: self.stages.borrow().clone(),
+            msm: self.msm_costs.borrow().iter().copied().sum(),
+        }
     }
 
     pub fn assign_const_scalar(self: &Rc<Self>, constant: C::Scalar) -> Scalar<'a, 'b, C> {
@@ -133,6 +354,82 @@ where
         self.scalar(Value::Assigned(assigned[0].clone()))
     }
 
+    /// Returns `num_bits` little-endian bits of `scalar`, constrained to be
+    /// boolean and to compose `scalar`. The decomposition is not unique when
+    /// `num_bits` reaches `C::Scalar::NUM_BITS`, so caller should also check
+    /// it's less than the modulus if needed.
+    pub fn assign_bits(
+        self: &Rc<Self>,
+        scalar: &Scalar<'a, 'b, C>,
+        num_bits: usize,
+    ) -> Vec<Scalar<'a, 'b, C>> {
+        let value = match &scalar.value {
+            Value::Constant(constant) => circuit::Value::known(*constant),
+            Value::Assigned(assigned) => assigned.value().copied(),
+        };
+        let bits = (0..num_bits)
+            .map(|idx| {
+                let bit = value.map(|value| {
+                    let repr = value.to_repr();
+                    let byte = repr.as_ref().get(idx / 8).copied().unwrap_or_default();
+                    C::Scalar::from(((byte >> (idx % 8)) & 1) as u64)
+                });
+                let bit = self.assign_scalar(bit);
+                self.assert_eq("bit should be boolean", &(bit.clone() * &bit), &bit).unwrap();
+                bit
+            })
+            .collect_vec();
+
+        let powers_of_two =
+            std::iter::successors(Some(C::Scalar::one()), |power| Some(power.double()))
+                .take(num_bits)
+                .collect_vec();
+        let composed =
+            self.sum_with_coeff(&powers_of_two.iter().cloned().zip(bits.iter()).collect_vec());
+        self.assert_eq("bits should compose scalar", &composed, scalar).unwrap();
+
+        bits
+    }
+
+    /// Returns `C::Scalar::NUM_BITS` little-endian bits of `scalar`, which are
+    /// constrained to be canonical, for hashing with bit-oriented hash.
+    pub fn assign_canonical_scalar_bits(
+        self: &Rc<Self>,
+        scalar: &Scalar<'a, 'b, C>,
+    ) -> Vec<Bit<Scalar<'a, 'b, C>>> {
+        let bits = self
+            .assign_bits(scalar, C::Scalar::NUM_BITS as usize)
+            .into_iter()
+            .map(Bit::Assigned)
+            .collect_vec();
+        bits::assert_less_than(self, &bits, &modulus::<C::Scalar>());
+        bits
+    }
+
+    /// Returns `C::Base::NUM_BITS` little-endian bits of both coordinates of
+    /// `ec_point` decomposed from limbs, which are constrained to be canonical.
+    pub fn assign_canonical_ec_point_bits(
+        self: &Rc<Self>,
+        ec_point: &EcPoint<'a, 'b, C>,
+    ) -> [Vec<Bit<Scalar<'a, 'b, C>>>; 2] {
+        let limb_bits = self.field_chip().limb_bits;
+        let assigned = ec_point.assigned();
+        [&assigned.x, &assigned.y].map(|coordinate| {
+            let bits = coordinate
+                .truncation
+                .limbs
+                .iter()
+                .flat_map(|limb| {
+                    self.assign_bits(&self.scalar_from_assigned(limb.clone()), limb_bits)
+                })
+                .map(Bit::Assigned)
+                .collect_vec();
+            // Also constrains bits beyond `C::Base::NUM_BITS` to be zero
+            bits::assert_less_than(self, &bits, &modulus::<C::Base>());
+            bits.into_iter().take(C::Base::NUM_BITS as usize).collect()
+        })
+    }
+
     pub fn scalar(self: &Rc<Self>, value: Value<C::Scalar, AssignedValue<C>>) -> Scalar<'a, 'b, C> {
         let index = *self.num_scalar.borrow();
         *self.num_scalar.borrow_mut() += 1;
@@ -171,7 +468,15 @@ where
         x_limbs: Vec<AssignedValue<C>>,
         y_limbs: Vec<AssignedValue<C>>,
     ) -> EcPoint<'a, 'b, C> {
-        let limbs_to_crt = |limbs| {
+        let limbs_to_crt = |limbs: Vec<AssignedValue<C>>| {
+            assert_eq!(limbs.len(), self.limbs().num_limbs);
+            // Limbs might come from instances, so they need to be range checked
+            // to make `OverflowInteger` bounds below hold
+            for limb in limbs.iter() {
+                self.range()
+                    .range_check(&mut self.ctx_mut(), limb, self.field_chip().limb_bits)
+                    .unwrap();
+            }
             let native = OverflowInteger::evaluate(
                 self.gate(),
                 &mut self.ctx_mut(),
@@ -190,7 +495,10 @@ where
                 self.field_chip().limb_bits,
                 self.field_chip().p.clone() - 1usize,
             );
-            CRTInteger::construct(truncation, native, big_value)
+            let crt = CRTInteger::construct(truncation, native, big_value);
+            // The composed value should also be canonical
+            self.field_chip().enforce_less_than_p(&mut self.ctx_mut(), &crt).unwrap();
+            crt
         };
 
         let ec_point = EccPoint::construct(limbs_to_crt(x_limbs), limbs_to_crt(y_limbs));
@@ -201,6 +509,70 @@ where
         self.ec_point(ec_point)
     }
 
+    /// Returns `scalar` taken out of `Context`, which could be copied into
+    /// another loader by [`Self::copy_scalar`] after its region is replayed.
+    pub fn record_scalar(&self, scalar: &Scalar<'a, 'b, C>) -> RecordedValue<C::Scalar> {
+        RecordedValue::from(&scalar.assigned())
+    }
+
+    /// Returns `ec_point` taken out of `Context`, which could be copied into
+    /// another loader by [`Self::copy_ec_point`] after its region is replayed.
+    pub fn record_ec_point(&self, ec_point: &EcPoint<'a, 'b, C>) -> RecordedEcPoint<C::Scalar> {
+        let record_integer = |integer: &AssignedInteger<C>| RecordedInteger {
+            limbs: integer.truncation.limbs.iter().map(RecordedValue::from).collect(),
+            max_limb_size: integer.truncation.max_limb_size.clone(),
+            limb_bits: integer.truncation.limb_bits,
+            max_size: integer.truncation.max_size.clone(),
+            native: RecordedValue::from(&integer.native),
+            value: integer.value.clone(),
+        };
+
+        let assigned = ec_point.assigned();
+        RecordedEcPoint { x: record_integer(&assigned.x), y: record_integer(&assigned.y) }
+    }
+
+    /// Assigns `recorded` as witness and constrains it to equal the replayed
+    /// cell in `cells`.
+    pub fn copy_scalar(
+        self: &Rc<Self>,
+        recorded: &RecordedValue<C::Scalar>,
+        cells: &CellMap,
+    ) -> Scalar<'a, 'b, C> {
+        let scalar = self.assign_scalar(recorded.value);
+        let cell = scalar.assigned().cell();
+        self.ctx_mut()
+            .region
+            .constrain_equal(cell, cells.get(recorded.cell))
+            .expect("copying recorded cell");
+        scalar
+    }
+
+    /// Assigns `recorded` as witness with the same bounds and constrains each
+    /// cell to equal the replayed one in `cells`, so no range check is needed.
+    pub fn copy_ec_point(
+        self: &Rc<Self>,
+        recorded: &RecordedEcPoint<C::Scalar>,
+        cells: &CellMap,
+    ) -> EcPoint<'a, 'b, C> {
+        let copy_integer = |integer: &RecordedInteger<C::Scalar>| {
+            let limbs = integer
+                .limbs
+                .iter()
+                .map(|limb| self.copy_scalar(limb, cells).assigned())
+                .collect_vec();
+            let native = self.copy_scalar(&integer.native, cells).assigned();
+            let truncation = OverflowInteger::construct(
+                limbs,
+                integer.max_limb_size.clone(),
+                integer.limb_bits,
+                integer.max_size.clone(),
+            );
+            CRTInteger::construct(truncation, native, integer.value.clone())
+        };
+
+        self.ec_point(EccPoint::construct(copy_integer(&recorded.x), copy_integer(&recorded.y)))
+    }
+
     fn add(self: &Rc<Self>, lhs: &Scalar<'a, 'b, C>, rhs: &Scalar<'a, 'b, C>) -> Scalar<'a, 'b, C> {
         let output = match (&lhs.value, &rhs.value) {
             (Value::Constant(lhs), Value::Constant(rhs)) => Value::Constant(*lhs + rhs),
@@ -633,6 +1005,9 @@ where
     ) -> Self {
         let pairs = pairs.into_iter().collect_vec();
         let loader = &pairs[0].0.loader;
+        let MsmConfig { strategy, window_bits, fixed_window_bits, batch_fixed_base } =
+            loader.msm_config;
+        let [advice_cells, lookup_cells, _] = loader.num_cells();
 
         let mut sum_constants = None;
 
@@ -653,7 +1028,9 @@ where
                                     Some(prod)
                                 };
                             }
-                            fixed.push((constant_pt.clone(), scalar.assigned()));
+                            if !bool::from(constant_pt.is_identity()) {
+                                fixed.push((constant_pt.clone(), scalar.assigned()));
+                            }
                         }
                         Value::Assigned(assigned_pt) => {
                             scaled.push((assigned_pt.clone(), scalar.assigned()));
@@ -666,21 +1043,83 @@ where
         if let Some(sum) = sum_constants {
             non_scaled.push(loader.assign_const_ec_point(sum).assigned());
         }
+        let cost = MsmCost {
+            num_bases: scaled.len() + non_scaled.len(),
+            num_fixed_bases: fixed.len(),
+            ..Default::default()
+        };
 
         let mut sum = None;
         if !scaled.is_empty() {
-            sum = loader
+            let (points, scalars): (Vec<_>, Vec<_>) =
+                scaled.into_iter().map(|(point, scalar)| (point, vec![scalar])).unzip();
+            let max_bits = C::Scalar::NUM_BITS as usize;
+            sum = Some(match strategy {
+                MsmStrategy::Straus => loader
+                    .ecc_chip
+                    .multi_scalar_mult::<C>(
+                        &mut loader.ctx_mut(),
+                        &points,
+                        &scalars,
+                        max_bits,
+                        window_bits,
+                    )
+                    .expect("variable-base msm should not fail"),
+                MsmStrategy::Pippenger => {
+                    // Same radix as what `halo2_ecc` chooses for its own msm
+                    let radix = (max_bits as f64 / points.len() as f64).sqrt().floor() as usize;
+                    pippenger::multi_exp::<C::Scalar, _, C>(
+                        loader.field_chip(),
+                        &mut loader.ctx_mut(),
+                        &points,
+                        &scalars,
+                        max_bits,
+                        radix.max(1),
+                        window_bits,
+                    )
+                    .expect("variable-base msm should not fail")
+                }
+            });
+        }
+
+        let fixed_msms = if batch_fixed_base && !fixed.is_empty() {
+            let (points, scalars): (Vec<_>, Vec<_>) =
+                fixed.into_iter().map(|(point, scalar)| (point, vec![scalar])).unzip();
+            vec![loader
                 .ecc_chip
-                .multi_scalar_mult::<C>(
+                .fixed_base_msm::<C>(
                     &mut loader.ctx_mut(),
-                    &scaled.iter().map(|pair| pair.0.clone()).collect(),
-                    &scaled.into_iter().map(|pair| vec![pair.1]).collect(),
-                    <C::Scalar as PrimeField>::NUM_BITS as usize,
-                    4,
+                    &points,
+                    &scalars,
+                    C::Scalar::NUM_BITS as usize,
+                    0,
+                    fixed_window_bits,
                 )
-                .ok();
-        }
-        if !non_scaled.is_empty() || !fixed.is_empty() {
+                .expect("fixed msms should not fail")]
+        } else {
+            fixed
+                .iter()
+                .map(|(constant_point, scalar)| {
+                    let fixed_point = FixedEccPoint::from_g1(
+                        constant_point,
+                        loader.field_chip().num_limbs,
+                        loader.field_chip().limb_bits,
+                    );
+                    loader
+                        .ecc_chip
+                        .fixed_base_scalar_mult(
+                            &mut loader.ctx_mut(),
+                            &fixed_point,
+                            &vec![scalar.clone()],
+                            C::Scalar::NUM_BITS as usize,
+                            fixed_window_bits,
+                        )
+                        .expect("fixed msms should not fail")
+                })
+                .collect_vec()
+        };
+
+        if !non_scaled.is_empty() || !fixed_msms.is_empty() {
             let rand_point = loader.ecc_chip.load_random_point::<C>(&mut loader.ctx_mut()).unwrap();
             let mut acc = if let Some(prev) = sum {
                 loader
@@ -690,33 +1129,9 @@ where
             } else {
                 rand_point.clone()
             };
-            for point in non_scaled.into_iter() {
+            for point in non_scaled.iter().chain(fixed_msms.iter()) {
                 acc =
-                    loader.ecc_chip.add_unequal(&mut loader.ctx_mut(), &acc, &point, true).unwrap();
-            }
-            for (constant_point, scalar) in fixed.iter() {
-                if constant_point.is_identity().into() {
-                    continue;
-                }
-                let fixed_point = FixedEccPoint::from_g1(
-                    constant_point,
-                    loader.field_chip().num_limbs,
-                    loader.field_chip().limb_bits,
-                );
-                let fixed_msm = loader
-                    .ecc_chip
-                    .fixed_base_scalar_mult(
-                        &mut loader.ctx_mut(),
-                        &fixed_point,
-                        &vec![scalar.clone()],
-                        C::Scalar::NUM_BITS as usize,
-                        4,
-                    )
-                    .expect("fixed msms should not fail");
-                acc = loader
-                    .ecc_chip
-                    .add_unequal(&mut loader.ctx_mut(), &acc, &fixed_msm, true)
-                    .unwrap();
+                    loader.ecc_chip.add_unequal(&mut loader.ctx_mut(), &acc, point, true).unwrap();
             }
             acc = loader
                 .ecc_chip
@@ -724,6 +1139,14 @@ where
                 .unwrap();
             sum = Some(acc);
         }
+
+        let [advice_cells_after, lookup_cells_after, _] = loader.num_cells();
+        loader.msm_costs.borrow_mut().push(MsmCost {
+            advice_cells: advice_cells_after - advice_cells,
+            lookup_cells: lookup_cells_after - lookup_cells,
+            ..cost
+        });
+
         loader.ec_point(sum.unwrap())
     }
 }
@@ -734,19 +1157,66 @@ impl<'a, 'b, C: CurveAffine> Debug for EcPoint<'a, 'b, C> {
     }
 }
 
+impl<'a, 'b, C: CurveAffine> EcPoint<'a, 'b, C> {
+    fn is_constant_identity(&self) -> bool {
+        matches!(&self.value, Value::Constant(constant) if bool::from(constant.is_identity()))
+    }
+}
+
+// Addition and subtraction use the strict variants, which constrain `x` of
+// operands to differ, since the non-strict ones silently output garbage when
+// doubling or getting identity, which is only fine when one of operands is a
+// random point like in `multi_scalar_multiplication`. The same point is
+// doubled or cancelled out, but distinct points turning out to be equal (or
+// opposite) make the circuit unsatisfiable.
 impl<'a, 'b, C: CurveAffine> Add for EcPoint<'a, 'b, C> {
     type Output = Self;
 
-    fn add(self, _: Self) -> Self::Output {
-        todo!()
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.is_constant_identity() {
+            return rhs;
+        }
+        if rhs.is_constant_identity() {
+            return self;
+        }
+        let loader = self.loader.clone();
+        if let (Value::Constant(lhs), Value::Constant(rhs)) = (&self.value, &rhs.value) {
+            return loader.assign_const_ec_point(C::Curve::to_affine(&(*lhs + *rhs)));
+        }
+        let output = if self == rhs {
+            loader.ecc_chip.double(&mut loader.ctx_mut(), &self.assigned()).unwrap()
+        } else {
+            loader
+                .ecc_chip
+                .add_unequal(&mut loader.ctx_mut(), &self.assigned(), &rhs.assigned(), true)
+                .unwrap()
+        };
+        loader.ec_point(output)
     }
 }
 
 impl<'a, 'b, C: CurveAffine> Sub for EcPoint<'a, 'b, C> {
     type Output = Self;
 
-    fn sub(self, _: Self) -> Self::Output {
-        todo!()
+    fn sub(self, rhs: Self) -> Self::Output {
+        if self.is_constant_identity() {
+            return -rhs;
+        }
+        if rhs.is_constant_identity() {
+            return self;
+        }
+        let loader = self.loader.clone();
+        if let (Value::Constant(lhs), Value::Constant(rhs)) = (&self.value, &rhs.value) {
+            return loader.assign_const_ec_point(C::Curve::to_affine(&(*lhs - *rhs)));
+        }
+        if self == rhs {
+            return loader.assign_const_ec_point(C::identity());
+        }
+        let output = loader
+            .ecc_chip
+            .sub_unequal(&mut loader.ctx_mut(), &self.assigned(), &rhs.assigned(), true)
+            .unwrap();
+        loader.ec_point(output)
     }
 }
 
@@ -754,7 +1224,14 @@ impl<'a, 'b, C: CurveAffine> Neg for EcPoint<'a, 'b, C> {
     type Output = Self;
 
     fn neg(self) -> Self::Output {
-        todo!()
+        let loader = self.loader.clone();
+        match &self.value {
+            Value::Constant(constant) => loader.assign_const_ec_point(-*constant),
+            Value::Assigned(assigned) => {
+                let output = loader.ecc_chip.negate(&mut loader.ctx_mut(), assigned).unwrap();
+                loader.ec_point(output)
+            }
+        }
     }
 }
 
@@ -924,4 +1401,32 @@ impl<'a, 'b, C: CurveAffine> Loader<C> for Rc<Halo2Loader<'a, 'b, C>> {
             .expect("ec_point_select should not fail");
         Ok(self.ec_point(assigned))
     }
+
+    fn ec_point_add(
+        &self,
+        lhs: &EcPoint<'a, 'b, C>,
+        rhs: &EcPoint<'a, 'b, C>,
+    ) -> EcPoint<'a, 'b, C> {
+        lhs.clone() + rhs
+    }
+
+    fn ec_point_sub(
+        &self,
+        lhs: &EcPoint<'a, 'b, C>,
+        rhs: &EcPoint<'a, 'b, C>,
+    ) -> EcPoint<'a, 'b, C> {
+        lhs.clone() - rhs
+    }
+
+    fn ec_point_neg(&self, ec_point: &EcPoint<'a, 'b, C>) -> EcPoint<'a, 'b, C> {
+        -ec_point.clone()
+    }
+
+    fn start_cost_metering(&self, identifier: &str) {
+        self.start_stage(identifier)
+    }
+
+    fn end_cost_metering(&self) {
+        self.end_stage()
+    }
 }
@@ -2,6 +2,7 @@ use std::marker::PhantomData;
 
 use crate::loader::{LoadedScalar, ScalarLoader};
 use crate::util::arithmetic::{FieldExt, PrimeField};
+use crate::util::hash::TranscriptHash;
 // taken from https://github.com/scroll-tech/halo2-snark-aggregator/tree/main/halo2-snark-aggregator-api/src/hash
 use poseidon::{SparseMDSMatrix, Spec, State};
 
@@ -182,3 +183,15 @@ impl<F: PrimeField + FieldExt, L: LoadedScalar<F>, const T: usize, const RATE: u
         self.state.apply_mds(mds);
     }
 }
+
+impl<F: PrimeField + FieldExt, L: LoadedScalar<F>, const T: usize, const RATE: usize>
+    TranscriptHash<F, L> for PoseidonChip<F, L, T, RATE>
+{
+    fn update(&mut self, elements: &[L]) {
+        PoseidonChip::update(self, elements)
+    }
+
+    fn squeeze(&mut self) -> L {
+        PoseidonChip::squeeze(self)
+    }
+}
@@ -0,0 +1,198 @@
+//! Recording of region assignment, so witness of independent parts of a
+//! circuit could be generated in parallel, each by its own `Halo2Loader`, and
+//! then replayed into real regions in a deterministic order.
+
+use halo2_base::AssignedValue;
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{layouter::RegionLayouter, Cell, Region, RegionIndex, Value},
+    plonk::{Advice, Any, Assigned, Column, Error, Fixed, Instance, Selector},
+};
+use num_bigint::{BigInt, BigUint};
+use std::collections::HashMap;
+
+/// Region index of cells handed out by `Recorder`, which are only meaningful
+/// to the recording.
+const RECORDED_REGION_INDEX: usize = usize::MAX;
+
+fn recorded_cell(column: impl Into<Column<Any>>, row_offset: usize) -> Cell {
+    Cell {
+        region_index: RegionIndex::from(RECORDED_REGION_INDEX),
+        row_offset,
+        column: column.into(),
+    }
+}
+
+/// `RegionLayouter` that records everything assigned into it instead of
+/// assigning, which can be used as a `Region` in any thread by
+/// `Region::from(&mut recorder as &mut dyn RegionLayouter<F>)`.
+///
+/// Instance columns are not accessible from a recording.
+#[derive(Debug, Default)]
+pub struct Recorder<F: Field> {
+    selectors: Vec<(Selector, usize)>,
+    advice: Vec<(Column<Advice>, usize, Value<Assigned<F>>)>,
+    advice_from_constant: Vec<(Column<Advice>, usize, Assigned<F>)>,
+    fixed: Vec<(Column<Fixed>, usize, Value<Assigned<F>>)>,
+    constants: Vec<(Cell, Assigned<F>)>,
+    equalities: Vec<(Cell, Cell)>,
+}
+
+impl<F: Field> Recorder<F> {
+    /// Replays the recording into `region` at the same offsets, and returns
+    /// `CellMap` from recorded cells to assigned ones.
+    ///
+    /// Since replay is cheap, it's fine to be called in every pass of floor
+    /// planner.
+    pub fn replay(&self, region: &mut Region<'_, F>) -> Result<CellMap, Error> {
+        let mut cells = HashMap::new();
+        for (selector, offset) in self.selectors.iter() {
+            selector.enable(region, *offset)?;
+        }
+        for (column, offset, value) in self.advice.iter() {
+            let cell = region.assign_advice(|| "", *column, *offset, || *value)?.cell();
+            cells.insert(((*column).into(), *offset), cell);
+        }
+        for (column, offset, constant) in self.advice_from_constant.iter() {
+            let cell =
+                region.assign_advice_from_constant(|| "", *column, *offset, *constant)?.cell();
+            cells.insert(((*column).into(), *offset), cell);
+        }
+        for (column, offset, value) in self.fixed.iter() {
+            let cell = region.assign_fixed(|| "", *column, *offset, || *value)?.cell();
+            cells.insert(((*column).into(), *offset), cell);
+        }
+
+        let cell_map = CellMap(cells);
+        for (cell, constant) in self.constants.iter() {
+            region.constrain_constant(cell_map.get(*cell), *constant)?;
+        }
+        for (lhs, rhs) in self.equalities.iter() {
+            region.constrain_equal(cell_map.get(*lhs), cell_map.get(*rhs))?;
+        }
+
+        Ok(cell_map)
+    }
+}
+
+impl<F: Field> RegionLayouter<F> for Recorder<F> {
+    fn enable_selector<'v>(
+        &'v mut self,
+        _: &'v (dyn Fn() -> String + 'v),
+        selector: &Selector,
+        offset: usize,
+    ) -> Result<(), Error> {
+        self.selectors.push((*selector, offset));
+        Ok(())
+    }
+
+    fn assign_advice<'v>(
+        &'v mut self,
+        _: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
+    ) -> Result<Cell, Error> {
+        self.advice.push((column, offset, to()));
+        Ok(recorded_cell(column, offset))
+    }
+
+    fn assign_advice_from_constant<'v>(
+        &'v mut self,
+        _: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        constant: Assigned<F>,
+    ) -> Result<Cell, Error> {
+        self.advice_from_constant.push((column, offset, constant));
+        Ok(recorded_cell(column, offset))
+    }
+
+    fn assign_advice_from_instance<'v>(
+        &mut self,
+        _: &'v (dyn Fn() -> String + 'v),
+        _: Column<Instance>,
+        _: usize,
+        _: Column<Advice>,
+        _: usize,
+    ) -> Result<(Cell, Value<F>), Error> {
+        Err(Error::Synthesis)
+    }
+
+    fn instance_value(&mut self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Err(Error::Synthesis)
+    }
+
+    fn assign_fixed<'v>(
+        &'v mut self,
+        _: &'v (dyn Fn() -> String + 'v),
+        column: Column<Fixed>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
+    ) -> Result<Cell, Error> {
+        self.fixed.push((column, offset, to()));
+        Ok(recorded_cell(column, offset))
+    }
+
+    fn constrain_constant(&mut self, cell: Cell, constant: Assigned<F>) -> Result<(), Error> {
+        self.constants.push((cell, constant));
+        Ok(())
+    }
+
+    fn constrain_equal(&mut self, lhs: Cell, rhs: Cell) -> Result<(), Error> {
+        self.equalities.push((lhs, rhs));
+        Ok(())
+    }
+}
+
+/// Mapping from cells recorded by `Recorder` to cells assigned by replay.
+#[derive(Clone, Debug)]
+pub struct CellMap(HashMap<(Column<Any>, usize), Cell>);
+
+impl CellMap {
+    /// Returns the replayed cell of `cell` if it's recorded, otherwise `cell`
+    /// itself, which is a cell in another region copied from the recording.
+    pub fn get(&self, cell: Cell) -> Cell {
+        if *cell.region_index == RECORDED_REGION_INDEX {
+            self.0[&(cell.column, cell.row_offset)]
+        } else {
+            cell
+        }
+    }
+
+    pub fn get_value<F: Field>(&self, value: &RecordedValue<F>) -> RecordedValue<F> {
+        RecordedValue { cell: self.get(value.cell), value: value.value }
+    }
+}
+
+/// Assigned value taken out of `Context`, which could be sent across threads
+/// and copied into another region after its cell being mapped by `CellMap`.
+#[derive(Clone, Copy, Debug)]
+pub struct RecordedValue<F: Field> {
+    pub cell: Cell,
+    pub value: Value<F>,
+}
+
+impl<'a, F: FieldExt> From<&'a AssignedValue<F>> for RecordedValue<F> {
+    fn from(assigned: &'a AssignedValue<F>) -> Self {
+        Self { cell: assigned.cell(), value: assigned.value().copied() }
+    }
+}
+
+/// `CRTInteger` taken out of `Context`, with all bounds kept.
+#[derive(Clone, Debug)]
+pub struct RecordedInteger<F: Field> {
+    pub limbs: Vec<RecordedValue<F>>,
+    pub max_limb_size: BigUint,
+    pub limb_bits: usize,
+    pub max_size: BigUint,
+    pub native: RecordedValue<F>,
+    pub value: Value<BigInt>,
+}
+
+/// `EccPoint` taken out of `Context`.
+#[derive(Clone, Debug)]
+pub struct RecordedEcPoint<F: Field> {
+    pub x: RecordedInteger<F>,
+    pub y: RecordedInteger<F>,
+}
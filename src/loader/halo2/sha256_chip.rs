@@ -0,0 +1,152 @@
+use crate::{
+    loader::{
+        halo2::bits::{self, Bit},
+        LoadedScalar,
+    },
+    util::{arithmetic::PrimeField, Itertools},
+};
+use std::marker::PhantomData;
+
+const BLOCK: usize = 512;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+type Word<L> = Vec<Bit<L>>;
+
+/// Converts between bytes in order and little-endian bits of big-endian
+/// words, which is an involution.
+fn reverse_bytes<L: Clone>(bits: &[Bit<L>]) -> Vec<Bit<L>> {
+    bits.chunks(32).flat_map(|word| word.chunks(8).rev().flatten().cloned()).collect()
+}
+
+fn rotate_right<L: Clone>(word: &[Bit<L>], offset: usize) -> Word<L> {
+    (0..32).map(|idx| word[(idx + offset) % 32].clone()).collect()
+}
+
+fn shift_right<L: Clone>(word: &[Bit<L>], offset: usize) -> Word<L> {
+    (0..32).map(|idx| word.get(idx + offset).cloned().unwrap_or(Bit::Constant(false))).collect()
+}
+
+fn xor<F: PrimeField, L: LoadedScalar<F>>(words: &[&[Bit<L>]]) -> Word<L> {
+    (0..32)
+        .map(|idx| {
+            words.iter().skip(1).fold(words[0][idx].clone(), |acc, word| acc.xor(&word[idx]))
+        })
+        .collect()
+}
+
+fn add<F: PrimeField, L: LoadedScalar<F>>(words: &[&[Bit<L>]]) -> Word<L> {
+    words.iter().skip(1).fold(words[0].to_vec(), |acc, word| bits::add(&acc, word))
+}
+
+/// SHA-256 over bits, as computed by the precompile at `0x02`.
+///
+/// Message and digest are bytes in order, each byte as 8 little-endian bits,
+/// same as [`KeccakChip`](super::keccak_chip::KeccakChip).
+pub struct Sha256Chip<F: PrimeField, L: LoadedScalar<F>> {
+    absorbing: Vec<Bit<L>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, L: LoadedScalar<F>> Default for Sha256Chip<F, L> {
+    fn default() -> Self {
+        Self { absorbing: Vec::new(), _marker: PhantomData }
+    }
+}
+
+impl<F: PrimeField, L: LoadedScalar<F>> Sha256Chip<F, L> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, bits: &[Bit<L>]) {
+        assert_eq!(bits.len() % 8, 0);
+        self.absorbing.extend_from_slice(bits);
+    }
+
+    /// Returns 256 bits of SHA-256 of absorbed bits, then resets.
+    pub fn squeeze(&mut self) -> Vec<Bit<L>> {
+        let mut input = std::mem::take(&mut self.absorbing);
+        let len = input.len() as u64;
+
+        // Padding `0x80 || 0x00* || len` with `len` in bits as big-endian u64
+        input.extend(Bit::from_u64(0x80, 8));
+        while input.len() % BLOCK != BLOCK - 64 {
+            input.extend(Bit::from_u64(0, 8));
+        }
+        input.extend(Bit::from_bytes(&len.to_be_bytes()));
+
+        let mut h = IV.iter().map(|word| Bit::from_u64(*word as u64, 32)).collect_vec();
+        for block in input.chunks(BLOCK) {
+            Self::compress(&mut h, &reverse_bytes(block));
+        }
+        reverse_bytes(&h.concat())
+    }
+
+    fn compress(h: &mut [Word<L>], block: &[Bit<L>]) {
+        let mut w = block.chunks(32).map(<[_]>::to_vec).collect_vec();
+        for idx in 16..64 {
+            let s0 = xor(&[
+                &rotate_right(&w[idx - 15], 7),
+                &rotate_right(&w[idx - 15], 18),
+                &shift_right(&w[idx - 15], 3),
+            ]);
+            let s1 = xor(&[
+                &rotate_right(&w[idx - 2], 17),
+                &rotate_right(&w[idx - 2], 19),
+                &shift_right(&w[idx - 2], 10),
+            ]);
+            let word = add(&[&w[idx - 16], &s0, &w[idx - 7], &s1]);
+            w.push(word);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh]: [Word<L>; 8] =
+            h.to_vec().try_into().unwrap_or_else(|_| unreachable!());
+        for (w, constant) in w.iter().zip(ROUND_CONSTANTS.iter()) {
+            let s1 = xor(&[&rotate_right(&e, 6), &rotate_right(&e, 11), &rotate_right(&e, 25)]);
+            // `(e & f) ^ (!e & g)`
+            let ch = e
+                .iter()
+                .zip(f.iter())
+                .zip(g.iter())
+                .map(|((e, f), g)| e.and(f).xor_andn(e, g))
+                .collect_vec();
+            let temp1 = add(&[&hh, &s1, &ch, &Bit::from_u64(*constant as u64, 32), w]);
+            let s0 = xor(&[&rotate_right(&a, 2), &rotate_right(&a, 13), &rotate_right(&a, 22)]);
+            // `(a & b) ^ (a & c) ^ (b & c)` as `(a & b) ^ (c & (a ^ b))`
+            let maj = a
+                .iter()
+                .zip(b.iter())
+                .zip(c.iter())
+                .map(|((a, b), c)| a.and(b).xor(&c.and(&a.xor(b))))
+                .collect_vec();
+            let temp2 = add(&[&s0, &maj]);
+
+            hh = g;
+            g = f;
+            f = e;
+            e = add(&[&d, &temp1]);
+            d = c;
+            c = b;
+            b = a;
+            a = add(&[&temp1, &temp2]);
+        }
+
+        for (h, word) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *h = add(&[h.as_slice(), &word]);
+        }
+    }
+}
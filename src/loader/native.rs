@@ -1,6 +1,9 @@
 use crate::{
     loader::{EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader},
-    util::arithmetic::{Curve, CurveAffine, FieldOps, PrimeField},
+    util::{
+        arithmetic::{Curve, CurveAffine, FieldOps, PrimeField},
+        msm,
+    },
     Error,
 };
 use lazy_static::lazy_static;
@@ -21,12 +24,8 @@ impl<C: CurveAffine> LoadedEcPoint<C> for C {
     }
 
     fn multi_scalar_multiplication(pairs: impl IntoIterator<Item = (C::Scalar, C)>) -> Self {
-        pairs
-            .into_iter()
-            .map(|(scalar, base)| base * scalar)
-            .reduce(|acc, value| acc + value)
-            .unwrap()
-            .to_affine()
+        let (scalars, bases): (Vec<_>, Vec<_>) = pairs.into_iter().unzip();
+        msm::multi_scalar_multiplication(&scalars, &bases).to_affine()
     }
 }
 
@@ -86,4 +85,43 @@ impl<F: PrimeField> ScalarLoader<F> for NativeLoader {
     }
 }
 
-impl<C: CurveAffine> Loader<C> for NativeLoader {}
+impl<C: CurveAffine> Loader<C> for NativeLoader {
+    fn ec_point_select(&self, a: &C, b: &C, sel: &C::Scalar) -> Result<C, Error> {
+        select(a, b, sel)
+    }
+
+    fn scalar_select(
+        &self,
+        a: &C::Scalar,
+        b: &C::Scalar,
+        sel: &C::Scalar,
+    ) -> Result<C::Scalar, Error> {
+        select(a, b, sel)
+    }
+
+    fn assert_bool(&self, annotation: &str, scalar: &C::Scalar) -> Result<(), Error> {
+        select(&(), &(), scalar).map_err(|_| Error::AssertionFailure(annotation.to_string()))
+    }
+
+    fn ec_point_add(&self, lhs: &C, rhs: &C) -> C {
+        (*lhs + *rhs).to_affine()
+    }
+
+    fn ec_point_sub(&self, lhs: &C, rhs: &C) -> C {
+        (*lhs - *rhs).to_affine()
+    }
+
+    fn ec_point_neg(&self, ec_point: &C) -> C {
+        -*ec_point
+    }
+}
+
+fn select<T: Clone, F: PrimeField>(a: &T, b: &T, sel: &F) -> Result<T, Error> {
+    if *sel == F::one() {
+        Ok(a.clone())
+    } else if *sel == F::zero() {
+        Ok(b.clone())
+    } else {
+        Err(Error::AssertionFailure("Selector should be 0 or 1".to_string()))
+    }
+}
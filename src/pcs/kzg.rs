@@ -11,8 +11,10 @@ mod decider;
 mod multiopen;
 
 pub use accumulation::{KzgAs, KzgAsProvingKey, KzgAsVerifyingKey};
-pub use accumulator::{KzgAccumulator, LimbsEncoding};
+pub use accumulator::{ConstLimbs, KzgAccumulator, LimbsEncoding, LimbsParams};
 pub use decider::KzgDecidingKey;
+#[cfg(feature = "loader_evm")]
+pub use decider::{decode_accumulator, fold_accumulators};
 pub use multiopen::{Bdfg21, Bdfg21Proof, Gwc19, Gwc19Proof};
 
 #[derive(Clone, Debug)]
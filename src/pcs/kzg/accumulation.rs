@@ -49,14 +49,25 @@ where
             .chain(proof.blind.clone())
             .unzip::<_, _, Vec<_>, Vec<_>>();
 
+        let loader = proof.r.loader();
+        loader.start_cost_metering("accumulation");
         let powers_of_r = proof.r.powers(lhs.len());
-        let [lhs, rhs] = [lhs, rhs].map(|msms| {
-            msms.into_iter()
-                .zip(powers_of_r.iter())
-                .map(|(msm, r)| Msm::<C, L>::base(msm) * r)
+        // The first term has coefficient `r^0`, so it's added after the rest
+        // instead of multiplied by one.
+        let [lhs, rhs] = [lhs, rhs].map(|ec_points| {
+            let (first, rest) = ec_points.split_first().unwrap();
+            if rest.is_empty() {
+                return first.clone();
+            }
+            let rest = rest
+                .iter()
+                .zip(powers_of_r[1..].iter())
+                .map(|(ec_point, r)| Msm::<C, L>::base(ec_point.clone()) * r)
                 .sum::<Msm<_, _>>()
-                .evaluate(None)
+                .evaluate(None);
+            loader.ec_point_add(first, &rest)
         });
+        loader.end_cost_metering();
 
         Ok(KzgAccumulator::new(lhs, rhs))
     }
@@ -116,6 +127,7 @@ where
     {
         assert!(instances.len() > 1);
 
+        transcript.common_label(b"KzgAs");
         for accumulator in instances {
             transcript.common_ec_point(&accumulator.lhs)?;
             transcript.common_ec_point(&accumulator.rhs)?;
@@ -126,7 +138,7 @@ where
             .then(|| Ok((transcript.read_ec_point()?, transcript.read_ec_point()?)))
             .transpose()?;
 
-        let r = transcript.squeeze_challenge();
+        let r = transcript.squeeze_labeled_challenge(b"r");
 
         Ok(Self {
             blind,
@@ -155,6 +167,7 @@ where
     {
         assert!(instances.len() > 1);
 
+        transcript.common_label(b"KzgAs");
         for accumulator in instances {
             transcript.common_ec_point(&accumulator.lhs)?;
             transcript.common_ec_point(&accumulator.rhs)?;
@@ -173,7 +186,7 @@ where
             })
             .transpose()?;
 
-        let r = transcript.squeeze_challenge();
+        let r = transcript.squeeze_labeled_challenge(b"r");
 
         let (lhs, rhs) = instances
             .iter()
@@ -1,5 +1,8 @@
-use crate::{loader::Loader, util::arithmetic::CurveAffine};
-use std::fmt::Debug;
+use crate::{
+    loader::Loader,
+    util::arithmetic::{CurveAffine, LimbsDescriptor},
+};
+use std::{fmt::Debug, marker::PhantomData};
 
 #[derive(Clone, Debug)]
 pub struct KzgAccumulator<C, L>
@@ -21,32 +24,58 @@ where
     }
 }
 
+/// Type-level source of `LimbsDescriptor` for `LimbsEncoding`, which could
+/// come from params of a circuit only known at runtime.
+pub trait LimbsParams {
+    fn limbs() -> LimbsDescriptor;
+}
+
+/// `LimbsParams` of const generic `LIMBS` and `BITS`, which respectively
+/// represents how many limbs a base field element are split into and how many
+/// bits each limbs could have.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConstLimbs<const LIMBS: usize, const BITS: usize>;
+
+impl<const LIMBS: usize, const BITS: usize> LimbsParams for ConstLimbs<LIMBS, BITS> {
+    fn limbs() -> LimbsDescriptor {
+        LimbsDescriptor::new(LIMBS, BITS)
+    }
+}
+
 /// `AccumulatorEncoding` that encodes `Accumulator` into limbs.
 ///
 /// Since in circuit everything are in scalar field, but `Accumulator` might contain base field elements, so we split them into limbs.
-/// The const generic `LIMBS` and `BITS` respectively represents how many limbs
-/// a base field element are split into and how many bits each limbs could have.
-#[derive(Clone, Debug)]
-pub struct LimbsEncoding<const LIMBS: usize, const BITS: usize>;
+/// How they are split is described by `P::limbs()`.
+pub struct LimbsEncoding<P>(PhantomData<P>);
+
+// Implemented manually to not require `P: Clone + Debug` as derive does.
+impl<P> Clone for LimbsEncoding<P> {
+    fn clone(&self) -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<P> Debug for LimbsEncoding<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LimbsEncoding").finish()
+    }
+}
 
 mod native {
     use crate::{
         loader::native::NativeLoader,
         pcs::{
-            kzg::{KzgAccumulator, LimbsEncoding},
+            kzg::{KzgAccumulator, LimbsEncoding, LimbsParams},
             AccumulatorEncoding, PolynomialCommitmentScheme,
         },
-        util::{
-            arithmetic::{fe_from_limbs, CurveAffine},
-            Itertools,
-        },
+        util::{arithmetic::CurveAffine, Itertools},
         Error,
     };
 
-    impl<C, PCS, const LIMBS: usize, const BITS: usize> AccumulatorEncoding<C, NativeLoader, PCS>
-        for LimbsEncoding<LIMBS, BITS>
+    impl<C, PCS, P> AccumulatorEncoding<C, NativeLoader, PCS> for LimbsEncoding<P>
     where
         C: CurveAffine,
+        P: LimbsParams,
         PCS: PolynomialCommitmentScheme<
             C,
             NativeLoader,
@@ -54,12 +83,13 @@ mod native {
         >,
     {
         fn from_repr(limbs: Vec<C::Scalar>) -> Result<PCS::Accumulator, Error> {
-            assert_eq!(limbs.len(), 4 * LIMBS);
+            let descriptor = P::limbs();
+            assert_eq!(limbs.len(), descriptor.num_accumulator_limbs());
 
             let [lhs_x, lhs_y, rhs_x, rhs_y]: [_; 4] = limbs
-                .chunks(LIMBS)
+                .chunks(descriptor.num_limbs)
                 .into_iter()
-                .map(|limbs| fe_from_limbs::<_, _, LIMBS, BITS>(limbs.try_into().unwrap()))
+                .map(|limbs| descriptor.fe_from_limbs(limbs))
                 .collect_vec()
                 .try_into()
                 .unwrap();
@@ -78,21 +108,18 @@ mod evm {
     use crate::{
         loader::evm::{EvmLoader, Scalar},
         pcs::{
-            kzg::{KzgAccumulator, LimbsEncoding},
+            kzg::{KzgAccumulator, LimbsEncoding, LimbsParams},
             AccumulatorEncoding, PolynomialCommitmentScheme,
         },
-        util::{
-            arithmetic::{CurveAffine, PrimeField},
-            Itertools,
-        },
+        util::arithmetic::{CurveAffine, PrimeField},
         Error,
     };
     use std::rc::Rc;
 
-    impl<C, PCS, const LIMBS: usize, const BITS: usize> AccumulatorEncoding<C, Rc<EvmLoader>, PCS>
-        for LimbsEncoding<LIMBS, BITS>
+    impl<C, PCS, P> AccumulatorEncoding<C, Rc<EvmLoader>, PCS> for LimbsEncoding<P>
     where
         C: CurveAffine,
+        P: LimbsParams,
         C::Scalar: PrimeField<Repr = [u8; 0x20]>,
         PCS: PolynomialCommitmentScheme<
             C,
@@ -101,21 +128,16 @@ mod evm {
         >,
     {
         fn from_repr(limbs: Vec<Scalar>) -> Result<PCS::Accumulator, Error> {
-            assert_eq!(limbs.len(), 4 * LIMBS);
+            let descriptor = P::limbs();
+            assert_eq!(limbs.len(), descriptor.num_accumulator_limbs());
 
             let loader = limbs[0].loader();
 
-            let [lhs_x, lhs_y, rhs_x, rhs_y]: [[_; LIMBS]; 4] = limbs
-                .chunks(LIMBS)
-                .into_iter()
-                .map(|limbs| limbs.to_vec().try_into().unwrap())
-                .collect_vec()
-                .try_into()
-                .unwrap();
-            let accumulator = KzgAccumulator::new(
-                loader.ec_point_from_limbs::<LIMBS, BITS>(lhs_x, lhs_y),
-                loader.ec_point_from_limbs::<LIMBS, BITS>(rhs_x, rhs_y),
-            );
+            let num_limbs = descriptor.num_limbs;
+            let [lhs, rhs] = [&limbs[..2 * num_limbs], &limbs[2 * num_limbs..]].map(|limbs| {
+                loader.ec_point_from_limbs(descriptor, &limbs[..num_limbs], &limbs[num_limbs..])
+            });
+            let accumulator = KzgAccumulator::new(lhs, rhs);
 
             Ok(accumulator)
         }
@@ -128,7 +150,7 @@ mod halo2 {
         loader::halo2::{Halo2Loader, Scalar},
         loader::LoadedScalar,
         pcs::{
-            kzg::{KzgAccumulator, LimbsEncoding},
+            kzg::{KzgAccumulator, LimbsEncoding, LimbsParams},
             AccumulatorEncoding, PolynomialCommitmentScheme,
         },
         util::{arithmetic::CurveAffine, Itertools},
@@ -136,10 +158,10 @@ mod halo2 {
     };
     use std::rc::Rc;
 
-    impl<'a, 'b, C, PCS, const LIMBS: usize, const BITS: usize>
-        AccumulatorEncoding<C, Rc<Halo2Loader<'a, 'b, C>>, PCS> for LimbsEncoding<LIMBS, BITS>
+    impl<'a, 'b, C, PCS, P> AccumulatorEncoding<C, Rc<Halo2Loader<'a, 'b, C>>, PCS> for LimbsEncoding<P>
     where
         C: CurveAffine,
+        P: LimbsParams,
         PCS: PolynomialCommitmentScheme<
             C,
             Rc<Halo2Loader<'a, 'b, C>>,
@@ -147,19 +169,25 @@ mod halo2 {
         >,
     {
         fn from_repr(limbs: Vec<Scalar<'a, 'b, C>>) -> Result<PCS::Accumulator, Error> {
-            assert_eq!(limbs.len(), 4 * LIMBS);
+            let descriptor = P::limbs();
+            assert_eq!(limbs.len(), descriptor.num_accumulator_limbs());
 
             let loader = limbs[0].loader();
+            assert_eq!(
+                loader.limbs(),
+                descriptor,
+                "Accumulator limbs should be encoded as field chip of loader"
+            );
 
+            let num_limbs = descriptor.num_limbs;
             let assigned_limbs = limbs.iter().map(|limb| limb.assigned()).collect_vec();
-            let [lhs, rhs] = [&assigned_limbs[..2 * LIMBS], &assigned_limbs[2 * LIMBS..]].map(
-                |assigned_limbs| {
+            let [lhs, rhs] = [&assigned_limbs[..2 * num_limbs], &assigned_limbs[2 * num_limbs..]]
+                .map(|assigned_limbs| {
                     loader.assign_ec_point_from_limbs(
-                        assigned_limbs[..LIMBS].to_vec(),
-                        assigned_limbs[LIMBS..2 * LIMBS].to_vec(),
+                        assigned_limbs[..num_limbs].to_vec(),
+                        assigned_limbs[num_limbs..].to_vec(),
                     )
-                },
-            );
+                });
 
             let accumulator = KzgAccumulator::new(lhs, rhs);
 
@@ -69,7 +69,8 @@ mod native {
 mod evm {
     use crate::{
         loader::{
-            evm::{loader::Value, EvmLoader},
+            evm::{loader::Value, u256_to_fe, AccumulatorOutput, EvmLoader},
+            native::NativeLoader,
             LoadedScalar,
         },
         pcs::{
@@ -77,11 +78,13 @@ mod evm {
             Decider,
         },
         util::{
-            arithmetic::{CurveAffine, MultiMillerLoop, PrimeField},
+            arithmetic::{Curve, CurveAffine, MultiMillerLoop, PrimeField},
             msm::Msm,
+            Itertools,
         },
     };
     use ethereum_types::U256;
+    use sha3::{Digest, Keccak256};
     use std::{fmt::Debug, rc::Rc};
 
     impl<M, MOS> Decider<M::G1Affine, Rc<EvmLoader>> for Kzg<M, MOS>
@@ -98,18 +101,39 @@ mod evm {
             KzgAccumulator { lhs, rhs }: KzgAccumulator<M::G1Affine, Rc<EvmLoader>>,
         ) {
             let loader = lhs.loader();
-            let [g2, minus_s_g2] = [dk.g2, -dk.s_g2].map(|ec_point| {
-                let coordinates = ec_point.coordinates().unwrap();
-                let x = coordinates.x().to_repr();
-                let y = coordinates.y().to_repr();
-                (
-                    U256::from_little_endian(&x.as_ref()[32..]),
-                    U256::from_little_endian(&x.as_ref()[..32]),
-                    U256::from_little_endian(&y.as_ref()[32..]),
-                    U256::from_little_endian(&y.as_ref()[..32]),
-                )
-            });
-            loader.pairing(&lhs, g2, &rhs, minus_s_g2);
+            if let AccumulatorOutput::Pairing = loader.accumulator_output() {
+                let [g2, minus_s_g2] = [dk.g2, -dk.s_g2].map(|ec_point| {
+                    let coordinates = ec_point.coordinates().unwrap();
+                    let x = coordinates.x().to_repr();
+                    let y = coordinates.y().to_repr();
+                    (
+                        U256::from_little_endian(&x.as_ref()[32..]),
+                        U256::from_little_endian(&x.as_ref()[..32]),
+                        U256::from_little_endian(&y.as_ref()[32..]),
+                        U256::from_little_endian(&y.as_ref()[..32]),
+                    )
+                });
+                loader.start_gas_metering("pairing");
+                loader.pairing(&lhs, g2, &rhs, minus_s_g2);
+                loader.end_gas_metering();
+                return;
+            }
+
+            let ptr = loader.dup_ec_point(&lhs).ptr();
+            loader.dup_ec_point(&rhs);
+            match loader.accumulator_output() {
+                AccumulatorOutput::Pairing => unreachable!(),
+                AccumulatorOutput::Return => loader.set_return_data(ptr, 0x80),
+                AccumulatorOutput::Store { slot } => {
+                    let mut code = loader.code_mut();
+                    for idx in 0..4 {
+                        code.push(ptr + idx * 0x20).mload().push(slot + idx).sstore();
+                    }
+                }
+                AccumulatorOutput::Emit { topic } => {
+                    loader.code_mut().push(topic).push(0x80).push(ptr).log1();
+                }
+            }
         }
 
         fn decide_all(
@@ -122,6 +146,7 @@ mod evm {
                 accumulators.pop().unwrap()
             } else {
                 let loader = accumulators[0].lhs.loader();
+                loader.start_gas_metering("msm");
                 let (lhs, rhs) = accumulators
                     .iter()
                     .map(|KzgAccumulator { lhs, rhs }| {
@@ -152,6 +177,7 @@ mod evm {
                         .sum::<Msm<_, _>>()
                         .evaluate(None)
                 });
+                loader.end_gas_metering();
 
                 KzgAccumulator::new(lhs, rhs)
             };
@@ -159,4 +185,76 @@ mod evm {
             Self::decide(dk, accumulator)
         }
     }
+
+    /// Folds `accumulators` into one the same way [`Decider::decide_all`] for
+    /// [`EvmLoader`] does, so an accumulator output by verifiers not running
+    /// the pairing (see [`AccumulatorOutput`]) can be checked or batched
+    /// natively.
+    pub fn fold_accumulators<C>(
+        mut accumulators: Vec<KzgAccumulator<C, NativeLoader>>,
+    ) -> KzgAccumulator<C, NativeLoader>
+    where
+        C: CurveAffine,
+        C::Base: PrimeField<Repr = [u8; 0x20]>,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        assert!(!accumulators.is_empty());
+
+        if accumulators.len() == 1 {
+            return accumulators.pop().unwrap();
+        }
+
+        let hash: [u8; 32] = Keccak256::digest(
+            accumulators
+                .iter()
+                .flat_map(|KzgAccumulator { lhs, rhs }| [lhs, rhs])
+                .flat_map(|ec_point| {
+                    let coordinates = ec_point.coordinates().unwrap();
+                    [*coordinates.x(), *coordinates.y()]
+                })
+                .flat_map(|coordinate| {
+                    coordinate.to_repr().as_ref().iter().rev().cloned().collect_vec()
+                })
+                .collect_vec(),
+        )
+        .into();
+        let challenge = u256_to_fe::<C::Scalar>(U256::from_big_endian(&hash));
+        let powers_of_challenge = LoadedScalar::powers(&challenge, accumulators.len());
+
+        let [lhs, rhs] = [0, 1].map(|idx| {
+            accumulators
+                .iter()
+                .zip(powers_of_challenge.iter())
+                .map(|(KzgAccumulator { lhs, rhs }, power_of_challenge)| {
+                    *[lhs, rhs][idx] * *power_of_challenge
+                })
+                .reduce(|acc, value| acc + value)
+                .unwrap()
+                .to_affine()
+        });
+        KzgAccumulator::new(lhs, rhs)
+    }
+
+    /// Decodes the 4 words output by verifiers with [`AccumulatorOutput`]
+    /// other than [`AccumulatorOutput::Pairing`], returns `None` if the words
+    /// are not valid points.
+    pub fn decode_accumulator<C>(words: &[u8]) -> Option<KzgAccumulator<C, NativeLoader>>
+    where
+        C: CurveAffine,
+        C::Base: PrimeField<Repr = [u8; 0x20]>,
+    {
+        if words.len() != 0x80 {
+            return None;
+        }
+
+        let [lhs_x, lhs_y, rhs_x, rhs_y] = [0, 1, 2, 3].map(|idx| {
+            let mut repr = [0; 0x20];
+            repr.copy_from_slice(&words[idx * 0x20..(idx + 1) * 0x20]);
+            repr.reverse();
+            Option::<C::Base>::from(C::Base::from_repr(repr))
+        });
+        let lhs = Option::from(C::from_xy(lhs_x?, lhs_y?))?;
+        let rhs = Option::from(C::from_xy(rhs_x?, rhs_y?))?;
+        Some(KzgAccumulator::new(lhs, rhs))
+    }
 }
@@ -130,10 +130,10 @@ where
     L: Loader<C>,
 {
     fn read<T: TranscriptRead<C, L>>(transcript: &mut T) -> Result<Self, Error> {
-        let mu = transcript.squeeze_challenge();
-        let gamma = transcript.squeeze_challenge();
+        let mu = transcript.squeeze_labeled_challenge(b"mu");
+        let gamma = transcript.squeeze_labeled_challenge(b"gamma");
         let w = transcript.read_ec_point()?;
-        let z_prime = transcript.squeeze_challenge();
+        let z_prime = transcript.squeeze_labeled_challenge(b"z_prime");
         let w_prime = transcript.read_ec_point()?;
         Ok(Bdfg21Proof { mu, gamma, w, z_prime, w_prime })
     }
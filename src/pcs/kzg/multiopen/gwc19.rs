@@ -98,9 +98,9 @@ where
     where
         T: TranscriptRead<C, L>,
     {
-        let v = transcript.squeeze_challenge();
+        let v = transcript.squeeze_labeled_challenge(b"v");
         let ws = transcript.read_n_ec_points(query_sets(queries).len())?;
-        let u = transcript.squeeze_challenge();
+        let u = transcript.squeeze_labeled_challenge(b"u");
         Ok(Gwc19Proof { v, ws, u })
     }
 }
@@ -1,6 +1,7 @@
 use crate::{
+    pcs::kzg::LimbsParams,
     util::{
-        arithmetic::{root_of_unity, CurveAffine, Domain, FieldExt, Rotation},
+        arithmetic::{root_of_unity, CurveAffine, Domain, FieldExt, LimbsDescriptor, Rotation},
         protocol::{
             CommonPolynomial, Expression, InstanceCommittingKey, Query, QuotientPolynomial,
         },
@@ -20,18 +21,25 @@ use halo2_proofs::{
     },
     transcript::{EncodedChallenge, Transcript},
 };
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::{
     fs::{self, File},
     io::{self, BufReader, BufWriter},
     iter,
     mem::size_of,
+    path::Path,
+    sync::{Mutex, PoisonError, RwLock},
 };
 
 pub mod aggregation;
 pub mod transcript;
+pub mod tuner;
 
+/// Default number of limbs a base field element is split into, see
+/// `Halo2VerifierCircuitConfigParams::limbs` for the one used by a circuit.
 pub const LIMBS: usize = 3;
+/// Default number of bits of each limb.
 pub const BITS: usize = 88;
 
 #[cfg(test)]
@@ -742,8 +750,8 @@ fn instance_committing_key<'a, C: CurveAffine, P: Params<'a, C>>(
     InstanceCommittingKey { bases, constant: Some(w) }
 }
 
-// for tuning the circuit
-#[derive(Serialize, Deserialize)]
+// for tuning the circuit, see `tuner::tune_config`
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Halo2VerifierCircuitConfigParams {
     pub strategy: halo2_ecc::fields::fp::FpStrategy,
     pub degree: u32,
@@ -755,6 +763,132 @@ pub struct Halo2VerifierCircuitConfigParams {
     pub num_limbs: usize,
 }
 
+impl Halo2VerifierCircuitConfigParams {
+    /// Returns how base field elements are split into limbs in the circuit,
+    /// which is also how the accumulator is encoded in instances.
+    pub fn limbs(&self) -> LimbsDescriptor {
+        LimbsDescriptor::new(self.num_limbs, self.limb_bits)
+    }
+}
+
+/// Type-level source of `Halo2VerifierCircuitConfigParams` for circuits, since
+/// `Circuit::configure` has no access to the circuit itself. Circuits of
+/// different shapes could coexist by being generic over different params.
+///
+/// ```ignore
+/// #[derive(Clone, Copy, Debug, Default)]
+/// struct Wide;
+///
+/// impl Halo2VerifierCircuitParams for Wide {
+///     fn params() -> Halo2VerifierCircuitConfigParams {
+///         Halo2VerifierCircuitConfigParams {
+///             degree: 19,
+///             num_advice: 14,
+///             lookup_bits: 18,
+///             ..DefaultVerifierCircuitParams::params()
+///         }
+///     }
+/// }
+///
+/// let circuit = AggregationCircuit::<Wide>::builder(&params).with_snarks(snarks).build();
+/// ```
+pub trait Halo2VerifierCircuitParams {
+    fn params() -> Halo2VerifierCircuitConfigParams;
+}
+
+/// Accumulator in instances of verifier circuits is encoded as their field
+/// chips, so `LimbsEncoding<P>` decodes the one of a circuit configured by `P`.
+impl<P: Halo2VerifierCircuitParams> LimbsParams for P {
+    fn limbs() -> LimbsDescriptor {
+        P::params().limbs()
+    }
+}
+
+/// Params of a verifier circuit in `2^20` rows, which is enough to aggregate
+/// a few simple snarks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultVerifierCircuitParams;
+
+impl Halo2VerifierCircuitParams for DefaultVerifierCircuitParams {
+    fn params() -> Halo2VerifierCircuitConfigParams {
+        Halo2VerifierCircuitConfigParams {
+            strategy: halo2_ecc::fields::fp::FpStrategy::Simple,
+            degree: 20,
+            num_advice: 7,
+            num_lookup_advice: 1,
+            num_fixed: 1,
+            lookup_bits: 19,
+            limb_bits: BITS,
+            num_limbs: LIMBS,
+        }
+    }
+}
+
+lazy_static! {
+    static ref RUNTIME_VERIFIER_CIRCUIT_PARAMS: RwLock<Option<Halo2VerifierCircuitConfigParams>> =
+        RwLock::new(None);
+    static ref RUNTIME_VERIFIER_CIRCUIT_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Params set at runtime, for params only known after compilation like ones
+/// returned by [`tuner::tune_config`] or read from a file.
+///
+/// Params are process-global since `Circuit::configure` can't take them from
+/// the circuit, so they are only set within [`RuntimeVerifierCircuitParams::with`],
+/// which holds a lock for the whole call. Circuits of different runtime shapes
+/// are thus keygen-ed and proved one after another even across threads, use
+/// type-level params instead to run them concurrently.
+///
+/// ```ignore
+/// let pk = RuntimeVerifierCircuitParams::with(circuit.tune_config(k), || {
+///     let circuit = AggregationCircuit::<RuntimeVerifierCircuitParams>::builder(&params)
+///         .with_snarks(snarks)
+///         .build();
+///     gen_pk(&params, &circuit, "aggregation")
+/// });
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RuntimeVerifierCircuitParams;
+
+impl RuntimeVerifierCircuitParams {
+    /// Runs `f` with `params` returned by `RuntimeVerifierCircuitParams::params`,
+    /// then unsets them. Calls are serialized, so `f` should not call `with`
+    /// again, which deadlocks.
+    pub fn with<R>(params: Halo2VerifierCircuitConfigParams, f: impl FnOnce() -> R) -> R {
+        struct Unset;
+
+        impl Drop for Unset {
+            fn drop(&mut self) {
+                *RUNTIME_VERIFIER_CIRCUIT_PARAMS.write().unwrap_or_else(PoisonError::into_inner) =
+                    None;
+            }
+        }
+
+        // A panic in `f` of another call shouldn't fail following ones
+        let _guard = RUNTIME_VERIFIER_CIRCUIT_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        *RUNTIME_VERIFIER_CIRCUIT_PARAMS.write().unwrap_or_else(PoisonError::into_inner) =
+            Some(params);
+        let _unset = Unset;
+        f()
+    }
+
+    /// Reads params in json from `path`, to be passed to
+    /// [`RuntimeVerifierCircuitParams::with`].
+    pub fn read_from_file(path: impl AsRef<Path>) -> io::Result<Halo2VerifierCircuitConfigParams> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+}
+
+impl Halo2VerifierCircuitParams for RuntimeVerifierCircuitParams {
+    fn params() -> Halo2VerifierCircuitConfigParams {
+        RUNTIME_VERIFIER_CIRCUIT_PARAMS
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .expect("RuntimeVerifierCircuitParams should be used within its `with`")
+    }
+}
+
 #[derive(Clone)]
 pub struct Halo2VerifierCircuitConfig {
     pub base_field_config: halo2_ecc::fields::fp::FpConfig<Fr, Fq>,
@@ -766,11 +900,6 @@ impl Halo2VerifierCircuitConfig {
         meta: &mut ConstraintSystem<Fr>,
         params: Halo2VerifierCircuitConfigParams,
     ) -> Self {
-        assert!(
-            params.limb_bits == BITS && params.num_limbs == LIMBS,
-            "For now we fix limb_bits = {}, otherwise change code",
-            BITS
-        );
         let base_field_config = halo2_ecc::fields::fp::FpConfig::configure(
             meta,
             params.strategy,
@@ -1,6 +1,16 @@
-use super::{BITS, LIMBS};
 use crate::{
-    loader::{self, native::NativeLoader, Loader},
+    loader::{
+        self,
+        halo2::{
+            bits::{self, Bit},
+            keccak_chip::KeccakChip,
+            poseidon_chip::PoseidonChip,
+            recorder::{RecordedEcPoint, RecordedValue, Recorder},
+            CircuitStats, MsmConfig,
+        },
+        native::NativeLoader,
+        Loader,
+    },
     pcs::{
         kzg::{
             Bdfg21, Kzg, KzgAccumulator, KzgAs, KzgAsProvingKey, KzgAsVerifyingKey,
@@ -11,23 +21,27 @@ use crate::{
     system::{
         self,
         halo2::{
-            compile, read_or_create_srs, transcript::halo2::ChallengeScalar, Config,
-            Halo2VerifierCircuitConfig, Halo2VerifierCircuitConfigParams,
+            compile, read_or_create_srs, transcript::halo2::ChallengeScalar, tuner, Config,
+            DefaultVerifierCircuitParams, Halo2VerifierCircuitConfig,
+            Halo2VerifierCircuitConfigParams, Halo2VerifierCircuitParams,
         },
     },
-    util::arithmetic::fe_to_limbs,
+    util::arithmetic::PrimeField,
     verifier::{self, PlonkVerifier},
     Protocol,
 };
 use ark_std::{end_timer, start_timer};
+#[cfg(feature = "loader_evm")]
+pub use evm::encode_calldata_with_committed_instances;
 use halo2_base::AssignedValue;
 pub use halo2_base::{
     utils::{biguint_to_fe, fe_to_biguint},
     Context, ContextParams,
 };
 use halo2_curves::bn256::{Bn256, Fr, G1Affine};
+use halo2_ecc::fields::fp::FpStrategy;
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner, Value},
+    circuit::{layouter::RegionLayouter, Layouter, Region, SimpleFloorPlanner, Value},
     plonk::{
         self, create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, ProvingKey, VerifyingKey,
     },
@@ -46,9 +60,13 @@ use itertools::Itertools;
 use num_bigint::BigUint;
 use num_traits::Num;
 use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::{
+    cell::RefCell,
     fs::File,
     io::{BufReader, BufWriter, Cursor, Read, Write},
+    marker::PhantomData,
     path::Path,
     rc::Rc,
 };
@@ -67,7 +85,11 @@ pub type Svk = KzgSuccinctVerifyingKey<G1Affine>;
 pub type As = KzgAs<Pcs>;
 pub type AsPk = KzgAsProvingKey<G1Affine>;
 pub type AsVk = KzgAsVerifyingKey;
-pub type Plonk = verifier::Plonk<Pcs, LimbsEncoding<LIMBS, BITS>>;
+/// PLONK verifier decoding accumulators in instances of snarks as encoded by
+/// a verifier circuit configured by `P`.
+pub type Plonk<P = DefaultVerifierCircuitParams> = verifier::Plonk<Pcs, LimbsEncoding<P>>;
+pub type Scalar<'a, 'b> = loader::halo2::Scalar<'a, 'b, G1Affine>;
+pub type Accumulator<'a, 'b> = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, 'b>>>;
 
 pub struct Snark {
     protocol: Protocol<G1Affine>,
@@ -137,51 +159,49 @@ impl SnarkWitness {
     }
 }
 
-pub fn aggregate<'a, 'b>(
+/// Assigns instances of `snark` and succinctly verifies it, then returns the
+/// assigned instances and accumulators, where the accumulator in instances of
+/// `snark` if any is decoded by limbs of `P`.
+pub fn succinct_verify<'a, 'b, P: Halo2VerifierCircuitParams>(
     svk: &Svk,
     loader: &Rc<Halo2Loader<'a, 'b>>,
-    snarks: &[SnarkWitness],
-    as_vk: &AsVk,
-    as_proof: Value<&'_ [u8]>,
-    expose_instances: bool,
-) -> Vec<AssignedValue<Fr>> {
-    let assign_instances = |instances: &[Vec<Value<Fr>>]| {
-        instances
-            .iter()
-            .map(|instances| {
-                instances.iter().map(|instance| loader.assign_scalar(*instance)).collect_vec()
-            })
-            .collect_vec()
-    };
-
-    let mut instances_to_expose = vec![];
-    let mut accumulators = snarks
+    snark: &SnarkWitness,
+) -> (Vec<Vec<Scalar<'a, 'b>>>, Vec<Accumulator<'a, 'b>>) {
+    let instances = snark
+        .instances
         .iter()
-        .flat_map(|snark| {
-            let instances = assign_instances(&snark.instances);
-            if expose_instances {
-                instances_to_expose.extend(
-                    instances
-                        .iter()
-                        .flat_map(|instance| instance.iter().map(|scalar| scalar.assigned())),
-                );
-            }
-            let mut transcript =
-                PoseidonTranscript::<Rc<Halo2Loader>, _, _>::new(loader, snark.proof());
-            let proof =
-                Plonk::read_proof(svk, &snark.protocol, &instances, &mut transcript).unwrap();
-            Plonk::succinct_verify(svk, &snark.protocol, &instances, &proof).unwrap()
+        .map(|instances| {
+            instances.iter().map(|instance| loader.assign_scalar(*instance)).collect_vec()
         })
         .collect_vec();
+    let mut transcript = PoseidonTranscript::<Rc<Halo2Loader>, _, _>::new(loader, snark.proof());
+    let proof = Plonk::<P>::read_proof(svk, &snark.protocol, &instances, &mut transcript).unwrap();
+    let accumulators =
+        Plonk::<P>::succinct_verify(svk, &snark.protocol, &instances, &proof).unwrap();
+    (instances, accumulators)
+}
 
-    let KzgAccumulator { lhs, rhs } = if accumulators.len() > 1 {
+/// Accumulates `accumulators` by verifying `as_proof`, or returns the only one
+/// directly.
+pub fn accumulate<'a, 'b>(
+    loader: &Rc<Halo2Loader<'a, 'b>>,
+    mut accumulators: Vec<Accumulator<'a, 'b>>,
+    as_vk: &AsVk,
+    as_proof: Value<&'_ [u8]>,
+) -> Accumulator<'a, 'b> {
+    if accumulators.len() > 1 {
         let mut transcript = PoseidonTranscript::<Rc<Halo2Loader>, _, _>::new(loader, as_proof);
         let proof = As::read_proof(as_vk, &accumulators, &mut transcript).unwrap();
         As::verify(as_vk, &accumulators, &proof).unwrap()
     } else {
         accumulators.pop().unwrap()
-    };
+    }
+}
 
+/// Returns limbs of `accumulator` in order of `lhs.x`, `lhs.y`, `rhs.x` and
+/// `rhs.y`.
+pub fn accumulator_limbs(accumulator: &Accumulator) -> Vec<AssignedValue<Fr>> {
+    let KzgAccumulator { lhs, rhs } = accumulator;
     let lhs = lhs.assigned();
     let rhs = rhs.assigned();
 
@@ -192,12 +212,85 @@ pub fn aggregate<'a, 'b>(
         .chain(lhs.y.truncation.limbs.iter())
         .chain(rhs.x.truncation.limbs.iter())
         .chain(rhs.y.truncation.limbs.iter())
-        .chain(instances_to_expose.iter())
         .cloned()
         .collect_vec()
 }
 
-pub fn recursive_aggregate<'a, 'b>(
+pub fn aggregate<'a, 'b, P: Halo2VerifierCircuitParams>(
+    svk: &Svk,
+    loader: &Rc<Halo2Loader<'a, 'b>>,
+    snarks: &[SnarkWitness],
+    as_vk: &AsVk,
+    as_proof: Value<&'_ [u8]>,
+    expose_instances: bool,
+) -> Vec<AssignedValue<Fr>> {
+    let mut instances_to_expose = vec![];
+    let accumulators = snarks
+        .iter()
+        .flat_map(|snark| {
+            let (instances, accumulators) = succinct_verify::<P>(svk, loader, snark);
+            if expose_instances {
+                instances_to_expose.extend(
+                    instances
+                        .iter()
+                        .flat_map(|instance| instance.iter().map(|scalar| scalar.assigned())),
+                );
+            }
+            accumulators
+        })
+        .collect_vec();
+
+    let accumulator = accumulate(loader, accumulators, as_vk, as_proof);
+    accumulator_limbs(&accumulator).into_iter().chain(instances_to_expose).collect_vec()
+}
+
+/// Snark succinctly verified by its own `Halo2Loader` into a `Recorder`, which
+/// doesn't depend on any other snark so could be done in parallel.
+pub struct RecordedSnark {
+    recorder: Recorder<Fr>,
+    instances: Vec<RecordedValue<Fr>>,
+    accumulators: Vec<[RecordedEcPoint<Fr>; 2]>,
+    stats: CircuitStats,
+}
+
+impl RecordedSnark {
+    pub fn new<P: Halo2VerifierCircuitParams>(
+        config: &Halo2VerifierCircuitConfig,
+        svk: &Svk,
+        snark: &SnarkWitness,
+        msm_config: MsmConfig,
+    ) -> Self {
+        let mut recorder = Recorder::default();
+        let (instances, accumulators, stats) = {
+            let region = Region::from(&mut recorder as &mut dyn RegionLayouter<Fr>);
+            let ctx = config.base_field_config.new_context(region);
+            let loader = Halo2Loader::with_msm_config(&config.base_field_config, ctx, msm_config);
+
+            let (instances, accumulators) = succinct_verify::<P>(svk, &loader, snark);
+            let instances = instances
+                .iter()
+                .flatten()
+                .map(|instance| loader.record_scalar(instance))
+                .collect_vec();
+            let accumulators = accumulators
+                .iter()
+                .map(|KzgAccumulator { lhs, rhs }| {
+                    [lhs, rhs].map(|ec_point| loader.record_ec_point(ec_point))
+                })
+                .collect_vec();
+            (instances, accumulators, loader.finalize())
+        };
+
+        Self { recorder, instances, accumulators, stats }
+    }
+
+    /// Returns resources used by the succinct verification.
+    pub fn stats(&self) -> &CircuitStats {
+        &self.stats
+    }
+}
+
+pub fn recursive_aggregate<'a, 'b, P: Halo2VerifierCircuitParams>(
     svk: &Svk,
     loader: &Rc<Halo2Loader<'a, 'b>>,
     snarks: &[SnarkWitness],
@@ -229,8 +322,8 @@ pub fn recursive_aggregate<'a, 'b>(
             let mut transcript =
                 PoseidonTranscript::<Rc<Halo2Loader>, _, _>::new(loader, snark.proof());
             let proof =
-                Plonk::read_proof(svk, &snark.protocol, &instances, &mut transcript).unwrap();
-            Plonk::succinct_verify(svk, &snark.protocol, &instances, &proof).unwrap()
+                Plonk::<P>::read_proof(svk, &snark.protocol, &instances, &mut transcript).unwrap();
+            Plonk::<P>::succinct_verify(svk, &snark.protocol, &instances, &proof).unwrap()
         })
         .collect_vec();
 
@@ -240,10 +333,14 @@ pub fn recursive_aggregate<'a, 'b>(
     let mut accs = {
         let mut transcript =
             PoseidonTranscript::<Rc<Halo2Loader>, _, _>::new(loader, recursive_snark.proof());
-        let proof =
-            Plonk::read_proof(svk, &recursive_snark.protocol, &prev_instances, &mut transcript)
-                .unwrap();
-        let mut accs = Plonk::succinct_verify_or_dummy(
+        let proof = Plonk::<P>::read_proof(
+            svk,
+            &recursive_snark.protocol,
+            &prev_instances,
+            &mut transcript,
+        )
+        .unwrap();
+        let mut accs = Plonk::<P>::succinct_verify_or_dummy(
             svk,
             &recursive_snark.protocol,
             &prev_instances,
@@ -289,21 +386,206 @@ pub fn recursive_aggregate<'a, 'b>(
     (new_instances, assigned_instances)
 }
 
+/// Converts between little-endian bits of an integer and its big-endian bytes
+/// in bits, which is an involution.
+fn reverse_bytes<T: Clone>(bits: &[T]) -> Vec<T> {
+    bits.chunks(8).rev().flatten().cloned().collect()
+}
+
+/// Hash to commit to target instances with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstanceHash {
+    /// Poseidon with the same parameters as `PoseidonTranscript`, which is
+    /// cheap in circuit but costs a long subroutine on EVM.
+    Poseidon,
+    /// Keccak256 of instances as 32-byte big-endian words, reduced by the
+    /// scalar modulus, which is cheap on EVM but costs a lot in circuit.
+    Keccak,
+}
+
+impl InstanceHash {
+    /// Returns the commitment to `instances` computed natively.
+    pub fn commit(&self, instances: &[Fr]) -> Fr {
+        match self {
+            InstanceHash::Poseidon => {
+                let mut poseidon = PoseidonChip::<Fr, Fr, T, RATE>::new(NativeLoader, R_F, R_P);
+                poseidon.update(instances);
+                poseidon.squeeze()
+            }
+            InstanceHash::Keccak => {
+                let mut keccak = KeccakChip::<Fr, Fr>::new();
+                for instance in instances {
+                    let bytes = instance.to_repr().into_iter().rev().collect_vec();
+                    keccak.update(&Bit::from_bytes(&bytes));
+                }
+                bits::compose(&NativeLoader, &reverse_bytes(&keccak.squeeze()))
+            }
+        }
+    }
+
+    /// Returns the commitment to `instances` computed in circuit.
+    pub fn commit_in_circuit<'a, 'b>(
+        &self,
+        loader: &Rc<Halo2Loader<'a, 'b>>,
+        instances: &[Scalar<'a, 'b>],
+    ) -> Scalar<'a, 'b> {
+        match self {
+            InstanceHash::Poseidon => {
+                let mut poseidon =
+                    PoseidonChip::<Fr, Scalar, T, RATE>::new(loader.clone(), R_F, R_P);
+                poseidon.update(instances);
+                poseidon.squeeze()
+            }
+            InstanceHash::Keccak => {
+                let mut keccak = KeccakChip::new();
+                for instance in instances {
+                    let mut bits = loader.assign_canonical_scalar_bits(instance);
+                    bits.resize(0x100, Bit::Constant(false));
+                    keccak.update(&reverse_bytes(&bits));
+                }
+                bits::compose(loader, &reverse_bytes(&keccak.squeeze()))
+            }
+        }
+    }
+}
+
+/// How instances of target snarks are exposed after the accumulator.
+///
+/// Target instances are indexed as flattened in order of snarks, then their
+/// instance columns.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TargetInstances {
+    /// Target instances are not exposed.
+    #[default]
+    None,
+    /// Every target instance is exposed as is.
+    All,
+    /// A single commitment by `hash` to target instances at `indices`, or to
+    /// all of them if `indices` is `None`, is exposed.
+    Commitment { hash: InstanceHash, indices: Option<Vec<usize>> },
+}
+
+impl TargetInstances {
+    /// Returns target instances the commitment is computed over, which need
+    /// to be provided along with the proof for a verifier to check it.
+    pub fn committed<V: Clone>(&self, target_instances: &[V]) -> Vec<V> {
+        match self {
+            TargetInstances::Commitment { indices: Some(indices), .. } => indices
+                .iter()
+                .map(|idx| {
+                    target_instances.get(*idx).cloned().unwrap_or_else(|| {
+                        panic!(
+                            "Target instance index {} out of {} instances",
+                            idx,
+                            target_instances.len()
+                        )
+                    })
+                })
+                .collect(),
+            TargetInstances::Commitment { indices: None, .. } => target_instances.to_vec(),
+            TargetInstances::None | TargetInstances::All => Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "loader_evm")]
+mod evm {
+    use super::{InstanceHash, RATE, R_F, R_P, T};
+    use crate::{
+        loader::{
+            evm::{encode_calldata, loader::Value, EvmLoader, PoseidonSponge, Scalar},
+            ScalarLoader,
+        },
+        util::Itertools,
+    };
+    use halo2_curves::bn256::Fr;
+    use std::rc::Rc;
+
+    impl InstanceHash {
+        /// Generates code computing the commitment to `instances` on EVM.
+        pub fn commit_on_evm(&self, loader: &Rc<EvmLoader>, instances: &[Scalar]) -> Scalar {
+            match self {
+                InstanceHash::Poseidon => {
+                    let mut poseidon = PoseidonSponge::<Fr, T, RATE>::new(loader.clone(), R_F, R_P);
+                    poseidon.update(instances);
+                    poseidon.squeeze()
+                }
+                InstanceHash::Keccak => {
+                    let ptr = loader.allocate(instances.len() * 0x20);
+                    for (idx, instance) in instances.iter().enumerate() {
+                        loader.copy_scalar(instance, ptr + idx * 0x20);
+                    }
+                    let hash_ptr = loader.keccak256(ptr, instances.len() * 0x20);
+                    let commitment_ptr = loader.allocate(0x20);
+                    loader
+                        .code_mut()
+                        .push(loader.scalar_modulus())
+                        .push(hash_ptr)
+                        .mload()
+                        .r#mod()
+                        .push(commitment_ptr)
+                        .mstore();
+                    loader.scalar(Value::Memory(commitment_ptr))
+                }
+            }
+        }
+
+        /// Generates code checking `commitment` exposed by `AggregationCircuit`
+        /// to be the commitment to `instances`, which are usually loaded by
+        /// `load_instances` of the transcript right after reading the proof,
+        /// see [`encode_calldata_with_committed_instances`].
+        pub fn assert_commitment_on_evm(
+            &self,
+            loader: &Rc<EvmLoader>,
+            instances: &[Scalar],
+            commitment: &Scalar,
+        ) {
+            let computed = self.commit_on_evm(loader, instances);
+            loader.assert_eq("commitment to target instances", commitment, &computed).unwrap();
+        }
+    }
+
+    /// Returns calldata of `instances` and `proof` followed by instances the
+    /// exposed commitment is computed over.
+    pub fn encode_calldata_with_committed_instances(
+        instances: &[Vec<Fr>],
+        proof: &[u8],
+        committed_instances: &[Fr],
+    ) -> Vec<u8> {
+        encode_calldata(instances, proof)
+            .into_iter()
+            .chain(encode_calldata(&[committed_instances.to_vec()], &[]))
+            .collect_vec()
+    }
+}
+
+/// Circuit aggregating snarks into a single accumulator, configured by
+/// `P::params()`.
 #[derive(Clone)]
-pub struct AggregationCircuit {
+pub struct AggregationCircuit<P = DefaultVerifierCircuitParams> {
     svk: Svk,
     snarks: Vec<SnarkWitness>,
     pub instances: Vec<Fr>,
     as_vk: AsVk,
     as_proof: Value<Vec<u8>>,
-    expose_target_instances: bool,
+    target_instances: TargetInstances,
+    committed_instances: Vec<Fr>,
+    msm_config: MsmConfig,
+    stats: RefCell<Option<CircuitStats>>,
+    _marker: PhantomData<P>,
 }
 
-impl AggregationCircuit {
+impl<P: Halo2VerifierCircuitParams> AggregationCircuit<P> {
+    /// Returns an `AggregationCircuitBuilder` which takes `params` to compute
+    /// the accumulator, see [`AggregationCircuitBuilder`].
+    pub fn builder(params: &ParamsKZG<Bn256>) -> AggregationCircuitBuilder<'_, P> {
+        AggregationCircuitBuilder::new(params)
+    }
+
     pub fn new(
         params: &ParamsKZG<Bn256>,
         snarks: impl IntoIterator<Item = Snark>,
-        expose_target_instances: bool,
+        target_instances: TargetInstances,
     ) -> Self {
         let svk = params.get_g()[0].into();
         let snarks = snarks.into_iter().collect_vec();
@@ -313,10 +595,15 @@ impl AggregationCircuit {
             .flat_map(|snark| {
                 let mut transcript =
                     PoseidonTranscript::<NativeLoader, _, _>::new(snark.proof.as_slice());
-                let proof =
-                    Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript)
-                        .unwrap();
-                Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof).unwrap()
+                let proof = Plonk::<P>::read_proof(
+                    &svk,
+                    &snark.protocol,
+                    &snark.instances,
+                    &mut transcript,
+                )
+                .unwrap();
+                Plonk::<P>::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof)
+                    .unwrap()
             })
             .collect_vec();
 
@@ -336,10 +623,20 @@ impl AggregationCircuit {
         };
 
         let KzgAccumulator { lhs, rhs } = accumulator;
-        let mut instances =
-            [lhs.x, lhs.y, rhs.x, rhs.y].map(fe_to_limbs::<_, _, LIMBS, BITS>).concat();
-        if expose_target_instances {
-            instances.extend(snarks.iter().flat_map(|snark| snark.instances.iter().flatten()));
+        let limbs = P::params().limbs();
+        let mut instances = [lhs.x, lhs.y, rhs.x, rhs.y]
+            .into_iter()
+            .flat_map(|fe| limbs.fe_to_limbs::<_, Fr>(fe))
+            .collect_vec();
+        let flattened =
+            snarks.iter().flat_map(|snark| snark.instances.iter().flatten().cloned()).collect_vec();
+        let committed_instances = target_instances.committed(&flattened);
+        match &target_instances {
+            TargetInstances::None => {}
+            TargetInstances::All => instances.extend(flattened),
+            TargetInstances::Commitment { hash, .. } => {
+                instances.push(hash.commit(&committed_instances))
+            }
         }
 
         Self {
@@ -348,16 +645,31 @@ impl AggregationCircuit {
             instances,
             as_vk: as_pk.vk(),
             as_proof,
-            expose_target_instances,
+            target_instances,
+            committed_instances,
+            msm_config: MsmConfig::default(),
+            stats: Default::default(),
+            _marker: PhantomData,
         }
     }
 
+    /// Returns `Halo2VerifierCircuitConfigParams` the circuit is configured by.
+    pub fn config_params() -> Halo2VerifierCircuitConfigParams {
+        P::params()
+    }
+
+    /// Sets `MsmConfig` of `Halo2Loader` used in synthesis, which changes the
+    /// circuit so it needs to be set before keygen.
+    pub fn set_msm_config(mut self, msm_config: MsmConfig) -> Self {
+        self.msm_config = msm_config;
+        self
+    }
+
     pub fn accumulator_indices() -> Vec<(usize, usize)> {
-        (0..4 * LIMBS).map(|idx| (0, idx)).collect()
+        P::params().limbs().accumulator_indices()
     }
 
     pub fn num_instance(&self) -> Vec<usize> {
-        dbg!(self.instances.len());
         vec![self.instances.len()]
     }
 
@@ -369,54 +681,140 @@ impl AggregationCircuit {
         self.as_proof.as_ref().map(Vec::as_slice)
     }
 
+    /// Returns index of the commitment to target instances in instances,
+    /// which is right after the accumulator.
+    pub fn commitment_index() -> usize {
+        P::params().limbs().num_accumulator_limbs()
+    }
+
+    /// Returns how target instances are exposed.
+    pub fn target_instances(&self) -> &TargetInstances {
+        &self.target_instances
+    }
+
+    /// Returns target instances the exposed commitment is computed over, see
+    /// [`TargetInstances::committed`].
+    pub fn committed_instances(&self) -> &[Fr] {
+        &self.committed_instances
+    }
+
+    /// Returns resources used by the last synthesis.
+    pub fn stats(&self) -> Option<CircuitStats> {
+        self.stats.borrow().clone()
+    }
+
+    /// Returns the minimal `Halo2VerifierCircuitConfigParams` for this circuit
+    /// to fit in `2^k` rows, see [`tuner::tune_config`].
+    pub fn tune_config(&self, k: u32) -> Halo2VerifierCircuitConfigParams {
+        tuner::tune_config(self, k, FpStrategy::Simple, P::params().limbs(), Self::stats)
+    }
+
+    /// Synthesizes the aggregation and returns instances to expose, where
+    /// cells are the replayed ones.
+    ///
+    /// Each snark is succinctly verified into its own region by
+    /// [`RecordedSnark`] in parallel when feature `parallel` is enabled, then
+    /// regions are replayed in order of snarks so the layout is deterministic.
+    /// Accumulation is done in the last region with accumulators copied from
+    /// regions of snarks.
     pub fn synthesize_proof(
         &self,
         config: Halo2VerifierCircuitConfig,
         layouter: &mut impl Layouter<Fr>,
         instance_equalities: Vec<(usize, usize)>,
-    ) -> Result<Vec<AssignedValue<Fr>>, plonk::Error> {
+    ) -> Result<Vec<RecordedValue<Fr>>, plonk::Error> {
         config.base_field_config.load_lookup_table(layouter)?;
 
-        // Need to trick layouter to skip first pass in get shape mode
-        let using_simple_floor_planner = true;
-        let mut first_pass = true;
-        let mut assigned_instances = None;
-        layouter.assign_region(
-            || "",
-            |region| {
-                if using_simple_floor_planner && first_pass {
-                    first_pass = false;
-                    return Ok(());
-                }
-                let ctx = config.base_field_config.new_context(region);
-
-                let loader = Halo2Loader::new(&config.base_field_config, ctx);
-                let instances = aggregate(
-                    &self.svk,
-                    &loader,
-                    &self.snarks,
-                    &self.as_vk,
-                    self.as_proof(),
-                    self.expose_target_instances,
-                );
+        let (svk, msm_config) = (&self.svk, self.msm_config);
+        let record = |snark| RecordedSnark::new::<P>(&config, svk, snark, msm_config);
+        #[cfg(feature = "parallel")]
+        let snarks = self.snarks.par_iter().map(record).collect::<Vec<_>>();
+        #[cfg(not(feature = "parallel"))]
+        let snarks = self.snarks.iter().map(record).collect_vec();
+        let snark_cells = snarks
+            .iter()
+            .enumerate()
+            .map(|(idx, snark)| {
+                layouter.assign_region(
+                    || format!("snark {}", idx),
+                    |mut region| snark.recorder.replay(&mut region),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-                for &(i, j) in &instance_equalities {
-                    loader
-                        .ctx_mut()
-                        .region
-                        .constrain_equal(instances[i].cell(), instances[j].cell())?;
+        let mut recorder = Recorder::default();
+        let (instances, stats) = {
+            let region = Region::from(&mut recorder as &mut dyn RegionLayouter<Fr>);
+            let ctx = config.base_field_config.new_context(region);
+            let loader =
+                Halo2Loader::with_msm_config(&config.base_field_config, ctx, self.msm_config);
+
+            let accumulators = snarks
+                .iter()
+                .zip(snark_cells.iter())
+                .flat_map(|(snark, cells)| {
+                    snark
+                        .accumulators
+                        .iter()
+                        .map(|[lhs, rhs]| {
+                            KzgAccumulator::new(
+                                loader.copy_ec_point(lhs, cells),
+                                loader.copy_ec_point(rhs, cells),
+                            )
+                        })
+                        .collect_vec()
+                })
+                .collect_vec();
+            let accumulator = accumulate(&loader, accumulators, &self.as_vk, self.as_proof());
+
+            let target_instances = snarks
+                .iter()
+                .zip(snark_cells.iter())
+                .flat_map(|(snark, cells)| {
+                    snark.instances.iter().map(move |instance| (instance, cells))
+                })
+                .collect_vec();
+            let instances_to_expose = match &self.target_instances {
+                TargetInstances::None => Vec::new(),
+                // Instances of snarks are exposed from their replayed cells directly
+                TargetInstances::All => target_instances
+                    .iter()
+                    .map(|(instance, cells)| cells.get_value(instance))
+                    .collect_vec(),
+                TargetInstances::Commitment { hash, .. } => {
+                    let committed = self
+                        .target_instances
+                        .committed(&target_instances)
+                        .into_iter()
+                        .map(|(instance, cells)| loader.copy_scalar(instance, cells))
+                        .collect_vec();
+                    let commitment = hash.commit_in_circuit(&loader, &committed);
+                    vec![loader.record_scalar(&commitment)]
                 }
-                // REQUIRED STEP
-                loader.finalize();
-                assigned_instances = Some(instances);
-                Ok(())
-            },
-        )?;
-        Ok(assigned_instances.unwrap())
+            };
+            let instances = accumulator_limbs(&accumulator)
+                .iter()
+                .map(RecordedValue::from)
+                .chain(instances_to_expose)
+                .collect_vec();
+
+            for &(i, j) in &instance_equalities {
+                loader.ctx_mut().region.constrain_equal(instances[i].cell, instances[j].cell)?;
+            }
+            // REQUIRED STEP
+            let stats = loader.finalize();
+            (instances, stats)
+        };
+        let cells =
+            layouter.assign_region(|| "accumulation", |mut region| recorder.replay(&mut region))?;
+
+        *self.stats.borrow_mut() =
+            Some(snarks.into_iter().map(|snark| snark.stats).chain([stats]).sum());
+        Ok(instances.iter().map(|instance| cells.get_value(instance)).collect())
     }
 }
 
-impl Circuit<Fr> for AggregationCircuit {
+impl<P: Halo2VerifierCircuitParams> Circuit<Fr> for AggregationCircuit<P> {
     type Config = Halo2VerifierCircuitConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
@@ -427,18 +825,16 @@ impl Circuit<Fr> for AggregationCircuit {
             instances: Vec::new(),
             as_vk: self.as_vk,
             as_proof: Value::unknown(),
-            expose_target_instances: self.expose_target_instances,
+            target_instances: self.target_instances.clone(),
+            committed_instances: Vec::new(),
+            msm_config: self.msm_config,
+            stats: Default::default(),
+            _marker: PhantomData,
         }
     }
 
     fn configure(meta: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
-        let path = std::env::var("VERIFY_CONFIG").expect("export VERIFY_CONFIG with config path");
-        let params: Halo2VerifierCircuitConfigParams = serde_json::from_reader(
-            File::open(path.as_str()).expect(format!("{} file should exist", path).as_str()),
-        )
-        .unwrap();
-
-        Halo2VerifierCircuitConfig::configure(meta, params)
+        Halo2VerifierCircuitConfig::configure(meta, P::params())
     }
 
     fn synthesize(
@@ -452,16 +848,65 @@ impl Circuit<Fr> for AggregationCircuit {
             // TODO: use less instances by following Scroll's strategy of keeping only last bit of y coordinate
             let mut layouter = layouter.namespace(|| "expose");
             for (i, assigned_instance) in assigned_instances.iter().enumerate() {
-                layouter.constrain_instance(
-                    assigned_instance.cell().clone(),
-                    config_instance,
-                    i,
-                )?;
+                layouter.constrain_instance(assigned_instance.cell, config_instance, i)?;
             }
         })
     }
 }
 
+/// Builder of `AggregationCircuit`, where snarks and options changing the
+/// circuit are all set explicitly.
+pub struct AggregationCircuitBuilder<'a, P = DefaultVerifierCircuitParams> {
+    params: &'a ParamsKZG<Bn256>,
+    snarks: Vec<Snark>,
+    target_instances: TargetInstances,
+    msm_config: MsmConfig,
+    _marker: PhantomData<P>,
+}
+
+impl<'a, P: Halo2VerifierCircuitParams> AggregationCircuitBuilder<'a, P> {
+    pub fn new(params: &'a ParamsKZG<Bn256>) -> Self {
+        Self {
+            params,
+            snarks: Vec::new(),
+            target_instances: TargetInstances::None,
+            msm_config: MsmConfig::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn with_snarks(mut self, snarks: impl IntoIterator<Item = Snark>) -> Self {
+        self.snarks.extend(snarks);
+        self
+    }
+
+    /// Sets whether instances of snarks are all exposed after the accumulator,
+    /// which is a shorthand of [`Self::set_target_instances`].
+    pub fn set_expose_target_instances(mut self, expose_target_instances: bool) -> Self {
+        self.target_instances =
+            if expose_target_instances { TargetInstances::All } else { TargetInstances::None };
+        self
+    }
+
+    /// Sets how instances of snarks are exposed after the accumulator, where
+    /// a commitment costs a single instance however many target instances.
+    pub fn set_target_instances(mut self, target_instances: TargetInstances) -> Self {
+        self.target_instances = target_instances;
+        self
+    }
+
+    pub fn set_msm_config(mut self, msm_config: MsmConfig) -> Self {
+        self.msm_config = msm_config;
+        self
+    }
+
+    pub fn build(self) -> AggregationCircuit<P> {
+        assert!(!self.snarks.is_empty(), "AggregationCircuit needs at least one snark");
+        AggregationCircuit::new(self.params, self.snarks, self.target_instances)
+            .set_msm_config(self.msm_config)
+    }
+}
+
 pub fn gen_srs(k: u32) -> ParamsKZG<Bn256> {
     read_or_create_srs::<G1Affine, _>(k, |k| {
         ParamsKZG::<Bn256>::setup(k, ChaCha20Rng::from_seed(Default::default()))
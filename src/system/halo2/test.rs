@@ -1,4 +1,4 @@
-use super::{read_or_create_srs, Halo2VerifierCircuitConfigParams};
+use super::{read_or_create_srs, DefaultVerifierCircuitParams, Halo2VerifierCircuitParams};
 use ark_std::{end_timer, start_timer};
 use halo2_proofs::{
     plonk::{create_proof, verify_proof, Circuit, ProvingKey},
@@ -14,12 +14,7 @@ use std::io::Cursor;
 mod kzg;
 
 pub fn load_verify_circuit_degree() -> u32 {
-    let path = "./configs/verify_circuit.config";
-    let params_str =
-        std::fs::read_to_string(path).expect(format!("{} file should exist", path).as_str());
-    let params: Halo2VerifierCircuitConfigParams =
-        serde_json::from_str(params_str.as_str()).unwrap();
-    params.degree
+    DefaultVerifierCircuitParams::params().degree
 }
 
 pub fn create_proof_checked<'a, S, C, P, V, VS, TW, TR, EC, R>(
@@ -10,6 +10,9 @@ mod evm;
 #[cfg(feature = "loader_halo2")]
 pub(crate) mod halo2;
 
+#[cfg(all(feature = "loader_evm", feature = "loader_halo2", feature = "evm_runtime"))]
+pub(crate) mod transcript;
+
 #[allow(dead_code)]
 pub const TESTDATA_DIR: &str = "./src/system/halo2/test/data";
 
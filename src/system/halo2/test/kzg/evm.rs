@@ -1,12 +1,12 @@
 use crate::{
     loader::{halo2::test::StandardPlonk, native::NativeLoader},
-    pcs::kzg::{Bdfg21, Gwc19, Kzg, LimbsEncoding},
+    pcs::kzg::{Bdfg21, ConstLimbs, Gwc19, Kzg, LimbsEncoding},
     system::halo2::{
         test::kzg::{
             self, halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_native_verify,
             halo2_kzg_prepare, BITS, LIMBS,
         },
-        transcript::evm::{ChallengeEvm, EvmTranscript},
+        transcript::evm::{ChallengeEvm, CompressedEvmTranscript, EvmTranscript},
     },
     verifier::Plonk,
 };
@@ -16,7 +16,10 @@ use paste::paste;
 use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
 
 macro_rules! halo2_kzg_evm_verify {
-    ($plonk_verifier:ty, $params:expr, $protocol:expr, $instances:expr, $proof:expr) => {{
+    ($plonk_verifier:ty, $params:expr, $protocol:expr, $instances:expr, $proof:expr) => {
+        halo2_kzg_evm_verify!($plonk_verifier, $params, $protocol, $instances, $proof, false)
+    };
+    ($plonk_verifier:ty, $params:expr, $protocol:expr, $instances:expr, $proof:expr, $compressed:expr) => {{
         use halo2_curves::bn256::{Bn256, Fq, Fr};
         use halo2_proofs::poly::commitment::ParamsProver;
         use std::rc::Rc;
@@ -30,11 +33,15 @@ macro_rules! halo2_kzg_evm_verify {
             verifier::PlonkVerifier,
         };
 
-        let loader = EvmLoader::new::<Fq, Fr>();
+        let loader = EvmLoader::new_with_gas_metering::<Fq, Fr>();
         let runtime_code = {
             let svk = $params.get_g()[0].into();
             let dk = ($params.g2(), $params.s_g2()).into();
-            let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(loader.clone());
+            let mut transcript = if $compressed {
+                EvmTranscript::<_, Rc<EvmLoader>, _, _>::new_compressed(loader.clone())
+            } else {
+                EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(loader.clone())
+            };
             let instances = transcript
                 .load_instances($instances.iter().map(|instances| instances.len()).collect_vec());
             let proof = <$plonk_verifier>::read_proof(&svk, $protocol, &instances, &mut transcript)
@@ -44,13 +51,21 @@ macro_rules! halo2_kzg_evm_verify {
             loader.runtime_code()
         };
 
-        let (accept, total_cost, costs) =
-            execute(runtime_code, encode_calldata($instances, &$proof));
+        let calldata = encode_calldata($instances, &$proof);
+        let (accept, total_cost, costs) = execute(runtime_code, calldata.clone());
 
         loader.print_gas_metering(costs);
         println!("Total gas cost: {}", total_cost);
 
         assert!(accept);
+
+        let estimation = loader.estimate_gas().unwrap();
+        println!("Estimated gas cost: {}", estimation.total());
+        assert_eq!(estimation.calldata_len, calldata.len());
+        assert!(estimation.total().abs_diff(total_cost) * 100 <= total_cost * 2);
+
+        let report = loader.gas_report(calldata).unwrap();
+        println!("{:#?}", report);
     }};
 }
 
@@ -93,11 +108,11 @@ macro_rules! test {
         }
     };
     ($name:ident, $k:expr, $config:expr, $create_circuit:expr) => {
-        test!(@ #[test], shplonk, $name, $k, $config, $create_circuit, ProverSHPLONK<_>, VerifierSHPLONK<_>, Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<LIMBS, BITS>>);
-        test!(@ #[test], plonk, $name, $k, $config, $create_circuit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<LIMBS, BITS>>);
+        test!(@ #[test], shplonk, $name, $k, $config, $create_circuit, ProverSHPLONK<_>, VerifierSHPLONK<_>, Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>);
+        test!(@ #[test], plonk, $name, $k, $config, $create_circuit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>);
     };
     ($(#[$attr:meta],)* $name:ident, $k:expr, $config:expr, $create_circuit:expr) => {
-        test!(@ #[test] $(,#[$attr])*, plonk, $name, $k, $config, $create_circuit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<LIMBS, BITS>>);
+        test!(@ #[test] $(,#[$attr])*, plonk, $name, $k, $config, $create_circuit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>);
     };
 }
 
@@ -123,3 +138,734 @@ test!(
     halo2_kzg_config!(true, 1, (0..4 * LIMBS).map(|idx| (0, idx)).collect()),
     kzg::halo2::Accumulation::two_snark_with_accumulator()
 );
+
+#[test]
+fn test_shplonk_zk_standard_plonk_rand_compressed() {
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        CompressedEvmTranscript<G1Affine, _>,
+        CompressedEvmTranscript<G1Affine, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+    halo2_kzg_native_verify!(
+        Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>,
+        params,
+        &snark.protocol,
+        &snark.instances,
+        &mut EvmTranscript::<_, NativeLoader, _, _>::new_compressed(snark.proof.as_slice())
+    );
+    halo2_kzg_evm_verify!(
+        Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>,
+        params,
+        &snark.protocol,
+        &snark.instances,
+        snark.proof,
+        true
+    );
+}
+
+#[cfg(feature = "evm_runtime")]
+#[test]
+fn test_shplonk_zk_standard_plonk_rand_fail_fast() {
+    use crate::{
+        loader::evm::{encode_calldata, revert_pc, runtime, EvmLoader},
+        util::Itertools,
+        verifier::PlonkVerifier,
+    };
+    use halo2_curves::bn256::{Fq, Fr};
+    use halo2_proofs::poly::commitment::ParamsProver;
+    use std::rc::Rc;
+
+    type Pv = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        EvmTranscript<G1Affine, _, _, _>,
+        EvmTranscript<G1Affine, _, _, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let loader = EvmLoader::new::<Fq, Fr>();
+    loader.set_fail_fast(true);
+    let runtime_code = {
+        let svk = params.get_g()[0].into();
+        let dk = (params.g2(), params.s_g2()).into();
+        let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(loader.clone());
+        let instances = transcript
+            .load_instances(snark.instances.iter().map(|instances| instances.len()).collect_vec());
+        let proof = Pv::read_proof(&svk, &snark.protocol, &instances, &mut transcript).unwrap();
+        Pv::verify(&svk, &dk, &snark.protocol, &instances, &proof).unwrap();
+
+        loader.runtime_code()
+    };
+    let source_map = loader.source_map();
+    let listing = source_map.listing(&runtime_code);
+    assert!(listing.contains("; challenge 0"));
+    assert!(listing.contains("; msm term 0"));
+
+    let mut calldata = encode_calldata(&snark.instances, &snark.proof);
+    assert!(runtime::call(runtime_code.clone(), calldata.clone()).success);
+
+    // Make y of the first commitment off curve
+    let num_instance = snark.instances.iter().map(Vec::len).sum::<usize>();
+    calldata[num_instance * 0x20 + 0x3f] ^= 1;
+    let output = runtime::call(runtime_code, calldata);
+    assert!(!output.success);
+    let pc = revert_pc(&output.return_data).unwrap();
+    assert_eq!(
+        source_map.describe(pc).unwrap(),
+        format!("transcript / proof element 0 at calldata {:#x}", num_instance * 0x20)
+    );
+}
+
+#[cfg(feature = "evm_runtime")]
+#[test]
+fn test_shplonk_zk_standard_plonk_rand_return_accumulator() {
+    use crate::{
+        loader::evm::{encode_calldata, runtime, AccumulatorOutput, EvmLoader},
+        pcs::{
+            kzg::{decode_accumulator, fold_accumulators, KzgDecidingKey},
+            Decider,
+        },
+        util::Itertools,
+        verifier::PlonkVerifier,
+    };
+    use halo2_curves::bn256::{Fq, Fr};
+    use halo2_proofs::poly::commitment::ParamsProver;
+    use std::rc::Rc;
+
+    type Pv = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        EvmTranscript<G1Affine, _, _, _>,
+        EvmTranscript<G1Affine, _, _, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let svk = params.get_g()[0].into();
+    let dk: KzgDecidingKey<Bn256> = (params.g2(), params.s_g2()).into();
+    let loader = EvmLoader::new::<Fq, Fr>();
+    loader.set_accumulator_output(AccumulatorOutput::Return);
+    let runtime_code = {
+        let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(loader.clone());
+        let instances = transcript
+            .load_instances(snark.instances.iter().map(|instances| instances.len()).collect_vec());
+        let proof = Pv::read_proof(&svk, &snark.protocol, &instances, &mut transcript).unwrap();
+        Pv::verify(&svk, &dk, &snark.protocol, &instances, &proof).unwrap();
+
+        loader.runtime_code()
+    };
+
+    let calldata = encode_calldata(&snark.instances, &snark.proof);
+    let output = runtime::call(runtime_code, calldata);
+    assert!(output.success);
+
+    let accumulator = decode_accumulator::<G1Affine>(&output.return_data).unwrap();
+    assert!(<Kzg<Bn256, Bdfg21> as Decider<_, NativeLoader>>::decide(&dk, accumulator.clone()));
+    let folded = fold_accumulators(vec![accumulator.clone(), accumulator]);
+    assert!(<Kzg<Bn256, Bdfg21> as Decider<_, NativeLoader>>::decide(&dk, folded));
+}
+
+#[cfg(all(feature = "loader_halo2", feature = "evm_runtime"))]
+#[test]
+fn test_shplonk_zk_standard_plonk_rand_poseidon() {
+    use crate::{
+        loader::evm::{encode_calldata, runtime, EvmLoader, PoseidonSponge},
+        system::halo2::{
+            aggregation::{PoseidonTranscript, RATE, T},
+            transcript::halo2::ChallengeScalar,
+        },
+        util::Itertools,
+        verifier::PlonkVerifier,
+    };
+    use halo2_curves::bn256::{Fq, Fr};
+    use halo2_proofs::poly::commitment::ParamsProver;
+    use std::rc::Rc;
+
+    type Pv = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        PoseidonTranscript<_, _, _>,
+        PoseidonTranscript<_, _, _>,
+        ChallengeScalar<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+    halo2_kzg_native_verify!(
+        Pv,
+        params,
+        &snark.protocol,
+        &snark.instances,
+        &mut PoseidonTranscript::<NativeLoader, _, _>::new(snark.proof.as_slice())
+    );
+
+    let loader = EvmLoader::new::<Fq, Fr>();
+    let runtime_code = {
+        let svk = params.get_g()[0].into();
+        let dk = (params.g2(), params.s_g2()).into();
+        let mut transcript =
+            PoseidonTranscript::<Rc<EvmLoader>, _, PoseidonSponge<Fr, T, RATE>>::new(
+                loader.clone(),
+            );
+        let instances = transcript
+            .load_instances(snark.instances.iter().map(|instances| instances.len()).collect_vec());
+        let proof = Pv::read_proof(&svk, &snark.protocol, &instances, &mut transcript).unwrap();
+        Pv::verify(&svk, &dk, &snark.protocol, &instances, &proof).unwrap();
+
+        loader.runtime_code()
+    };
+    println!("Runtime code size: {}", runtime_code.len());
+
+    let mut calldata = encode_calldata(&snark.instances, &snark.proof);
+    let output = runtime::call(runtime_code.clone(), calldata.clone());
+    println!("Total gas cost: {}", output.gas_used);
+    assert!(output.success);
+
+    // Flip a bit of the last proof element
+    let last = calldata.len() - 0x20;
+    calldata[last] ^= 1;
+    assert!(!runtime::call(runtime_code, calldata).success);
+}
+
+#[cfg(feature = "evm_runtime")]
+#[test]
+fn test_shplonk_zk_standard_plonk_rand_sha256() {
+    use crate::{
+        loader::evm::{encode_calldata, runtime, EvmLoader},
+        system::halo2::transcript::sha256::{ChallengeSha256, Sha256Transcript},
+        util::Itertools,
+        verifier::PlonkVerifier,
+    };
+    use halo2_curves::bn256::{Fq, Fr};
+    use halo2_proofs::poly::commitment::ParamsProver;
+    use std::rc::Rc;
+
+    type Pv = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        Sha256Transcript<G1Affine, _, _, _>,
+        Sha256Transcript<G1Affine, _, _, _>,
+        ChallengeSha256<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+    halo2_kzg_native_verify!(
+        Pv,
+        params,
+        &snark.protocol,
+        &snark.instances,
+        &mut Sha256Transcript::<_, NativeLoader, _, _>::new(snark.proof.as_slice())
+    );
+
+    let loader = EvmLoader::new::<Fq, Fr>();
+    let runtime_code = {
+        let svk = params.get_g()[0].into();
+        let dk = (params.g2(), params.s_g2()).into();
+        let mut transcript = Sha256Transcript::<_, Rc<EvmLoader>, _, _>::new(loader.clone());
+        let instances = transcript
+            .load_instances(snark.instances.iter().map(|instances| instances.len()).collect_vec());
+        let proof = Pv::read_proof(&svk, &snark.protocol, &instances, &mut transcript).unwrap();
+        Pv::verify(&svk, &dk, &snark.protocol, &instances, &proof).unwrap();
+
+        loader.runtime_code()
+    };
+
+    let mut calldata = encode_calldata(&snark.instances, &snark.proof);
+    let output = runtime::call(runtime_code.clone(), calldata.clone());
+    println!("Total gas cost: {}", output.gas_used);
+    assert!(output.success);
+
+    // Flip a bit of the last proof element
+    let last = calldata.len() - 0x20;
+    calldata[last] ^= 1;
+    assert!(!runtime::call(runtime_code, calldata).success);
+}
+
+#[cfg(all(feature = "loader_halo2", feature = "evm_runtime"))]
+#[test]
+fn test_shplonk_zk_standard_plonk_rand_poseidon_transcript_divergence() {
+    use crate::system::halo2::{
+        aggregation::PoseidonTranscript,
+        test::kzg::transcript::{
+            first_divergence, transcript_divergence, Element, Poseidon, ProofFormat, Step,
+        },
+        transcript::halo2::ChallengeScalar,
+    };
+    use halo2_curves::bn256::Fr;
+    use halo2_proofs::poly::commitment::ParamsProver;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        PoseidonTranscript<_, _, _>,
+        PoseidonTranscript<_, _, _>,
+        ChallengeScalar<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    // Divergence is reported at the first different step
+    let native = Poseidon::native(&params.get_g()[0].into(), &snark);
+    assert!(native.iter().any(|record| {
+        record.step == Step::SqueezeChallenge && record.label.as_deref() == Some("z")
+    }));
+    let mut tampered = native.clone();
+    tampered[3].element = Element::Scalar(Fr::from(1));
+    let divergence =
+        first_divergence(&[("native", native.clone()), ("tampered", tampered)]).unwrap();
+    assert_eq!(divergence.index, 3);
+    assert!(first_divergence(&[("native", native.clone()), ("native", native)]).is_none());
+
+    if let Some(divergence) = transcript_divergence::<Poseidon>(&params, snark) {
+        panic!("{}", divergence);
+    }
+}
+
+#[cfg(all(feature = "loader_halo2", feature = "evm_runtime"))]
+#[test]
+#[ignore = "cause it requires keccak in circuit which is too large for MockProver"]
+fn test_shplonk_zk_standard_plonk_rand_evm_transcript_divergence() {
+    use crate::system::halo2::test::kzg::transcript::{transcript_divergence, Evm};
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        EvmTranscript<G1Affine, _, _, _>,
+        EvmTranscript<G1Affine, _, _, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    if let Some(divergence) = transcript_divergence::<Evm>(&params, snark) {
+        panic!("{}", divergence);
+    }
+}
+
+#[cfg(all(feature = "loader_halo2", feature = "evm_runtime"))]
+#[test]
+fn test_aggregation_instance_commitment() {
+    use crate::{
+        loader::evm::{runtime, EvmLoader},
+        system::halo2::aggregation::{encode_calldata_with_committed_instances, InstanceHash},
+        util::Itertools,
+    };
+    use halo2_curves::bn256::{Fq, Fr};
+    use rand_chacha::rand_core::RngCore;
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    // More than rate of both hashes to cover absorbing multiple blocks
+    let committed_instances = (0..5).map(|_| Fr::from(rng.next_u64())).collect_vec();
+
+    for hash in [InstanceHash::Poseidon, InstanceHash::Keccak] {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let runtime_code = {
+            let commitment = loader.calldataload_scalar(0);
+            let committed_instances = (0..committed_instances.len())
+                .map(|idx| loader.calldataload_scalar(0x20 * (idx + 1)))
+                .collect_vec();
+            hash.assert_commitment_on_evm(&loader, &committed_instances, &commitment);
+
+            loader.runtime_code()
+        };
+
+        let commitment = hash.commit(&committed_instances);
+        let calldata = |commitment| {
+            encode_calldata_with_committed_instances(&[vec![commitment]], &[], &committed_instances)
+        };
+        assert!(runtime::call(runtime_code.clone(), calldata(commitment)).success);
+        assert!(!runtime::call(runtime_code, calldata(commitment + Fr::from(1))).success);
+    }
+}
+
+#[test]
+fn test_estimate_runtime_gas_unsupported_code() {
+    use crate::loader::evm::{estimate_runtime_gas, EvmTarget};
+
+    let target = EvmTarget::default();
+    // `PUSH1 0x01 PUSH1 0x02 ADD STOP`
+    let estimation = estimate_runtime_gas(&[0x60, 0x01, 0x60, 0x02, 0x01, 0x00], &target).unwrap();
+    assert_eq!(estimation.execution, 9);
+    // `INVALID`
+    assert!(estimate_runtime_gas(&[0xfe], &target).is_err());
+    // `PUSH1 0x01 ADD` underflows the stack
+    assert!(estimate_runtime_gas(&[0x60, 0x01, 0x01], &target).is_err());
+}
+
+#[cfg(feature = "evm_runtime")]
+#[test]
+fn test_shplonk_zk_standard_plonk_rand_cancun_without_big_mod_exp() {
+    use crate::{
+        loader::evm::{disassemble, encode_calldata, runtime, EvmLoader, EvmTarget, Precompiles},
+        util::Itertools,
+        verifier::PlonkVerifier,
+    };
+    use halo2_curves::bn256::{Fq, Fr};
+    use halo2_proofs::poly::commitment::ParamsProver;
+    use std::rc::Rc;
+
+    type Pv = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        EvmTranscript<G1Affine, _, _, _>,
+        EvmTranscript<G1Affine, _, _, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let target = EvmTarget::cancun()
+        .with_precompiles(Precompiles { big_mod_exp: None, ..Default::default() });
+    let loader = EvmLoader::new_with_target::<Fq, Fr>(target).unwrap();
+    let deployment_code = {
+        let svk = params.get_g()[0].into();
+        let dk = (params.g2(), params.s_g2()).into();
+        let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(loader.clone());
+        let instances = transcript
+            .load_instances(snark.instances.iter().map(|instances| instances.len()).collect_vec());
+        let proof = Pv::read_proof(&svk, &snark.protocol, &instances, &mut transcript).unwrap();
+        Pv::verify(&svk, &dk, &snark.protocol, &instances, &proof).unwrap();
+
+        loader.deployment_code()
+    };
+
+    // `PUSH0` and `MCOPY` are emitted and executed
+    let opcodes = disassemble(&loader.runtime_code())
+        .into_iter()
+        .map(|instruction| instruction.opcode)
+        .collect_vec();
+    assert!(opcodes.contains(&0x5f));
+    assert!(opcodes.contains(&0x5e));
+
+    let calldata = encode_calldata(&snark.instances, &snark.proof);
+    assert!(runtime::deploy_and_call(deployment_code, calldata).success);
+}
+
+#[cfg(feature = "evm_runtime")]
+#[test]
+fn test_evm_invert_without_big_mod_exp() {
+    use crate::{
+        loader::{
+            evm::{encode_calldata, runtime, EvmLoader, EvmTarget, Precompiles},
+            FieldOps, ScalarLoader,
+        },
+        util::arithmetic::Field,
+    };
+    use halo2_curves::bn256::{Fq, Fr};
+
+    let target = EvmTarget::default()
+        .with_precompiles(Precompiles { big_mod_exp: None, ..Default::default() });
+    let loader = EvmLoader::new_with_target::<Fq, Fr>(target).unwrap();
+    let runtime_code = {
+        let scalar = loader.calldataload_scalar(0);
+        let inv = scalar.invert().unwrap();
+        let one = ScalarLoader::<Fr>::load_one(&loader);
+        ScalarLoader::<Fr>::assert_eq(&loader, "scalar * inv == 1", &(scalar * inv), &one).unwrap();
+
+        loader.runtime_code()
+    };
+
+    let scalar = Fr::random(ChaCha20Rng::from_seed(Default::default()));
+    let calldata = |scalar| encode_calldata(&[vec![scalar]], &[]);
+    assert!(runtime::call(runtime_code.clone(), calldata(scalar)).success);
+    // `pow_mod` of zero results zero
+    assert!(!runtime::call(runtime_code, calldata(Fr::zero())).success);
+}
+
+#[cfg(feature = "evm_runtime")]
+#[test]
+fn test_evm_code_size_limit() {
+    use crate::loader::evm::{runtime, EvmLoader, EvmTarget};
+    use halo2_curves::bn256::{Fq, Fr};
+
+    let target = EvmTarget::default().with_code_size_limit(None);
+    let loader = EvmLoader::new_with_target::<Fq, Fr>(target).unwrap();
+    while loader.runtime_code().len() <= 0x6000 {
+        loader.calldataload_scalar(0);
+    }
+
+    // Deployment is rejected by EIP-170, while the code itself runs fine
+    assert!(!runtime::deploy_and_call(loader.deployment_code(), vec![0; 0x20]).success);
+    assert!(runtime::call(loader.runtime_code(), vec![0; 0x20]).success);
+}
+
+#[test]
+fn test_evm_target_without_fallback() {
+    use crate::loader::evm::{EvmLoader, EvmTarget, Precompiles};
+    use halo2_curves::bn256::{Fq, Fr};
+
+    let target = EvmTarget::default()
+        .with_precompiles(Precompiles { bn254_pairing: None, ..Default::default() });
+    assert!(target.validate().is_err());
+    assert!(EvmLoader::new_with_target::<Fq, Fr>(target).is_err());
+}
+
+#[cfg(all(feature = "loader_halo2", feature = "evm_runtime"))]
+#[test]
+fn test_hash_transcript_known_answers() {
+    use crate::{
+        loader::{
+            evm::{encode_calldata, runtime, EvmLoader, Scalar},
+            ScalarLoader,
+        },
+        system::halo2::transcript::halo2::HashTranscript,
+        util::{
+            arithmetic::Field,
+            hash::{HashParams, Poseidon2Params, PoseidonParams, Sponge},
+            transcript::{Transcript, TranscriptRead, TranscriptWrite},
+        },
+    };
+    use halo2_curves::{
+        bn256::{Fq, Fr, G1},
+        group::{Curve, Group},
+    };
+    use std::rc::Rc;
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let scalar = Fr::random(&mut rng);
+    let ec_point = G1::random(&mut rng).to_affine();
+
+    for params in [
+        HashParams::from(PoseidonParams::<Fr>::circom(3)),
+        Poseidon2Params::<Fr>::bn254_t3().into(),
+    ] {
+        let (proof, challenges) = {
+            let mut transcript = HashTranscript::<G1Affine, NativeLoader, _, _>::new(
+                Vec::new(),
+                Sponge::<Fr, Fr>::new(&NativeLoader, params.clone()),
+            );
+            transcript.write_scalar(scalar).unwrap();
+            let challenge_0 = transcript.squeeze_challenge();
+            transcript.write_ec_point(ec_point).unwrap();
+            let challenge_1 = transcript.squeeze_challenge();
+            // Squeezing without absorbing
+            let challenge_2 = transcript.squeeze_challenge();
+            (transcript.finalize(), [challenge_0, challenge_1, challenge_2])
+        };
+
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let runtime_code = {
+            let mut transcript = HashTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(
+                loader.clone(),
+                Sponge::<Fr, Scalar>::new(&loader, params),
+            );
+            transcript.read_scalar().unwrap();
+            let challenge_0 = transcript.squeeze_challenge();
+            transcript.read_ec_point().unwrap();
+            let challenge_1 = transcript.squeeze_challenge();
+            let challenge_2 = transcript.squeeze_challenge();
+            for (lhs, rhs) in [challenge_0, challenge_1, challenge_2].iter().zip(challenges) {
+                let rhs = loader.load_const(&rhs);
+                ScalarLoader::<Fr>::assert_eq(&loader, "challenge should match", lhs, &rhs)
+                    .unwrap();
+            }
+
+            loader.runtime_code()
+        };
+
+        let calldata = encode_calldata::<Fr>(&[], &proof);
+        assert!(runtime::call(runtime_code, calldata).success);
+    }
+}
+
+#[cfg(feature = "evm_runtime")]
+#[test]
+fn test_evm_ec_point_select() {
+    use crate::{
+        loader::{
+            evm::{encode_calldata, runtime, EvmLoader},
+            EcPointLoader, Loader,
+        },
+        util::arithmetic::{Coordinates, CurveAffine, PrimeField},
+    };
+    use halo2_curves::{
+        bn256::{Fq, Fr, G1},
+        group::{Curve, Group},
+    };
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let [a, b] = [(); 2].map(|_| G1::random(&mut rng).to_affine());
+    // Selects between `a` and `b` from calldata, and asserts the output to be
+    // `expected` if any
+    let runtime_code = |expected: Option<G1Affine>| {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let sel = loader.calldataload_scalar(0);
+        let [a, b] = [0x20, 0x60].map(|offset| loader.calldataload_ec_point(offset));
+        let output = Loader::<G1Affine>::ec_point_select(&loader, &a, &b, &sel).unwrap();
+        if let Some(expected) = expected {
+            let expected = loader.ec_point_load_const(&expected);
+            EcPointLoader::<G1Affine>::ec_point_assert_eq(
+                &loader,
+                "output should match",
+                &output,
+                &expected,
+            )
+            .unwrap();
+        }
+        loader.runtime_code()
+    };
+    let calldata = |sel: u64| {
+        let points = [a, b]
+            .iter()
+            .flat_map(|ec_point| {
+                let coordinates = Option::<Coordinates<_>>::from(ec_point.coordinates()).unwrap();
+                [coordinates.x().to_repr(), coordinates.y().to_repr()]
+            })
+            .flat_map(|repr| repr.into_iter().rev())
+            .collect::<Vec<_>>();
+        encode_calldata(&[vec![Fr::from(sel)]], &points)
+    };
+
+    for (sel, expected, unexpected) in [(0, b, a), (1, a, b)] {
+        assert!(runtime::call(runtime_code(Some(expected)), calldata(sel)).success);
+        assert!(!runtime::call(runtime_code(Some(unexpected)), calldata(sel)).success);
+    }
+    // Non-bit selector reverts even without checking the output
+    assert!(runtime::call(runtime_code(None), calldata(1)).success);
+    assert!(!runtime::call(runtime_code(None), calldata(2)).success);
+}
+
+#[cfg(all(feature = "loader_halo2", feature = "evm_runtime"))]
+#[test]
+fn test_kzg_as_poseidon_with_gas_metering() {
+    use crate::{
+        loader::{
+            evm::{runtime, AccumulatorOutput, EvmLoader, PoseidonSponge},
+            EcPointLoader,
+        },
+        pcs::{
+            kzg::{decode_accumulator, KzgAccumulator, KzgDecidingKey},
+            AccumulationScheme, AccumulationSchemeProver, Decider,
+        },
+        system::halo2::aggregation::{As, AsPk, Pcs, PoseidonTranscript, RATE, T},
+        util::{transcript::Transcript, Itertools},
+    };
+    use halo2_curves::{
+        bn256::{Fq, Fr, G2Affine, G1},
+        group::{Curve, Group},
+    };
+    use std::rc::Rc;
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let [g, s_g] = [(); 2].map(|_| G1::random(&mut rng).to_affine());
+    let as_pk = AsPk::new(Some((g, s_g)));
+    let accumulators = (0..2)
+        .map(|_| {
+            KzgAccumulator::<_, NativeLoader>::new(
+                G1::random(&mut rng).to_affine(),
+                G1::random(&mut rng).to_affine(),
+            )
+        })
+        .collect_vec();
+
+    let mut transcript = PoseidonTranscript::<NativeLoader, _, _>::new(Vec::new());
+    transcript.squeeze_challenge();
+    let accumulator = As::create_proof(&as_pk, &accumulators, &mut transcript, &mut rng).unwrap();
+    let proof = transcript.finalize();
+
+    // Poseidon is squeezed both in a metered stage and out of it, which are
+    // at different stack depths
+    let loader = EvmLoader::new_with_gas_metering::<Fq, Fr>();
+    loader.set_accumulator_output(AccumulatorOutput::Return);
+    let runtime_code = {
+        let dk: KzgDecidingKey<Bn256> = (G2Affine::generator(), G2Affine::generator()).into();
+        let mut transcript =
+            PoseidonTranscript::<Rc<EvmLoader>, _, PoseidonSponge<Fr, T, RATE>>::new(
+                loader.clone(),
+            );
+        loader.start_gas_metering("transcript");
+        transcript.squeeze_challenge();
+        loader.end_gas_metering();
+        let accumulators = accumulators
+            .iter()
+            .map(|accumulator| {
+                KzgAccumulator::new(
+                    loader.ec_point_load_const(&accumulator.lhs),
+                    loader.ec_point_load_const(&accumulator.rhs),
+                )
+            })
+            .collect_vec();
+        let as_proof = As::read_proof(&as_pk.vk(), &accumulators, &mut transcript).unwrap();
+        let accumulator = As::verify(&as_pk.vk(), &accumulators, &as_proof).unwrap();
+        <Pcs as Decider<_, _>>::decide(&dk, accumulator);
+
+        loader.runtime_code()
+    };
+
+    let output = runtime::call(runtime_code, proof);
+    assert!(output.success);
+    let output = decode_accumulator::<G1Affine>(&output.return_data).unwrap();
+    assert_eq!((output.lhs, output.rhs), (accumulator.lhs, accumulator.rhs));
+}
@@ -1,17 +1,21 @@
 use crate::{
     loader::{
         self,
-        halo2::test::{Snark, SnarkWitness, StandardPlonk},
+        halo2::{
+            test::{Snark, SnarkWitness, StandardPlonk},
+            CircuitStats, MsmConfig, MsmStrategy,
+        },
         native::NativeLoader,
     },
     pcs::{
         kzg::{
-            Bdfg21, Kzg, KzgAccumulator, KzgAs, KzgAsProvingKey, KzgAsVerifyingKey,
+            Bdfg21, ConstLimbs, Kzg, KzgAccumulator, KzgAs, KzgAsProvingKey, KzgAsVerifyingKey,
             KzgSuccinctVerifyingKey, LimbsEncoding,
         },
-        AccumulationScheme, AccumulationSchemeProver,
+        AccumulationScheme, AccumulationSchemeProver, AccumulatorEncoding,
     },
     system::halo2::{
+        aggregation::{self, InstanceHash},
         test::{
             kzg::{
                 halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_native_verify,
@@ -20,9 +24,13 @@ use crate::{
             load_verify_circuit_degree,
         },
         transcript::halo2::{ChallengeScalar, PoseidonTranscript as GenericPoseidonTranscript},
-        Halo2VerifierCircuitConfig, Halo2VerifierCircuitConfigParams,
+        DefaultVerifierCircuitParams, Halo2VerifierCircuitConfig, Halo2VerifierCircuitConfigParams,
+        Halo2VerifierCircuitParams,
+    },
+    util::{
+        arithmetic::{fe_from_big, fe_to_big, fe_to_limbs, modulus},
+        Itertools,
     },
-    util::{arithmetic::fe_to_limbs, Itertools},
     verifier::{self, PlonkVerifier},
 };
 use ark_std::{end_timer, start_timer};
@@ -47,7 +55,9 @@ use halo2_proofs::{
 use paste::paste;
 use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
 use std::{
+    cell::RefCell,
     io::{Cursor, Read, Write},
+    marker::PhantomData,
     rc::Rc,
 };
 
@@ -64,7 +74,7 @@ type Svk = KzgSuccinctVerifyingKey<G1Affine>;
 type As = KzgAs<Pcs>;
 type AsPk = KzgAsProvingKey<G1Affine>;
 type AsVk = KzgAsVerifyingKey;
-type Plonk = verifier::Plonk<Pcs, LimbsEncoding<LIMBS, BITS>>;
+type Plonk = verifier::Plonk<Pcs, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>;
 
 pub fn accumulate<'a, 'b>(
     svk: &Svk,
@@ -111,6 +121,8 @@ pub struct Accumulation {
     instances: Vec<Fr>,
     as_vk: AsVk,
     as_proof: Value<Vec<u8>>,
+    msm_config: MsmConfig,
+    stats: RefCell<CircuitStats>,
 }
 
 impl Accumulation {
@@ -161,50 +173,44 @@ impl Accumulation {
             instances,
             as_vk: as_pk.vk(),
             as_proof,
+            msm_config: MsmConfig::default(),
+            stats: Default::default(),
         }
     }
 
+    pub fn set_msm_config(mut self, msm_config: MsmConfig) -> Self {
+        self.msm_config = msm_config;
+        self
+    }
+
     pub fn two_snark() -> Self {
-        let (params, snark1) = {
-            const K: u32 = 9;
-            let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
-                K,
-                halo2_kzg_config!(true, 1),
-                StandardPlonk::<_>::rand(ChaCha20Rng::from_seed(Default::default()))
-            );
-            let snark = halo2_kzg_create_snark!(
-                ProverSHPLONK<_>,
-                VerifierSHPLONK<_>,
-                PoseidonTranscript<_, _, _>,
-                PoseidonTranscript<_, _, _>,
-                ChallengeScalar<_>,
-                &params,
-                &pk,
-                &protocol,
-                &circuits
-            );
-            (params, snark)
-        };
-        let snark2 = {
-            const K: u32 = 9;
-            let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
-                K,
-                halo2_kzg_config!(true, 1),
-                StandardPlonk::<_>::rand(ChaCha20Rng::from_seed(Default::default()))
-            );
-            halo2_kzg_create_snark!(
-                ProverSHPLONK<_>,
-                VerifierSHPLONK<_>,
-                PoseidonTranscript<_, _, _>,
-                PoseidonTranscript<_, _, _>,
-                ChallengeScalar<_>,
-                &params,
-                &pk,
-                &protocol,
-                &circuits
-            )
-        };
-        Self::new(&params, [snark1, snark2])
+        Self::n_snark(2)
+    }
+
+    pub fn n_snark(n: usize) -> Self {
+        let (params, snarks): (Vec<_>, Vec<_>) = (0..n)
+            .map(|_| {
+                const K: u32 = 9;
+                let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+                    K,
+                    halo2_kzg_config!(true, 1),
+                    StandardPlonk::<_>::rand(ChaCha20Rng::from_seed(Default::default()))
+                );
+                let snark = halo2_kzg_create_snark!(
+                    ProverSHPLONK<_>,
+                    VerifierSHPLONK<_>,
+                    PoseidonTranscript<_, _, _>,
+                    PoseidonTranscript<_, _, _>,
+                    ChallengeScalar<_>,
+                    &params,
+                    &pk,
+                    &protocol,
+                    &circuits
+                );
+                (params, snark)
+            })
+            .unzip();
+        Self::new(&params[0], snarks)
     }
 
     pub fn two_snark_with_accumulator() -> Self {
@@ -250,15 +256,13 @@ impl Circuit<Fr> for Accumulation {
             instances: Vec::new(),
             as_vk: self.as_vk,
             as_proof: Value::unknown(),
+            msm_config: self.msm_config,
+            stats: Default::default(),
         }
     }
 
     fn configure(meta: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
-        let path = "./configs/verify_circuit.config";
-        let params_str =
-            std::fs::read_to_string(path).expect(format!("{} should exist", path).as_str());
-        let params: Halo2VerifierCircuitConfigParams =
-            serde_json::from_str(params_str.as_str()).unwrap();
+        let params = DefaultVerifierCircuitParams::params();
 
         assert!(
             params.limb_bits == BITS && params.num_limbs == LIMBS,
@@ -305,12 +309,13 @@ impl Circuit<Fr> for Accumulation {
                 }
                 let ctx = config.base_field_config.new_context(region);
 
-                let loader = Halo2Loader::new(&config.base_field_config, ctx);
+                let loader =
+                    Halo2Loader::with_msm_config(&config.base_field_config, ctx, self.msm_config);
                 let KzgAccumulator { lhs, rhs } =
                     accumulate(&self.svk, &loader, &self.snarks, &self.as_vk, self.as_proof());
 
                 // REQUIRED STEP
-                loader.finalize();
+                *self.stats.borrow_mut() = loader.finalize();
                 final_pair = Some((lhs.assigned(), rhs.assigned()));
 
                 Ok(())
@@ -394,6 +399,947 @@ test!(
     Accumulation::two_snark_with_accumulator()
 );
 
+#[cfg(feature = "loader_evm")]
+#[test]
+fn test_keccak_chip_native() {
+    use crate::loader::halo2::{bits::Bit, keccak_chip::KeccakChip};
+    use rand_chacha::rand_core::RngCore;
+    use sha3::{Digest, Keccak256};
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    // Lengths around the rate 136 to cover both paddings `0x01..0x80` and `0x81`
+    for len in [0, 32, 33, 64, 135, 136, 137, 300] {
+        let mut message = vec![0; len];
+        rng.fill_bytes(&mut message);
+
+        let mut keccak = KeccakChip::<Fr, Fr>::new();
+        keccak.update(
+            &Bit::<Fr>::from_bytes(&message)
+                .into_iter()
+                .map(|bit| Bit::Assigned(Fr::from(bit.constant().unwrap() as u64)))
+                .collect_vec(),
+        );
+        let hash = keccak
+            .squeeze()
+            .chunks(8)
+            .map(|bits| {
+                bits.iter().enumerate().fold(0, |acc, (idx, bit)| {
+                    let bit = match bit {
+                        Bit::Constant(bit) => *bit,
+                        Bit::Assigned(bit) => *bit == Fr::from(1),
+                    };
+                    acc | ((bit as u8) << idx)
+                })
+            })
+            .collect_vec();
+
+        assert_eq!(hash, Keccak256::digest(&message).to_vec());
+    }
+}
+
+#[test]
+fn test_sha256_chip_native() {
+    use crate::loader::halo2::{bits::Bit, sha256_chip::Sha256Chip};
+    use rand_chacha::rand_core::RngCore;
+    use sha2::{Digest, Sha256};
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    // Lengths around the block 64 to cover padding into an extra block
+    for len in [0, 32, 55, 56, 64, 65, 119, 200] {
+        let mut message = vec![0; len];
+        rng.fill_bytes(&mut message);
+
+        let mut sha256 = Sha256Chip::<Fr, Fr>::new();
+        sha256.update(
+            &Bit::<Fr>::from_bytes(&message)
+                .into_iter()
+                .map(|bit| Bit::Assigned(Fr::from(bit.constant().unwrap() as u64)))
+                .collect_vec(),
+        );
+        let hash = sha256
+            .squeeze()
+            .chunks(8)
+            .map(|bits| {
+                bits.iter().enumerate().fold(0, |acc, (idx, bit)| {
+                    let bit = match bit {
+                        Bit::Constant(bit) => *bit,
+                        Bit::Assigned(bit) => *bit == Fr::from(1),
+                    };
+                    acc | ((bit as u8) << idx)
+                })
+            })
+            .collect_vec();
+
+        assert_eq!(hash, Sha256::digest(&message).to_vec());
+    }
+}
+
+#[test]
+fn test_blake2b_chip_native() {
+    use crate::loader::halo2::{
+        bits::{self, Bit},
+        blake2b_chip::Blake2bChip,
+    };
+    use halo2_curves::group::ff::{Field, PrimeField};
+    use halo2_proofs::transcript::Transcript;
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(Vec::new());
+    let mut blake2b = Blake2bChip::<Fr, Fr>::new(b"Halo2-Transcript");
+    // Enough scalars to cross several blocks, with challenges squeezed in
+    // between to check state is kept after squeezing.
+    for idx in 0..10 {
+        let scalar = Fr::random(&mut rng);
+        transcript.common_scalar(scalar).unwrap();
+        blake2b.update(&Bit::from_u64(2, 8));
+        blake2b.update(
+            &Bit::<Fr>::from_bytes(scalar.to_repr().as_ref())
+                .into_iter()
+                .map(|bit| Bit::Assigned(Fr::from(bit.constant().unwrap() as u64)))
+                .collect_vec(),
+        );
+
+        if idx % 3 == 0 {
+            let challenge = *transcript.squeeze_challenge_scalar::<()>();
+            blake2b.update(&Bit::from_u64(0, 8));
+            assert_eq!(bits::compose(&NativeLoader, &blake2b.squeeze()), challenge);
+        }
+    }
+}
+
+#[test]
+fn test_transcript_domain_separation() {
+    use crate::{
+        system::halo2::transcript::{halo2::HashTranscript, sha256::Sha256Transcript},
+        util::{
+            hash::{PoseidonParams, Sponge},
+            transcript::{label_to_fe, Transcript},
+        },
+    };
+
+    // Length prefix keeps labels with trailing or leading zeros apart
+    let labels: [&[u8]; 5] = [b"", b"\0", b"\0\0", b"r", b"\0r"];
+    for (lhs, rhs) in labels.iter().tuple_combinations() {
+        assert_ne!(label_to_fe::<Fr>(lhs), label_to_fe::<Fr>(rhs));
+    }
+
+    fn assert_domain_separation<T: Transcript<G1Affine, NativeLoader>>(
+        new_transcript: impl Fn(bool) -> T,
+    ) {
+        let squeeze = |domain_separation: bool, labeled: bool| {
+            let mut transcript = new_transcript(domain_separation);
+            transcript.common_scalar(&Fr::from(1)).unwrap();
+            if labeled {
+                transcript.squeeze_labeled_challenge(b"r")
+            } else {
+                transcript.squeeze_challenge()
+            }
+        };
+        // Labels are ignored unless enabled to keep existing proofs valid
+        assert_eq!(squeeze(false, true), squeeze(false, false));
+        assert_eq!(squeeze(true, false), squeeze(false, false));
+        assert_ne!(squeeze(true, true), squeeze(false, false));
+    }
+
+    assert_domain_separation(|domain_separation| {
+        PoseidonTranscript::<NativeLoader, _, _>::new(std::io::empty())
+            .set_domain_separation(domain_separation)
+    });
+    assert_domain_separation(|domain_separation| {
+        Sha256Transcript::<_, NativeLoader, _, _>::new(std::io::empty())
+            .set_domain_separation(domain_separation)
+    });
+    assert_domain_separation(|domain_separation| {
+        let hash = Sponge::<Fr, Fr>::new(&NativeLoader, PoseidonParams::circom(3));
+        HashTranscript::<_, NativeLoader, _, _>::new(std::io::empty(), hash)
+            .set_domain_separation(domain_separation)
+    });
+}
+
+#[test]
+fn test_circuit_stats() {
+    use halo2_proofs::dev::MockProver;
+
+    let circuit = Accumulation::n_snark(2);
+    MockProver::run(21, &circuit, circuit.instances()).unwrap().assert_satisfied();
+    let stats = circuit.stats.take();
+
+    let stages = stats.stages.iter().map(|stage| stage.identifier.as_str()).collect_vec();
+    assert_eq!(stages, ["transcript", "evaluations", "msm", "accumulation"]);
+    assert!(stats.stages.iter().all(|stage| stage.advice_cells > 0));
+    assert!(
+        stats.stages.iter().map(|stage| stage.advice_cells).sum::<usize>() <= stats.advice_cells
+    );
+    assert_eq!(serde_json::from_str::<CircuitStats>(&stats.to_json()).unwrap(), stats);
+}
+
+#[test]
+fn test_tune_config() {
+    use crate::{
+        system::halo2::tuner::{count, tune_config},
+        util::arithmetic::LimbsDescriptor,
+    };
+    use halo2_ecc::fields::fp::FpStrategy;
+
+    let circuit = Accumulation::n_snark(2);
+    let stats = |circuit: &Accumulation| Some(circuit.stats.borrow().clone());
+    let limbs = LimbsDescriptor::new(LIMBS, BITS);
+    let params = tune_config(&circuit, 21, FpStrategy::Simple, limbs, stats);
+    assert_eq!((params.degree, params.lookup_bits), (21, 20));
+
+    // Hand-tuned `DefaultVerifierCircuitParams` uses 7 advice columns for 2^20
+    assert!(params.num_advice <= 7);
+    let usable_rows = count(&circuit, &params).unwrap();
+    let stats = circuit.stats.take();
+    assert!(stats.max_advice_rows <= usable_rows);
+    assert!(stats.max_lookup_rows <= usable_rows);
+}
+
+/// Params of a verifier circuit with accumulator in 4 limbs of 68 bits.
+#[derive(Clone, Copy, Debug, Default)]
+struct Wide;
+
+impl Halo2VerifierCircuitParams for Wide {
+    fn params() -> Halo2VerifierCircuitConfigParams {
+        Halo2VerifierCircuitConfigParams {
+            degree: 21,
+            num_advice: 14,
+            lookup_bits: 20,
+            limb_bits: 68,
+            num_limbs: 4,
+            ..DefaultVerifierCircuitParams::params()
+        }
+    }
+}
+
+#[test]
+fn test_aggregation_circuit_params() {
+    use crate::system::halo2::aggregation::AggregationCircuit;
+    use halo2_proofs::plonk::ConstraintSystem;
+
+    // Circuits of different shapes could be configured in the same process
+    let mut meta = ConstraintSystem::default();
+    AggregationCircuit::<DefaultVerifierCircuitParams>::configure(&mut meta);
+    let mut wide_meta = ConstraintSystem::default();
+    AggregationCircuit::<Wide>::configure(&mut wide_meta);
+    assert_eq!(wide_meta.num_advice_columns(), meta.num_advice_columns() + 7);
+
+    // Accumulator is encoded as field chip of each circuit
+    assert_eq!(AggregationCircuit::<DefaultVerifierCircuitParams>::accumulator_indices().len(), 12);
+    assert_eq!(AggregationCircuit::<Wide>::accumulator_indices().len(), 16);
+}
+
+#[test]
+fn test_aggregation_circuit_runtime_params() {
+    use crate::system::halo2::{aggregation::AggregationCircuit, RuntimeVerifierCircuitParams};
+    use halo2_proofs::plonk::ConstraintSystem;
+
+    // Params only known at runtime, e.g. written by `tune_config` before
+    let params = Halo2VerifierCircuitConfigParams {
+        num_advice: 10,
+        limb_bits: 68,
+        num_limbs: 4,
+        ..DefaultVerifierCircuitParams::params()
+    };
+    let path = std::env::temp_dir().join("test_aggregation_circuit_runtime_params.json");
+    std::fs::write(&path, serde_json::to_string(&params).unwrap()).unwrap();
+    let read = RuntimeVerifierCircuitParams::read_from_file(&path).unwrap();
+    assert_eq!(serde_json::to_value(&read).unwrap(), serde_json::to_value(&params).unwrap());
+
+    let mut meta = ConstraintSystem::default();
+    Halo2VerifierCircuitConfig::configure(&mut meta, params.clone());
+    RuntimeVerifierCircuitParams::with(read, || {
+        let mut runtime_meta = ConstraintSystem::default();
+        AggregationCircuit::<RuntimeVerifierCircuitParams>::configure(&mut runtime_meta);
+        assert_eq!(runtime_meta.num_advice_columns(), meta.num_advice_columns());
+        assert_eq!(
+            AggregationCircuit::<RuntimeVerifierCircuitParams>::accumulator_indices().len(),
+            16
+        );
+    });
+
+    // Calls from other threads take their own params one after another
+    let handles = [(7, BITS, LIMBS), (14, 68, 4)].map(|(num_advice, limb_bits, num_limbs)| {
+        let params =
+            Halo2VerifierCircuitConfigParams { num_advice, limb_bits, num_limbs, ..params.clone() };
+        std::thread::spawn(move || {
+            RuntimeVerifierCircuitParams::with(params, || {
+                let mut meta = ConstraintSystem::default();
+                AggregationCircuit::<RuntimeVerifierCircuitParams>::configure(&mut meta);
+                std::thread::yield_now();
+                let indices =
+                    AggregationCircuit::<RuntimeVerifierCircuitParams>::accumulator_indices();
+                (meta.num_advice_columns(), indices.len())
+            })
+        })
+    });
+    let outputs = handles.map(|handle| handle.join().unwrap());
+    assert_eq!(outputs[1].0, outputs[0].0 + 7);
+    assert_eq!(outputs.map(|output| output.1), [12, 16]);
+}
+
+/// Returns snarks of `StandardPlonk` with `aggregation::PoseidonTranscript`
+/// and params to aggregate them.
+fn aggregation_snarks(n: usize) -> (ParamsKZG<Bn256>, Vec<aggregation::Snark>) {
+    let (params, snarks): (Vec<_>, Vec<_>) = (0..n)
+        .map(|_| {
+            const K: u32 = 9;
+            let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+                K,
+                halo2_kzg_config!(true, 1),
+                StandardPlonk::<_>::rand(ChaCha20Rng::from_seed(Default::default()))
+            );
+            let snark = halo2_kzg_create_snark!(
+                ProverSHPLONK<_>,
+                VerifierSHPLONK<_>,
+                aggregation::PoseidonTranscript<_, _, _>,
+                aggregation::PoseidonTranscript<_, _, _>,
+                ChallengeScalar<_>,
+                &params,
+                &pk,
+                &protocol,
+                &circuits
+            );
+            (params, aggregation::Snark::new(snark.protocol, snark.instances, snark.proof))
+        })
+        .unzip();
+    (params.into_iter().next().unwrap(), snarks)
+}
+
+#[test]
+fn test_aggregation_circuit_recorded_snarks() {
+    use crate::system::halo2::{aggregation::AggregationCircuit, tuner::count};
+    use halo2_proofs::dev::MockProver;
+
+    let (params, snarks) = aggregation_snarks(2);
+    let circuit: AggregationCircuit = AggregationCircuit::builder(&params)
+        .with_snarks(snarks)
+        .set_expose_target_instances(true)
+        .build();
+    MockProver::run(21, &circuit, circuit.instances()).unwrap().assert_satisfied();
+    let stats = circuit.stats().unwrap();
+    assert_eq!(stats.stages.last().unwrap().identifier, "accumulation");
+
+    // Layout of snarks verified in parallel doesn't depend on witness
+    let circuit = circuit.without_witnesses();
+    count(&circuit, &DefaultVerifierCircuitParams::params()).unwrap();
+    assert_eq!(circuit.stats().unwrap(), stats);
+}
+
+#[test]
+fn test_aggregation_circuit_with_wide_limbs() {
+    use crate::system::halo2::aggregation::AggregationCircuit;
+    use halo2_proofs::dev::MockProver;
+
+    const K: u32 = 21;
+    assert_eq!(Wide::params().degree, K);
+
+    // Aggregation circuit exposing accumulator in limbs of `Wide`
+    let (params, pk, protocol, circuits) = {
+        let (params, snarks) = aggregation_snarks(2);
+        let circuit = AggregationCircuit::<Wide>::builder(&params).with_snarks(snarks).build();
+        halo2_kzg_prepare!(
+            K,
+            halo2_kzg_config!(true, 1, AggregationCircuit::<Wide>::accumulator_indices()),
+            circuit.clone()
+        )
+    };
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        aggregation::PoseidonTranscript<_, _, _>,
+        aggregation::PoseidonTranscript<_, _, _>,
+        ChallengeScalar<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+    let snark = aggregation::Snark::new(snark.protocol, snark.instances, snark.proof);
+
+    // Its accumulator is decoded by limbs of `Wide` both natively and in circuit
+    let circuit = AggregationCircuit::<Wide>::builder(&params).with_snarks([snark]).build();
+    assert_eq!(circuit.instances()[0].len(), 16);
+    MockProver::run(K, &circuit, circuit.instances()).unwrap().assert_satisfied();
+}
+
+#[test]
+fn test_aggregation_circuit_deterministic_layout() {
+    use crate::system::halo2::{
+        aggregation::AggregationCircuit, read_or_create_srs, test::kzg::setup,
+        transcript_initial_state,
+    };
+    use halo2_proofs::plonk::keygen_vk;
+
+    let params = read_or_create_srs(21, setup::<Bn256>);
+    // Builds the circuit from scratch and returns the digest of its vk
+    let transcript_repr = || {
+        let (params_app, snarks) = aggregation_snarks(2);
+        let circuit: AggregationCircuit =
+            AggregationCircuit::builder(&params_app).with_snarks(snarks).build();
+        transcript_initial_state(&keygen_vk(&params, &circuit).unwrap())
+    };
+
+    let expected = transcript_repr();
+    assert_eq!(transcript_repr(), expected);
+    // A single thread records snarks one by one in order, as done without
+    // feature `parallel`
+    #[cfg(feature = "parallel")]
+    {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        assert_eq!(pool.install(transcript_repr), expected);
+    }
+}
+
+fn aggregation_instance_commitment(hash: InstanceHash) {
+    use crate::system::halo2::aggregation::{AggregationCircuit, TargetInstances};
+    use halo2_proofs::dev::MockProver;
+
+    let (params, snarks) = aggregation_snarks(2);
+    let target_instances =
+        snarks.iter().flat_map(|snark| snark.instances().iter().flatten().cloned()).collect_vec();
+    let indices = vec![target_instances.len() - 1, 0];
+    let circuit: AggregationCircuit = AggregationCircuit::builder(&params)
+        .with_snarks(snarks)
+        .set_target_instances(TargetInstances::Commitment { hash, indices: Some(indices) })
+        .build();
+
+    let committed_instances =
+        vec![target_instances[target_instances.len() - 1], target_instances[0]];
+    assert_eq!(circuit.committed_instances(), committed_instances);
+    let mut instances = circuit.instances();
+    assert_eq!(
+        instances[0].len(),
+        AggregationCircuit::<DefaultVerifierCircuitParams>::commitment_index() + 1
+    );
+    assert_eq!(instances[0].last(), Some(&hash.commit(&committed_instances)));
+    MockProver::run(21, &circuit, instances.clone()).unwrap().assert_satisfied();
+
+    *instances[0].last_mut().unwrap() = hash.commit(&committed_instances[..1]);
+    assert!(MockProver::run(21, &circuit, instances).unwrap().verify().is_err());
+}
+
+#[test]
+fn test_aggregation_circuit_poseidon_instance_commitment() {
+    aggregation_instance_commitment(InstanceHash::Poseidon);
+}
+
+#[test]
+#[ignore = "cause it requires keccak in circuit which is too large for MockProver"]
+fn test_aggregation_circuit_keccak_instance_commitment() {
+    aggregation_instance_commitment(InstanceHash::Keccak);
+}
+
+#[test]
+fn test_msm_config() {
+    use halo2_proofs::dev::MockProver;
+
+    let straus = |window_bits| MsmConfig { window_bits, ..Default::default() };
+    let msm_configs = [
+        MsmConfig::default(),
+        straus(3),
+        straus(5),
+        MsmConfig { batch_fixed_base: true, ..Default::default() },
+        MsmConfig { strategy: MsmStrategy::Pippenger, ..Default::default() },
+        MsmConfig {
+            strategy: MsmStrategy::Pippenger,
+            batch_fixed_base: true,
+            ..Default::default()
+        },
+    ];
+    for n in [1, 2] {
+        let default = Accumulation::n_snark(n);
+        let costs = msm_configs.map(|msm_config| {
+            // Every strategy should compute the same accumulator in instances
+            let circuit = Accumulation::n_snark(n).set_msm_config(msm_config);
+            assert_eq!(circuit.instances(), default.instances());
+            MockProver::run(21, &circuit, default.instances()).unwrap().assert_satisfied();
+            circuit.stats.take().msm
+        });
+
+        // Default should cost no more than Straus of other window bits
+        assert_eq!(msm_configs[0].strategy, MsmStrategy::Straus);
+        for cost in &costs[1..3] {
+            assert!(costs[0].advice_cells <= cost.advice_cells);
+        }
+    }
+}
+
+/// Circuit running `synthesize` with a `Halo2Loader`, for testing gadgets of
+/// loader without verifying a whole snark, configured by `P::params()`.
+struct LoaderCircuit<P = DefaultVerifierCircuitParams> {
+    synthesize: Rc<dyn for<'a, 'b> Fn(&Rc<Halo2Loader<'a, 'b>>)>,
+    _marker: PhantomData<P>,
+}
+
+impl LoaderCircuit {
+    fn new(synthesize: impl for<'a, 'b> Fn(&Rc<Halo2Loader<'a, 'b>>) + 'static) -> Self {
+        Self::with_params(synthesize)
+    }
+}
+
+impl<P: Halo2VerifierCircuitParams> LoaderCircuit<P> {
+    fn with_params(synthesize: impl for<'a, 'b> Fn(&Rc<Halo2Loader<'a, 'b>>) + 'static) -> Self {
+        Self { synthesize: Rc::new(synthesize), _marker: PhantomData }
+    }
+
+    fn is_satisfied(&self) -> bool {
+        use halo2_proofs::dev::MockProver;
+
+        let prover = MockProver::run(P::params().degree, self, vec![vec![]]).unwrap();
+        prover.verify().is_ok()
+    }
+}
+
+impl<P: Halo2VerifierCircuitParams> Circuit<Fr> for LoaderCircuit<P> {
+    type Config = Halo2VerifierCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { synthesize: self.synthesize.clone(), _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
+        Halo2VerifierCircuitConfig::configure(meta, P::params())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), plonk::Error> {
+        config.base_field_config.load_lookup_table(&mut layouter)?;
+
+        // Need to trick layouter to skip first pass in get shape mode
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let ctx = config.base_field_config.new_context(region);
+
+                let loader = Halo2Loader::new(&config.base_field_config, ctx);
+                (self.synthesize)(&loader);
+
+                // REQUIRED STEP
+                loader.finalize();
+
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Circuit decoding an accumulator from `limbs` by `LimbsEncoding`, as done
+/// for accumulator in instances of previous layer.
+struct AccumulatorFromLimbs {
+    limbs: Vec<Fr>,
+}
+
+impl Circuit<Fr> for AccumulatorFromLimbs {
+    type Config = Halo2VerifierCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { limbs: Vec::new() }
+    }
+
+    fn configure(meta: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
+        Accumulation::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), plonk::Error> {
+        config.base_field_config.load_lookup_table(&mut layouter)?;
+
+        // Need to trick layouter to skip first pass in get shape mode
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let ctx = config.base_field_config.new_context(region);
+
+                let loader = Halo2Loader::new(&config.base_field_config, ctx);
+                let limbs = self
+                    .limbs
+                    .iter()
+                    .map(|limb| loader.assign_scalar(Value::known(*limb)))
+                    .collect();
+                <LimbsEncoding<ConstLimbs<LIMBS, BITS>> as AccumulatorEncoding<_, _, Pcs>>::from_repr(limbs)
+                    .unwrap();
+
+                // REQUIRED STEP
+                loader.finalize();
+
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_accumulator_from_non_canonical_limbs() {
+    use halo2_curves::{
+        bn256::G1,
+        group::{ff::Field, Curve, Group},
+    };
+    use halo2_proofs::dev::MockProver;
+    use num_bigint::BigUint;
+
+    let limbs_of = |coordinate: &BigUint| {
+        let mask = (BigUint::from(1u64) << BITS) - 1u64;
+        (0..LIMBS).map(|idx| fe_from_big::<Fr>((coordinate >> (idx * BITS)) & &mask)).collect_vec()
+    };
+    let is_satisfied = |limbs: Vec<Fr>| {
+        let circuit = AccumulatorFromLimbs { limbs };
+        let prover = MockProver::run(load_verify_circuit_degree(), &circuit, vec![vec![]]).unwrap();
+        prover.verify().is_ok()
+    };
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let [lhs, rhs] = [(); 2].map(|_| G1::random(&mut rng).to_affine());
+    let coordinates = [lhs.x, lhs.y, rhs.x, rhs.y].map(fe_to_big);
+    let limbs = coordinates.iter().flat_map(limbs_of).collect_vec();
+    assert!(is_satisfied(limbs.clone()));
+
+    // Same composed value but with the lowest limb overflowing `BITS`
+    let mut overflowing = limbs.clone();
+    assert!(!bool::from(overflowing[1].is_zero()));
+    overflowing[0] += fe_from_big::<Fr>(BigUint::from(1u64) << BITS);
+    overflowing[1] -= Fr::one();
+    assert!(!is_satisfied(overflowing));
+
+    // Same coordinate modulo `p` but not less than `p`
+    let mut unreduced = limbs;
+    unreduced.splice(0..LIMBS, limbs_of(&(&coordinates[0] + modulus::<Fq>())));
+    assert!(!is_satisfied(unreduced));
+}
+
+/// Returns a circuit constraining in-circuit keccak256 of `message` to be
+/// `digest`, where `message` is assigned as witness bytes.
+#[cfg(feature = "loader_evm")]
+fn keccak_digest_circuit(message: Vec<u8>, digest: Vec<u8>) -> LoaderCircuit {
+    use crate::loader::{
+        halo2::{
+            bits::{self, Bit},
+            keccak_chip::KeccakChip,
+        },
+        ScalarLoader,
+    };
+
+    LoaderCircuit::new(move |loader| {
+        let message = message
+            .iter()
+            .flat_map(|byte| {
+                let byte = loader.assign_scalar(Value::known(Fr::from(*byte as u64)));
+                loader.assign_bits(&byte, 8).into_iter().map(Bit::Assigned)
+            })
+            .collect_vec();
+        let mut keccak = KeccakChip::<Fr, _>::new();
+        keccak.update(&message);
+        for (byte, expected) in keccak.squeeze().chunks(8).zip(digest.iter()) {
+            let byte = bits::compose(loader, byte);
+            let expected = loader.load_const(&Fr::from(*expected as u64));
+            loader.assert_eq("digest should match", &byte, &expected).unwrap();
+        }
+    })
+}
+
+#[cfg(feature = "loader_evm")]
+#[test]
+fn test_keccak_chip_in_circuit() {
+    use rand_chacha::rand_core::RngCore;
+    use sha3::{Digest, Keccak256};
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    // One block, and two blocks with padding in the second one
+    for len in [32, 137] {
+        let mut message = vec![0; len];
+        rng.fill_bytes(&mut message);
+        let digest = Keccak256::digest(&message).to_vec();
+        assert!(keccak_digest_circuit(message.clone(), digest.clone()).is_satisfied());
+
+        // Tampered witness should not satisfy the same digest
+        message[len - 1] ^= 1;
+        assert!(!keccak_digest_circuit(message, digest).is_satisfied());
+    }
+}
+
+#[cfg(feature = "evm_runtime")]
+#[test]
+fn test_keccak_instance_commitment_in_circuit() {
+    use crate::loader::{
+        evm::{encode_calldata, runtime, EvmLoader},
+        ScalarLoader,
+    };
+    use halo2_curves::group::ff::Field;
+
+    /// Params of a loader circuit in `2^18` rows, which is enough for a
+    /// single keccak block.
+    #[derive(Clone, Copy, Debug, Default)]
+    struct Small;
+
+    impl Halo2VerifierCircuitParams for Small {
+        fn params() -> Halo2VerifierCircuitConfigParams {
+            Halo2VerifierCircuitConfigParams {
+                degree: 18,
+                num_advice: 16,
+                lookup_bits: 17,
+                ..DefaultVerifierCircuitParams::params()
+            }
+        }
+    }
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let instances = [(); 2].map(|_| Fr::random(&mut rng)).to_vec();
+    let hash = InstanceHash::Keccak;
+    let commitment = hash.commit(&instances);
+
+    // Commitment on EVM should match the native one
+    let runtime_code = {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let loaded =
+            (0..instances.len()).map(|idx| loader.calldataload_scalar(idx * 0x20)).collect_vec();
+        let computed = hash.commit_on_evm(&loader, &loaded);
+        let expected = loader.load_const(&commitment);
+        ScalarLoader::<Fr>::assert_eq(&loader, "commitment should match", &computed, &expected)
+            .unwrap();
+        loader.runtime_code()
+    };
+    assert!(runtime::call(runtime_code, encode_calldata(&[instances.clone()], &[])).success);
+
+    // Commitment in circuit should match the native one, and nothing else
+    let circuit = |commitment: Fr| {
+        let instances = instances.clone();
+        LoaderCircuit::<Small>::with_params(move |loader| {
+            let instances = instances
+                .iter()
+                .map(|instance| loader.assign_scalar(Value::known(*instance)))
+                .collect_vec();
+            let computed = hash.commit_in_circuit(loader, &instances);
+            let expected = loader.load_const(&commitment);
+            loader.assert_eq("commitment should match", &computed, &expected).unwrap();
+        })
+    };
+    assert!(circuit(commitment).is_satisfied());
+    assert!(!circuit(commitment + Fr::one()).is_satisfied());
+}
+
+#[test]
+fn test_sha256_transcript_in_circuit() {
+    use crate::{
+        loader::ScalarLoader,
+        system::halo2::transcript::sha256::Sha256Transcript,
+        util::{
+            arithmetic::{Coordinates, CurveAffine, PrimeField},
+            transcript::{Transcript, TranscriptRead},
+        },
+    };
+    use halo2_curves::{
+        bn256::G1,
+        group::{ff::Field, Curve, Group},
+    };
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let scalar = Fr::random(&mut rng);
+    let ec_point = G1::random(&mut rng).to_affine();
+    // Scalar and coordinates in big-endian as `Sha256Transcript` reads
+    let proof = {
+        let coordinates = Option::<Coordinates<_>>::from(ec_point.coordinates()).unwrap();
+        [scalar.to_repr(), coordinates.x().to_repr(), coordinates.y().to_repr()]
+            .into_iter()
+            .flat_map(|repr| repr.into_iter().rev())
+            .collect_vec()
+    };
+
+    let challenges = {
+        let mut transcript = Sha256Transcript::<G1Affine, NativeLoader, _, _>::new(&proof[..]);
+        transcript.read_scalar().unwrap();
+        transcript.read_ec_point().unwrap();
+        // The second squeezes only the previous digest padded with `0x01`
+        [transcript.squeeze_challenge(), transcript.squeeze_challenge()]
+    };
+
+    let circuit = |challenges: [Fr; 2]| {
+        let proof = proof.clone();
+        LoaderCircuit::new(move |loader| {
+            let mut transcript = Sha256Transcript::<G1Affine, Rc<Halo2Loader>, _, _>::new(
+                loader,
+                Value::known(proof.as_slice()),
+            );
+            transcript.read_scalar().unwrap();
+            transcript.read_ec_point().unwrap();
+            for expected in challenges {
+                let challenge = transcript.squeeze_challenge();
+                let expected = loader.load_const(&expected);
+                loader.assert_eq("challenge should match", &challenge, &expected).unwrap();
+            }
+        })
+    };
+    assert!(circuit(challenges).is_satisfied());
+    assert!(!circuit([challenges[0], challenges[1] + Fr::one()]).is_satisfied());
+}
+
+#[test]
+fn test_blake2b_transcript_in_circuit() {
+    use crate::{
+        loader::{EcPointLoader, ScalarLoader},
+        system::halo2::transcript::halo2::Blake2bTranscript,
+    };
+
+    /// Params of a loader circuit in `2^21` rows, which is enough for blake2b
+    /// over a proof of `StandardPlonk`.
+    #[derive(Clone, Copy, Debug, Default)]
+    struct Large;
+
+    impl Halo2VerifierCircuitParams for Large {
+        fn params() -> Halo2VerifierCircuitConfigParams {
+            Halo2VerifierCircuitConfigParams {
+                degree: 21,
+                num_advice: 14,
+                lookup_bits: 20,
+                ..DefaultVerifierCircuitParams::params()
+            }
+        }
+    }
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::<_>::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        Blake2bWrite<_, _, _>,
+        Blake2bRead<_, _, _>,
+        Challenge255<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let snark = Rc::new(snark);
+    let svk: Svk = params.get_g()[0].into();
+    let (challenges, accumulators) = {
+        let mut transcript = Blake2bRead::<_, G1Affine, _>::init(snark.proof.as_slice());
+        let proof =
+            Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript).unwrap();
+        let challenges = proof.challenges.iter().cloned().chain([proof.z]).collect_vec();
+        let accumulators =
+            Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof).unwrap();
+        (challenges, accumulators)
+    };
+
+    // Challenges and accumulator in circuit should match the native ones
+    let circuit = |proof: Vec<u8>| {
+        let (svk, snark) = (svk, snark.clone());
+        let (challenges, accumulators) = (challenges.clone(), accumulators.clone());
+        LoaderCircuit::<Large>::with_params(move |loader| {
+            let instances = snark
+                .instances
+                .iter()
+                .map(|instances| {
+                    instances
+                        .iter()
+                        .map(|instance| loader.assign_scalar(Value::known(*instance)))
+                        .collect_vec()
+                })
+                .collect_vec();
+            let mut transcript = Blake2bTranscript::<G1Affine, Rc<Halo2Loader>, _, _>::new(
+                loader,
+                Value::known(proof.as_slice()),
+            );
+            let proof =
+                Plonk::read_proof(&svk, &snark.protocol, &instances, &mut transcript).unwrap();
+            for (challenge, expected) in
+                proof.challenges.iter().chain([&proof.z]).zip(challenges.iter())
+            {
+                let expected = loader.load_const(expected);
+                loader.assert_eq("challenge should match", challenge, &expected).unwrap();
+            }
+            let output = Plonk::succinct_verify(&svk, &snark.protocol, &instances, &proof).unwrap();
+            for (output, expected) in output.iter().zip(accumulators.iter()) {
+                for (output, expected) in
+                    [(&output.lhs, &expected.lhs), (&output.rhs, &expected.rhs)]
+                {
+                    let expected = loader.ec_point_load_const(expected);
+                    loader
+                        .ec_point_assert_eq("accumulator should match", output, &expected)
+                        .unwrap();
+                }
+            }
+        })
+    };
+    assert!(circuit(snark.proof.clone()).is_satisfied());
+
+    // Flip the lowest bit of the last evaluation, which is followed by 2
+    // compressed points of SHPLONK
+    let mut tampered = snark.proof.clone();
+    let last_evaluation = tampered.len() - 3 * 0x20;
+    tampered[last_evaluation] ^= 1;
+    assert!(!circuit(tampered).is_satisfied());
+}
+
+#[test]
+fn test_ec_point_arithmetic_in_circuit() {
+    use crate::loader::{halo2::EcPoint, Loader};
+    use halo2_curves::{
+        bn256::G1,
+        group::{prime::PrimeCurveAffine, Curve, Group},
+    };
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let [p, q] = [(); 2].map(|_| G1::random(&mut rng).to_affine());
+
+    let circuit = LoaderCircuit::new(move |loader| {
+        let assert_eq = |lhs: &EcPoint<G1Affine>, rhs: G1Affine| {
+            let rhs = loader.assign_const_ec_point(rhs);
+            loader.ec_point_assert_eq("ec point should match", lhs, &rhs).unwrap();
+        };
+        let identity = loader.assign_const_ec_point(G1Affine::identity());
+        let constant = [p, q].map(|ec_point| loader.assign_const_ec_point(ec_point));
+        let assigned = [p, q].map(|ec_point| loader.assign_ec_point(Value::known(ec_point)));
+
+        // Assigned and constant in any mix
+        for lhs in [&constant[0], &assigned[0]] {
+            for rhs in [&constant[1], &assigned[1]] {
+                assert_eq(&loader.ec_point_add(lhs, rhs).unwrap(), (p + q).to_affine());
+                assert_eq(&loader.ec_point_sub(lhs, rhs).unwrap(), (p - q).to_affine());
+            }
+            assert_eq(&loader.ec_point_neg(lhs).unwrap(), -p);
+            // Constant identity is skipped
+            assert_eq(&loader.ec_point_add(lhs, &identity).unwrap(), p);
+            assert_eq(&loader.ec_point_add(&identity, lhs).unwrap(), p);
+            assert_eq(&loader.ec_point_sub(lhs, &identity).unwrap(), p);
+            assert_eq(&loader.ec_point_sub(&identity, lhs).unwrap(), -p);
+            // Same point is doubled or cancelled out
+            assert_eq(&loader.ec_point_add(lhs, lhs).unwrap(), (p + p).to_affine());
+            assert_eq(&loader.ec_point_sub(lhs, lhs).unwrap(), G1Affine::identity());
+        }
+    });
+    assert!(circuit.is_satisfied());
+
+    // Distinct assigned points turning out to be equal are rejected
+    let circuit = LoaderCircuit::new(move |loader| {
+        let lhs = loader.assign_ec_point(Value::known(p));
+        let rhs = loader.assign_ec_point(Value::known(p));
+        loader.ec_point_add(&lhs, &rhs).unwrap();
+    });
+    assert!(!circuit.is_satisfied());
+}
+
 pub trait TargetCircuit: Circuit<Fr> {
     const TARGET_CIRCUIT_K: u32;
     const PUBLIC_INPUT_SIZE: usize;
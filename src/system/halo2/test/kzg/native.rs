@@ -1,6 +1,6 @@
 use crate::{
     loader::halo2::test::StandardPlonk,
-    pcs::kzg::{Bdfg21, Gwc19, Kzg, LimbsEncoding},
+    pcs::kzg::{Bdfg21, ConstLimbs, Gwc19, Kzg, LimbsEncoding},
     system::halo2::test::kzg::{
         halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_native_verify, halo2_kzg_prepare, BITS,
         LIMBS,
@@ -47,8 +47,8 @@ macro_rules! test {
         }
     };
     ($name:ident, $k:expr, $config:expr, $create_cirucit:expr) => {
-        test!(@ shplonk, $name, $k, $config, $create_cirucit, ProverSHPLONK<_>, VerifierSHPLONK<_>, Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<LIMBS, BITS>>);
-        test!(@ plonk, $name, $k, $config, $create_cirucit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<LIMBS, BITS>>);
+        test!(@ shplonk, $name, $k, $config, $create_cirucit, ProverSHPLONK<_>, VerifierSHPLONK<_>, Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>);
+        test!(@ plonk, $name, $k, $config, $create_cirucit, ProverGWC<_>, VerifierGWC<_>, Plonk<Kzg<Bn256, Gwc19>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>);
     }
 }
 
@@ -58,3 +58,161 @@ test!(
     halo2_kzg_config!(true, 2),
     StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
 );
+
+#[test]
+fn test_transcript_hash_known_answers() {
+    use crate::{
+        loader::native::NativeLoader,
+        util::hash::{Poseidon2Params, PoseidonParams, Sponge, TranscriptHash},
+    };
+    use halo2_curves::{bn256::Fr, group::ff::PrimeField};
+
+    // circomlib `poseidon([1, 2])`
+    let mut sponge = Sponge::<Fr, Fr>::new(&NativeLoader, PoseidonParams::circom(3));
+    sponge.update(&[Fr::from(1), Fr::from(2)]);
+    assert_eq!(
+        sponge.squeeze(),
+        Fr::from_str_vartime(
+            "7853200120776062878684798364095072458815029376092732009249414926327459813530"
+        )
+        .unwrap()
+    );
+
+    // Known answer of Poseidon2 permutation from the reference implementation
+    let mut state = [Fr::from(0), Fr::from(1), Fr::from(2)];
+    Poseidon2Params::<Fr>::bn254_t3().permute(&mut state);
+    assert_eq!(
+        state,
+        [
+            "5297208644449048816064511434384511824916970985131888684874823260532015509555",
+            "21816030159894113985964609355246484851575571273661473159848781012394295965040",
+            "13940986381491601233448981668101586453321811870310341844570924906201623195336",
+        ]
+        .map(|value| Fr::from_str_vartime(value).unwrap())
+    );
+}
+
+#[test]
+fn test_shplonk_zk_standard_plonk_rand_succinct_verify_or_dummy() {
+    use crate::{
+        loader::{native::NativeLoader, Loader},
+        verifier::PlonkVerifier,
+    };
+    use halo2_curves::bn256::Fr;
+    use halo2_proofs::poly::commitment::ParamsProver;
+
+    type Pv = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        Blake2bWrite<_, _, _>,
+        Blake2bRead<_, _, _>,
+        Challenge255<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let svk = params.get_g()[0].into();
+    let proof = Pv::read_proof(
+        &svk,
+        &snark.protocol,
+        &snark.instances,
+        &mut Blake2bRead::<_, G1Affine, _>::init(snark.proof.as_slice()),
+    )
+    .unwrap();
+    let or_dummy = |use_dummy: u64| {
+        Pv::succinct_verify_or_dummy(
+            &svk,
+            &snark.protocol,
+            &snark.instances,
+            &proof,
+            &Fr::from(use_dummy),
+        )
+        .map(|accumulators| {
+            accumulators
+                .into_iter()
+                .map(|accumulator| (accumulator.lhs, accumulator.rhs))
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let accumulators: Vec<_> = Pv::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof)
+        .unwrap()
+        .into_iter()
+        .map(|accumulator| (accumulator.lhs, accumulator.rhs))
+        .collect();
+    assert_eq!(or_dummy(0).unwrap(), accumulators);
+    assert_ne!(or_dummy(1).unwrap(), accumulators);
+    assert!(or_dummy(2).is_err());
+
+    let loader = NativeLoader;
+    assert!(Loader::<G1Affine>::assert_bool(&loader, "bool", &Fr::from(1)).is_ok());
+    assert!(Loader::<G1Affine>::assert_bool(&loader, "bool", &Fr::from(2)).is_err());
+    assert_eq!(
+        Loader::<G1Affine>::scalar_select(&loader, &Fr::from(3), &Fr::from(4), &Fr::from(1))
+            .unwrap(),
+        Fr::from(3)
+    );
+}
+
+#[test]
+fn test_multi_scalar_multiplication() {
+    use crate::util::msm::multi_scalar_multiplication;
+    use halo2_curves::{
+        bn256::{Fr, G1},
+        group::{ff::Field, prime::PrimeCurveAffine, Curve, Group},
+    };
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    for size in [0, 1, 3, 40, 300] {
+        let mut scalars = (0..size).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let mut bases = (0..size).map(|_| G1::random(&mut rng).to_affine()).collect::<Vec<_>>();
+        // Duplicated, negated, identity bases and zero, minus one scalars
+        if size > 3 {
+            scalars.extend([scalars[0], scalars[1], Fr::zero(), scalars[2], -Fr::one()]);
+            bases.extend([bases[0], -bases[1], bases[2], G1Affine::identity(), bases[3]]);
+        }
+
+        let expected = scalars
+            .iter()
+            .zip(bases.iter())
+            .fold(G1::identity(), |acc, (scalar, base)| acc + base * scalar);
+        assert_eq!(multi_scalar_multiplication(&scalars, &bases), expected);
+    }
+
+    // Every pair in buckets shares `x`, so nothing is batch inverted
+    let scalar = Fr::random(&mut rng);
+    let base = G1::random(&mut rng).to_affine();
+    assert_eq!(multi_scalar_multiplication(&[scalar; 2], &[base; 2]), base * (scalar + scalar));
+    assert_eq!(multi_scalar_multiplication(&[scalar; 2], &[base, -base]), G1::identity());
+}
+
+#[test]
+fn test_limbs_descriptor() {
+    use crate::util::arithmetic::{fe_to_limbs, LimbsDescriptor};
+    use halo2_curves::{
+        bn256::{Fq, Fr},
+        group::ff::Field,
+    };
+
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let fe = -Fq::random(&mut rng);
+    for descriptor in [LimbsEncoding::<LIMBS, BITS>::DESCRIPTOR, LimbsDescriptor::new(4, 68)] {
+        let limbs = descriptor.fe_to_limbs::<_, Fr>(fe);
+        assert_eq!(limbs.len(), descriptor.num_limbs);
+        assert_eq!(descriptor.fe_from_limbs::<_, Fq>(&limbs), fe);
+        assert_eq!(descriptor.accumulator_indices().len(), descriptor.num_accumulator_limbs());
+    }
+    assert_eq!(
+        LimbsEncoding::<LIMBS, BITS>::DESCRIPTOR.fe_to_limbs::<_, Fr>(fe),
+        fe_to_limbs::<_, Fr, LIMBS, BITS>(fe)
+    );
+}
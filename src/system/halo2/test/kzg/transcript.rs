@@ -0,0 +1,468 @@
+use crate::{
+    loader::{
+        evm::{
+            self, encode_calldata, loader::Value as EvmValue, runtime, u256_to_fe, EvmLoader,
+            PoseidonSponge,
+        },
+        halo2::{
+            test::{Snark, SnarkWitness},
+            Halo2Loader,
+        },
+        native::NativeLoader,
+        Loader,
+    },
+    pcs::kzg::{Bdfg21, ConstLimbs, Kzg, KzgSuccinctVerifyingKey, LimbsEncoding},
+    system::halo2::{
+        aggregation::{PoseidonTranscript, RATE, T},
+        test::{
+            kzg::{halo2::Accumulation, BITS, LIMBS},
+            load_verify_circuit_degree,
+        },
+        transcript::evm::EvmTranscript,
+        Halo2VerifierCircuitConfig,
+    },
+    util::{
+        arithmetic::CurveAffine,
+        transcript::{Transcript, TranscriptRead},
+        Itertools,
+    },
+    verifier::{Plonk, PlonkVerifier},
+    Error,
+};
+use ethereum_types::U256;
+use halo2_base::utils::biguint_to_fe;
+use halo2_curves::bn256::{Bn256, Fq, Fr, G1Affine};
+use halo2_proofs::{
+    circuit::{self, Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    plonk::{self, Circuit},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use std::{cell::RefCell, fmt, marker::PhantomData, rc::Rc};
+
+type Pv = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<ConstLimbs<LIMBS, BITS>>>;
+type Svk = KzgSuccinctVerifyingKey<G1Affine>;
+type Halo2Scalar<'a, 'b> = crate::loader::halo2::Scalar<'a, 'b, G1Affine>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    ReadScalar,
+    ReadEcPoint,
+    CommonScalar,
+    CommonEcPoint,
+    SqueezeChallenge,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Element<C: CurveAffine> {
+    Scalar(C::Scalar),
+    EcPoint(C),
+}
+
+/// Element absorbed or squeezed by a transcript with the step doing it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record<C: CurveAffine> {
+    pub step: Step,
+    /// Label most recently passed to [`Transcript::common_label`], which tells
+    /// the stage of verifier doing the step, e.g. `challenge beta`.
+    pub label: Option<String>,
+    pub element: Element<C>,
+}
+
+enum Loaded<C: CurveAffine, L: Loader<C>> {
+    Scalar(L::LoadedScalar),
+    EcPoint(L::LoadedEcPoint),
+}
+
+/// Transcript recording every element passing through `inner` in order, which
+/// are loaded values to be resolved after the loader is done.
+pub struct RecordingTranscript<C: CurveAffine, L: Loader<C>, T> {
+    inner: T,
+    label: Option<String>,
+    records: Vec<(Step, Option<String>, Loaded<C, L>)>,
+}
+
+impl<C: CurveAffine, L: Loader<C>, T> RecordingTranscript<C, L, T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner, label: None, records: Vec::new() }
+    }
+
+    fn record(&mut self, step: Step, loaded: Loaded<C, L>) {
+        self.records.push((step, self.label.clone(), loaded));
+    }
+}
+
+impl<C: CurveAffine, L: Loader<C>, T: Transcript<C, L>> Transcript<C, L>
+    for RecordingTranscript<C, L, T>
+{
+    fn loader(&self) -> &L {
+        self.inner.loader()
+    }
+
+    fn squeeze_challenge(&mut self) -> L::LoadedScalar {
+        let challenge = self.inner.squeeze_challenge();
+        self.record(Step::SqueezeChallenge, Loaded::Scalar(challenge.clone()));
+        challenge
+    }
+
+    fn common_ec_point(&mut self, ec_point: &L::LoadedEcPoint) -> Result<(), Error> {
+        self.record(Step::CommonEcPoint, Loaded::EcPoint(ec_point.clone()));
+        self.inner.common_ec_point(ec_point)
+    }
+
+    fn common_scalar(&mut self, scalar: &L::LoadedScalar) -> Result<(), Error> {
+        self.record(Step::CommonScalar, Loaded::Scalar(scalar.clone()));
+        self.inner.common_scalar(scalar)
+    }
+
+    fn common_label(&mut self, label: &[u8]) {
+        self.label = Some(String::from_utf8_lossy(label).into_owned());
+        self.inner.common_label(label)
+    }
+}
+
+impl<C: CurveAffine, L: Loader<C>, T: TranscriptRead<C, L>> TranscriptRead<C, L>
+    for RecordingTranscript<C, L, T>
+{
+    fn read_scalar(&mut self) -> Result<L::LoadedScalar, Error> {
+        let scalar = self.inner.read_scalar()?;
+        self.record(Step::ReadScalar, Loaded::Scalar(scalar.clone()));
+        Ok(scalar)
+    }
+
+    fn read_ec_point(&mut self) -> Result<L::LoadedEcPoint, Error> {
+        let ec_point = self.inner.read_ec_point()?;
+        self.record(Step::ReadEcPoint, Loaded::EcPoint(ec_point.clone()));
+        Ok(ec_point)
+    }
+}
+
+/// Proof format with a transcript for every loader, so the same proof could
+/// be read by all of them.
+pub trait ProofFormat {
+    fn native(svk: &Svk, snark: &Snark<G1Affine>) -> Vec<Record<G1Affine>>;
+
+    fn evm(svk: &Svk, snark: &Snark<G1Affine>) -> Vec<Record<G1Affine>>;
+
+    fn halo2<'a, 'b>(
+        svk: &Svk,
+        loader: &Rc<Halo2Loader<'a, 'b, G1Affine>>,
+        snark: &SnarkWitness<G1Affine>,
+        instances: &[Vec<Halo2Scalar<'a, 'b>>],
+    ) -> Vec<Record<G1Affine>>;
+}
+
+/// [`PoseidonTranscript`] with parameters of aggregation.
+pub struct Poseidon;
+
+impl ProofFormat for Poseidon {
+    fn native(svk: &Svk, snark: &Snark<G1Affine>) -> Vec<Record<G1Affine>> {
+        let transcript = PoseidonTranscript::<NativeLoader, _, _>::new(snark.proof.as_slice());
+        native_records(svk, snark, transcript)
+    }
+
+    fn evm(svk: &Svk, snark: &Snark<G1Affine>) -> Vec<Record<G1Affine>> {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let mut transcript =
+            PoseidonTranscript::<Rc<EvmLoader>, _, PoseidonSponge<Fr, T, RATE>>::new(
+                loader.clone(),
+            );
+        let instances = transcript.load_instances(snark.instances.iter().map(Vec::len).collect());
+        evm_records(svk, snark, &loader, &instances, transcript)
+    }
+
+    fn halo2<'a, 'b>(
+        svk: &Svk,
+        loader: &Rc<Halo2Loader<'a, 'b, G1Affine>>,
+        snark: &SnarkWitness<G1Affine>,
+        instances: &[Vec<Halo2Scalar<'a, 'b>>],
+    ) -> Vec<Record<G1Affine>> {
+        let transcript = PoseidonTranscript::<Rc<Halo2Loader<'a, 'b, G1Affine>>, _, _>::new(
+            loader,
+            snark.proof(),
+        );
+        halo2_records(svk, snark, instances, transcript)
+    }
+}
+
+/// [`EvmTranscript`] with points uncompressed.
+pub struct Evm;
+
+impl ProofFormat for Evm {
+    fn native(svk: &Svk, snark: &Snark<G1Affine>) -> Vec<Record<G1Affine>> {
+        let transcript = EvmTranscript::<_, NativeLoader, _, _>::new(snark.proof.as_slice());
+        native_records(svk, snark, transcript)
+    }
+
+    fn evm(svk: &Svk, snark: &Snark<G1Affine>) -> Vec<Record<G1Affine>> {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(loader.clone());
+        let instances = transcript.load_instances(snark.instances.iter().map(Vec::len).collect());
+        evm_records(svk, snark, &loader, &instances, transcript)
+    }
+
+    fn halo2<'a, 'b>(
+        svk: &Svk,
+        loader: &Rc<Halo2Loader<'a, 'b, G1Affine>>,
+        snark: &SnarkWitness<G1Affine>,
+        instances: &[Vec<Halo2Scalar<'a, 'b>>],
+    ) -> Vec<Record<G1Affine>> {
+        let transcript =
+            EvmTranscript::<_, Rc<Halo2Loader<'a, 'b, G1Affine>>, _, _>::new(loader, snark.proof());
+        halo2_records(svk, snark, instances, transcript)
+    }
+}
+
+fn native_records<Tr>(svk: &Svk, snark: &Snark<G1Affine>, transcript: Tr) -> Vec<Record<G1Affine>>
+where
+    Tr: TranscriptRead<G1Affine, NativeLoader>,
+{
+    let mut transcript = RecordingTranscript::new(transcript);
+    Pv::read_proof(svk, &snark.protocol, &snark.instances, &mut transcript).unwrap();
+    transcript
+        .records
+        .into_iter()
+        .map(|(step, label, loaded)| {
+            let element = match loaded {
+                Loaded::Scalar(scalar) => Element::Scalar(scalar),
+                Loaded::EcPoint(ec_point) => Element::EcPoint(ec_point),
+            };
+            Record { step, label, element }
+        })
+        .collect()
+}
+
+/// Generates code returning every recorded element as 2 words, then executes
+/// it in a local EVM with calldata of `snark`. Returns no records if it fails
+/// to generate or execute the code, which then shows up as divergence at the
+/// first step.
+fn evm_records<Tr>(
+    svk: &Svk,
+    snark: &Snark<G1Affine>,
+    loader: &Rc<EvmLoader>,
+    instances: &[Vec<evm::Scalar>],
+    transcript: Tr,
+) -> Vec<Record<G1Affine>>
+where
+    Tr: TranscriptRead<G1Affine, Rc<EvmLoader>>,
+{
+    let mut transcript = RecordingTranscript::new(transcript);
+    if Pv::read_proof(svk, &snark.protocol, instances, &mut transcript).is_err() {
+        return Vec::new();
+    }
+
+    let ptr = loader.allocate(transcript.records.len() * 0x40);
+    for (idx, (_, _, loaded)) in transcript.records.iter().enumerate() {
+        let ptr = ptr + idx * 0x40;
+        match loaded {
+            Loaded::Scalar(scalar) => loader.copy_scalar(scalar, ptr),
+            Loaded::EcPoint(ec_point) => match ec_point.value() {
+                EvmValue::Constant((x, y)) => {
+                    loader.code_mut().push(x).push(ptr).mstore().push(y).push(ptr + 0x20).mstore();
+                }
+                EvmValue::Memory(src_ptr) => {
+                    loader
+                        .code_mut()
+                        .push(src_ptr)
+                        .mload()
+                        .push(ptr)
+                        .mstore()
+                        .push(src_ptr + 0x20)
+                        .mload()
+                        .push(ptr + 0x20)
+                        .mstore();
+                }
+                _ => unreachable!(),
+            },
+        }
+    }
+    loader.set_return_data(ptr, transcript.records.len() * 0x40);
+
+    let output =
+        runtime::call(loader.runtime_code(), encode_calldata(&snark.instances, &snark.proof));
+    if !output.success {
+        return Vec::new();
+    }
+
+    let words = output.return_data.chunks(0x20).map(U256::from_big_endian).collect_vec();
+    transcript
+        .records
+        .into_iter()
+        .zip(words.chunks(2))
+        .map(|((step, label, loaded), words)| {
+            let element = match loaded {
+                Loaded::Scalar(_) => Element::Scalar(u256_to_fe(words[0])),
+                Loaded::EcPoint(_) => Element::EcPoint(
+                    G1Affine::from_xy(u256_to_fe(words[0]), u256_to_fe(words[1])).unwrap(),
+                ),
+            };
+            Record { step, label, element }
+        })
+        .collect()
+}
+
+fn halo2_records<'a, 'b, Tr>(
+    svk: &Svk,
+    snark: &SnarkWitness<G1Affine>,
+    instances: &[Vec<Halo2Scalar<'a, 'b>>],
+    transcript: Tr,
+) -> Vec<Record<G1Affine>>
+where
+    Tr: TranscriptRead<G1Affine, Rc<Halo2Loader<'a, 'b, G1Affine>>>,
+{
+    fn known<V>(value: circuit::Value<V>) -> V {
+        let mut inner = None;
+        value.map(|value| inner = Some(value));
+        inner.expect("MockProver should have witness")
+    }
+
+    let mut transcript = RecordingTranscript::new(transcript);
+    Pv::read_proof(svk, &snark.protocol, instances, &mut transcript).unwrap();
+    transcript
+        .records
+        .into_iter()
+        .map(|(step, label, loaded)| {
+            let element = match loaded {
+                Loaded::Scalar(scalar) => {
+                    Element::Scalar(known(scalar.assigned().value().copied()))
+                }
+                Loaded::EcPoint(ec_point) => {
+                    let assigned = ec_point.assigned();
+                    let [x, y] = [&assigned.x, &assigned.y].map(|coordinate| {
+                        biguint_to_fe(&known(coordinate.value.clone()).to_biguint().unwrap())
+                    });
+                    Element::EcPoint(G1Affine::from_xy(x, y).unwrap())
+                }
+            };
+            Record { step, label, element }
+        })
+        .collect()
+}
+
+/// Circuit reading proof of `snark` by [`ProofFormat::halo2`], which records
+/// elements when synthesized.
+struct RecordingCircuit<P> {
+    svk: Svk,
+    snark: SnarkWitness<G1Affine>,
+    records: RefCell<Vec<Record<G1Affine>>>,
+    _marker: PhantomData<P>,
+}
+
+impl<P: ProofFormat> Circuit<Fr> for RecordingCircuit<P> {
+    type Config = Halo2VerifierCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            svk: self.svk,
+            snark: self.snark.without_witnesses(),
+            records: Default::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
+        Accumulation::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), plonk::Error> {
+        config.base_field_config.load_lookup_table(&mut layouter)?;
+
+        // Need to trick layouter to skip first pass in get shape mode
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let ctx = config.base_field_config.new_context(region);
+
+                let loader = Halo2Loader::new(&config.base_field_config, ctx);
+                let instances = self
+                    .snark
+                    .instances
+                    .iter()
+                    .map(|instances| {
+                        instances.iter().map(|instance| loader.assign_scalar(*instance)).collect()
+                    })
+                    .collect_vec();
+                let records = P::halo2(&self.svk, &loader, &self.snark, &instances);
+
+                // REQUIRED STEP
+                loader.finalize();
+                *self.records.borrow_mut() = records;
+
+                Ok(())
+            },
+        )
+    }
+}
+
+/// First step where transcripts of loaders disagree, with their records at
+/// that step (`None` if a transcript has ended).
+#[derive(Debug)]
+pub struct Divergence {
+    pub index: usize,
+    pub records: Vec<(&'static str, Option<Record<G1Affine>>)>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Transcripts diverge at step {}:", self.index)?;
+        for (name, record) in self.records.iter() {
+            match record {
+                Some(Record { step, label: Some(label), element }) => {
+                    writeln!(f, "  {}: {:?} in {} {:?}", name, step, label, element)?
+                }
+                Some(Record { step, label: None, element }) => {
+                    writeln!(f, "  {}: {:?} {:?}", name, step, element)?
+                }
+                None => writeln!(f, "  {}: ended", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the first step where any of named `records` disagree.
+pub fn first_divergence(records: &[(&'static str, Vec<Record<G1Affine>>)]) -> Option<Divergence> {
+    let len = records.iter().map(|(_, records)| records.len()).max().unwrap_or_default();
+    (0..len).find_map(|index| {
+        let records = records
+            .iter()
+            .map(|(name, records)| (*name, records.get(index).cloned()))
+            .collect_vec();
+        (!records.iter().map(|(_, record)| record).all_equal())
+            .then_some(Divergence { index, records })
+    })
+}
+
+/// Runs `read_proof` of `snark` with transcripts of `P` over `NativeLoader`,
+/// `Rc<EvmLoader>` executed in a local EVM and `Rc<Halo2Loader>` in
+/// `MockProver`, and returns the first step where they disagree.
+pub fn transcript_divergence<P: ProofFormat>(
+    params: &ParamsKZG<Bn256>,
+    snark: Snark<G1Affine>,
+) -> Option<Divergence> {
+    let svk: Svk = params.get_g()[0].into();
+    let native = P::native(&svk, &snark);
+    let evm = P::evm(&svk, &snark);
+
+    let circuit = RecordingCircuit::<P> {
+        svk,
+        snark: snark.into(),
+        records: Default::default(),
+        _marker: PhantomData,
+    };
+    let prover = MockProver::run(load_verify_circuit_degree(), &circuit, vec![vec![]]).unwrap();
+    prover.assert_satisfied();
+    let halo2 = circuit.records.take();
+
+    first_divergence(&[("native", native), ("evm", evm), ("halo2", halo2)])
+}
@@ -15,6 +15,8 @@ pub mod evm;
 #[cfg(feature = "loader_halo2")]
 pub mod halo2;
 
+pub mod sha256;
+
 impl<C: CurveAffine, R: Read> Transcript<C, NativeLoader> for Blake2bRead<R, C, Challenge255<C>> {
     fn loader(&self) -> &NativeLoader {
         &native::LOADER
@@ -1,13 +1,13 @@
 use crate::{
     loader::{
-        evm::{loader::Value, u256_to_fe, EcPoint, EvmLoader, MemoryChunk, Scalar},
+        evm::{fe_to_u256, loader::Value, u256_to_fe, EcPoint, EvmLoader, MemoryChunk, Scalar},
         native::{self, NativeLoader},
         Loader,
     },
     system::halo2::aggregation::KZG_QUERY_INSTANCE,
     util::{
-        arithmetic::{Coordinates, CurveAffine, PrimeField},
-        transcript::{Transcript, TranscriptRead},
+        arithmetic::{Coordinates, CurveAffine, Field, PrimeField},
+        transcript::{label_to_fe, Transcript, TranscriptRead},
         Itertools,
     },
     Error,
@@ -17,7 +17,6 @@ use halo2_proofs::transcript::EncodedChallenge;
 use sha3::{Digest, Keccak256};
 use std::{
     io::{self, Read, Write},
-    iter,
     marker::PhantomData,
     rc::Rc,
 };
@@ -26,9 +25,23 @@ pub struct EvmTranscript<C: CurveAffine, L: Loader<C>, S, B> {
     stream: S,
     buf: B,
     query_instance_reset: bool,
+    compressed: bool,
+    domain_separation: bool,
+    num_read: usize,
+    num_challenge: usize,
     _marker: PhantomData<C>,
 }
 
+impl<C: CurveAffine, L: Loader<C>, S, B> EvmTranscript<C, L, S, B> {
+    /// Sets whether labels passed to [`Transcript::common_label`] are absorbed
+    /// as a word of [`label_to_fe`], which is disabled by default. Prover and
+    /// verifier should agree on it.
+    pub fn set_domain_separation(mut self, domain_separation: bool) -> Self {
+        self.domain_separation = domain_separation;
+        self
+    }
+}
+
 impl<C> EvmTranscript<C, Rc<EvmLoader>, usize, MemoryChunk>
 where
     C: CurveAffine,
@@ -41,23 +54,50 @@ where
         if !KZG_QUERY_INSTANCE {
             buf.extend(0x20);
         }
-        Self { loader, stream: 0, buf, query_instance_reset: false, _marker: PhantomData }
+        Self {
+            loader,
+            stream: 0,
+            buf,
+            query_instance_reset: false,
+            compressed: false,
+            domain_separation: false,
+            num_read: 0,
+            num_challenge: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a transcript reading points in compressed form, see
+    /// [`EvmLoader::calldataload_ec_point_compressed`].
+    pub fn new_compressed(loader: Rc<EvmLoader>) -> Self {
+        Self { compressed: true, ..Self::new(loader) }
     }
 
     pub fn load_instances(&mut self, num_instance: Vec<usize>) -> Vec<Vec<Scalar>> {
         num_instance
             .into_iter()
-            .map(|len| {
-                iter::repeat_with(|| {
-                    let scalar = self.loader.calldataload_scalar(self.stream);
-                    self.stream += 0x20;
-                    scalar
-                })
-                .take(len)
-                .collect_vec()
+            .enumerate()
+            .map(|(i, len)| {
+                (0..len)
+                    .map(|j| {
+                        self.loader.code_mut().start_annotation(format!("instance {} {}", i, j));
+                        let scalar = self.loader.calldataload_scalar(self.stream);
+                        self.loader.code_mut().end_annotation();
+                        self.stream += 0x20;
+                        scalar
+                    })
+                    .collect_vec()
             })
             .collect()
     }
+
+    fn start_proof_element_annotation(&mut self) {
+        self.loader.code_mut().start_annotation(format!(
+            "proof element {} at calldata {:#x}",
+            self.num_read, self.stream
+        ));
+        self.num_read += 1;
+    }
 }
 
 impl<C> Transcript<C, Rc<EvmLoader>> for EvmTranscript<C, Rc<EvmLoader>, usize, MemoryChunk>
@@ -70,6 +110,8 @@ where
     }
 
     fn squeeze_challenge(&mut self) -> Scalar {
+        self.loader.code_mut().start_annotation(format!("challenge {}", self.num_challenge));
+        self.num_challenge += 1;
         let len = if self.buf.len() == 0x20 {
             assert_eq!(self.loader.ptr(), self.buf.end());
             self.loader.code_mut().push(1).push(self.buf.end()).mstore8();
@@ -95,6 +137,7 @@ where
 
         self.buf.reset(dup_hash_ptr);
         self.buf.extend(0x20);
+        self.loader.code_mut().end_annotation();
 
         self.loader.scalar(Value::Memory(challenge_ptr))
     }
@@ -138,6 +181,31 @@ where
         }
         Ok(())
     }
+
+    fn common_label(&mut self, label: &[u8]) {
+        if !self.domain_separation {
+            return;
+        }
+        // Move buffer to the end of memory to keep it contiguous, which only
+        // happens when something has been allocated since last absorbing.
+        if self.loader.ptr() != self.buf.end() {
+            let ptr = self.loader.allocate(self.buf.len());
+            for offset in (0..self.buf.len()).step_by(0x20) {
+                self.loader
+                    .code_mut()
+                    .push(self.buf.ptr() + offset)
+                    .mload()
+                    .push(ptr + offset)
+                    .mstore();
+            }
+            let len = self.buf.len();
+            self.buf.reset(ptr);
+            self.buf.extend(len);
+        }
+        let ptr = self.loader.allocate(0x20);
+        self.loader.code_mut().push(fe_to_u256(label_to_fe::<C::Scalar>(label))).push(ptr).mstore();
+        self.buf.extend(0x20);
+    }
 }
 
 impl<C> TranscriptRead<C, Rc<EvmLoader>> for EvmTranscript<C, Rc<EvmLoader>, usize, MemoryChunk>
@@ -146,16 +214,27 @@ where
     C::Scalar: PrimeField<Repr = [u8; 0x20]>,
 {
     fn read_scalar(&mut self) -> Result<Scalar, Error> {
+        self.start_proof_element_annotation();
         let scalar = self.loader.calldataload_scalar(self.stream);
         self.stream += 0x20;
         self.common_scalar(&scalar)?;
+        self.loader.code_mut().end_annotation();
         Ok(scalar)
     }
 
     fn read_ec_point(&mut self) -> Result<EcPoint, Error> {
-        let ec_point = self.loader.calldataload_ec_point(self.stream);
-        self.stream += 0x40;
+        self.start_proof_element_annotation();
+        let ec_point = if self.compressed {
+            let ec_point = self.loader.calldataload_ec_point_compressed(self.stream);
+            self.stream += 0x20;
+            ec_point
+        } else {
+            let ec_point = self.loader.calldataload_ec_point(self.stream);
+            self.stream += 0x40;
+            ec_point
+        };
         self.common_ec_point(&ec_point)?;
+        self.loader.code_mut().end_annotation();
         Ok(ec_point)
     }
 }
@@ -170,9 +249,20 @@ where
             stream,
             buf: Vec::new(),
             query_instance_reset: false,
+            compressed: false,
+            domain_separation: false,
+            num_read: 0,
+            num_challenge: 0,
             _marker: PhantomData,
         }
     }
+
+    /// Returns a transcript reading and writing points in compressed form,
+    /// which is `x` in big-endian with the most significant bit set when `y` is
+    /// odd. Points are still absorbed uncompressed.
+    pub fn new_compressed(stream: S) -> Self {
+        Self { compressed: true, ..Self::new(stream) }
+    }
 }
 
 impl<C, S> Transcript<C, NativeLoader> for EvmTranscript<C, NativeLoader, S, Vec<u8>>
@@ -217,6 +307,12 @@ where
 
         Ok(())
     }
+
+    fn common_label(&mut self, label: &[u8]) {
+        if self.domain_separation {
+            self.buf.extend(label_to_fe::<C::Scalar>(label).to_repr().as_ref().iter().rev());
+        }
+    }
 }
 
 impl<C, S> TranscriptRead<C, NativeLoader> for EvmTranscript<C, NativeLoader, S, Vec<u8>>
@@ -239,6 +335,28 @@ where
     }
 
     fn read_ec_point(&mut self) -> Result<C, Error> {
+        let invalid_ec_point = || {
+            Error::Transcript(
+                io::ErrorKind::Other,
+                "Invalid elliptic curve point encoding in proof".to_string(),
+            )
+        };
+
+        if self.compressed {
+            let mut x = <C::Base as PrimeField>::Repr::default();
+            self.stream
+                .read_exact(x.as_mut())
+                .map_err(|err| Error::Transcript(err.kind(), err.to_string()))?;
+            x.as_mut().reverse();
+            let is_odd = x.as_ref()[31] >> 7 == 1;
+            x.as_mut()[31] &= 0x7f;
+            let ec_point = Option::from(<C::Base as PrimeField>::from_repr(x))
+                .and_then(|x| decompress::<C>(x, is_odd))
+                .ok_or_else(invalid_ec_point)?;
+            self.common_ec_point(&ec_point)?;
+            return Ok(ec_point);
+        }
+
         let [mut x, mut y] = [<C::Base as PrimeField>::Repr::default(); 2];
         for repr in [&mut x, &mut y] {
             self.stream
@@ -248,18 +366,21 @@ where
         }
         let x = Option::from(<C::Base as PrimeField>::from_repr(x));
         let y = Option::from(<C::Base as PrimeField>::from_repr(y));
-        let ec_point =
-            x.zip(y).and_then(|(x, y)| Option::from(C::from_xy(x, y))).ok_or_else(|| {
-                Error::Transcript(
-                    io::ErrorKind::Other,
-                    "Invalid elliptic curve point encoding in proof".to_string(),
-                )
-            })?;
+        let ec_point = x
+            .zip(y)
+            .and_then(|(x, y)| Option::from(C::from_xy(x, y)))
+            .ok_or_else(invalid_ec_point)?;
         self.common_ec_point(&ec_point)?;
         Ok(ec_point)
     }
 }
 
+fn decompress<C: CurveAffine>(x: C::Base, is_odd: bool) -> Option<C> {
+    let y = Option::<C::Base>::from((x.square() * x + C::b()).sqrt())?;
+    let y = if bool::from(y.is_odd()) == is_odd { y } else { -y };
+    Option::from(C::from_xy(x, y))
+}
+
 impl<C, S> EvmTranscript<C, NativeLoader, S, Vec<u8>>
 where
     C: CurveAffine,
@@ -372,6 +493,14 @@ where
         })?;
         let mut x = coords.x().to_repr();
         let mut y = coords.y().to_repr();
+        if self.compressed {
+            assert!(<C::Base as PrimeField>::NUM_BITS < 256);
+            if bool::from(coords.y().is_odd()) {
+                x.as_mut()[31] |= 0x80;
+            }
+            x.as_mut().reverse();
+            return self.stream_mut().write_all(x.as_ref());
+        }
         x.as_mut().reverse();
         y.as_mut().reverse();
         self.stream_mut().write_all(x.as_ref())?;
@@ -400,3 +529,259 @@ where
         self.finalize()
     }
 }
+
+/// [`EvmTranscript`] with compressed points, which implements
+/// [`TranscriptWriterBuffer`] and [`TranscriptReadBuffer`] for halo2 prover and
+/// verifier to create and verify proofs in compressed form.
+///
+/// [`TranscriptWriterBuffer`]: halo2_proofs::transcript::TranscriptWriterBuffer
+/// [`TranscriptReadBuffer`]: halo2_proofs::transcript::TranscriptReadBuffer
+pub struct CompressedEvmTranscript<C: CurveAffine, S>(EvmTranscript<C, NativeLoader, S, Vec<u8>>);
+
+impl<C: CurveAffine, S> CompressedEvmTranscript<C, S> {
+    pub fn new(stream: S) -> Self {
+        Self(EvmTranscript::new_compressed(stream))
+    }
+
+    pub fn into_inner(self) -> EvmTranscript<C, NativeLoader, S, Vec<u8>> {
+        self.0
+    }
+}
+
+impl<C, S> halo2_proofs::transcript::Transcript<C, ChallengeEvm<C>>
+    for CompressedEvmTranscript<C, S>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn squeeze_challenge(&mut self) -> ChallengeEvm<C> {
+        halo2_proofs::transcript::Transcript::squeeze_challenge(&mut self.0)
+    }
+
+    fn common_point(&mut self, ec_point: C) -> io::Result<()> {
+        halo2_proofs::transcript::Transcript::common_point(&mut self.0, ec_point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        halo2_proofs::transcript::Transcript::common_scalar(&mut self.0, scalar)
+    }
+}
+
+impl<C, R: Read> halo2_proofs::transcript::TranscriptRead<C, ChallengeEvm<C>>
+    for CompressedEvmTranscript<C, R>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        halo2_proofs::transcript::TranscriptRead::read_point(&mut self.0)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        halo2_proofs::transcript::TranscriptRead::read_scalar(&mut self.0)
+    }
+}
+
+impl<C, R: Read> halo2_proofs::transcript::TranscriptReadBuffer<R, C, ChallengeEvm<C>>
+    for CompressedEvmTranscript<C, R>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn init(reader: R) -> Self {
+        Self::new(reader)
+    }
+}
+
+impl<C, W: Write> halo2_proofs::transcript::TranscriptWrite<C, ChallengeEvm<C>>
+    for CompressedEvmTranscript<C, W>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn write_point(&mut self, ec_point: C) -> io::Result<()> {
+        halo2_proofs::transcript::TranscriptWrite::write_point(&mut self.0, ec_point)
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        halo2_proofs::transcript::TranscriptWrite::write_scalar(&mut self.0, scalar)
+    }
+}
+
+impl<C, W: Write> halo2_proofs::transcript::TranscriptWriterBuffer<W, C, ChallengeEvm<C>>
+    for CompressedEvmTranscript<C, W>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn init(writer: W) -> Self {
+        Self::new(writer)
+    }
+
+    fn finalize(self) -> W {
+        self.0.finalize()
+    }
+}
+
+#[cfg(feature = "loader_halo2")]
+mod halo2 {
+    use super::{decompress, EvmTranscript};
+    use crate::{
+        loader::halo2::{
+            bits::{self, Bit},
+            keccak_chip::KeccakChip,
+            EcPoint, Halo2Loader, Scalar,
+        },
+        util::{
+            arithmetic::{CurveAffine, PrimeField},
+            transcript::{label_to_fe, Transcript, TranscriptRead},
+        },
+        Error,
+    };
+    use halo2_proofs::circuit;
+    use std::{io::Read, marker::PhantomData, rc::Rc};
+
+    type Bits<'a, 'b, C> = Vec<Bit<Scalar<'a, 'b, C>>>;
+
+    /// Converts between little-endian bits of an integer and its big-endian
+    /// bytes in bits, which is an involution.
+    fn reverse_bytes<T: Clone>(bits: &[T]) -> Vec<T> {
+        bits.chunks(8).rev().flatten().cloned().collect()
+    }
+
+    impl<'a, 'b, C, R> EvmTranscript<C, Rc<Halo2Loader<'a, 'b, C>>, circuit::Value<R>, Bits<'a, 'b, C>>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        pub fn new(loader: &Rc<Halo2Loader<'a, 'b, C>>, stream: circuit::Value<R>) -> Self {
+            Self {
+                loader: loader.clone(),
+                stream,
+                buf: Vec::new(),
+                query_instance_reset: false,
+                compressed: false,
+                domain_separation: false,
+                num_read: 0,
+                num_challenge: 0,
+                _marker: PhantomData,
+            }
+        }
+
+        /// Returns a transcript reading points in compressed form, which are
+        /// still absorbed uncompressed.
+        pub fn new_compressed(
+            loader: &Rc<Halo2Loader<'a, 'b, C>>,
+            stream: circuit::Value<R>,
+        ) -> Self {
+            Self { compressed: true, ..Self::new(loader, stream) }
+        }
+
+        /// Absorbs little-endian `bits` of a 256-bit word in big-endian.
+        fn absorb(&mut self, mut bits: Bits<'a, 'b, C>) {
+            bits.resize(0x100, Bit::Constant(false));
+            self.buf.extend(reverse_bytes(&bits));
+        }
+    }
+
+    impl<'a, 'b, C, R> Transcript<C, Rc<Halo2Loader<'a, 'b, C>>>
+        for EvmTranscript<C, Rc<Halo2Loader<'a, 'b, C>>, circuit::Value<R>, Bits<'a, 'b, C>>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        fn loader(&self) -> &Rc<Halo2Loader<'a, 'b, C>> {
+            &self.loader
+        }
+
+        fn squeeze_challenge(&mut self) -> Scalar<'a, 'b, C> {
+            let mut keccak = KeccakChip::new();
+            keccak.update(&self.buf);
+            // Same as `EvmTranscript` of `EvmLoader`, which appends `0x01` when
+            // there is only the previous hash in buffer.
+            if self.buf.len() == 0x100 {
+                keccak.update(&Bit::from_u64(1, 8));
+            }
+            let hash = keccak.squeeze();
+            self.buf = hash.clone();
+            self.num_challenge += 1;
+            bits::compose(&self.loader, &reverse_bytes(&hash))
+        }
+
+        fn common_scalar(&mut self, scalar: &Scalar<'a, 'b, C>) -> Result<(), Error> {
+            let bits = self.loader.assign_canonical_scalar_bits(scalar);
+            self.absorb(bits);
+            Ok(())
+        }
+
+        fn common_ec_point(&mut self, ec_point: &EcPoint<'a, 'b, C>) -> Result<(), Error> {
+            for bits in self.loader.assign_canonical_ec_point_bits(ec_point) {
+                self.absorb(bits);
+            }
+            Ok(())
+        }
+
+        fn common_label(&mut self, label: &[u8]) {
+            if self.domain_separation {
+                let label = label_to_fe::<C::Scalar>(label);
+                self.absorb(Bit::from_bytes(label.to_repr().as_ref()));
+            }
+        }
+    }
+
+    impl<'a, 'b, C, R> TranscriptRead<C, Rc<Halo2Loader<'a, 'b, C>>>
+        for EvmTranscript<C, Rc<Halo2Loader<'a, 'b, C>>, circuit::Value<R>, Bits<'a, 'b, C>>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        R: Read,
+    {
+        fn read_scalar(&mut self) -> Result<Scalar<'a, 'b, C>, Error> {
+            let scalar = self.stream.as_mut().and_then(|stream| {
+                let mut data = [0; 0x20];
+                if stream.read_exact(data.as_mut()).is_err() {
+                    return circuit::Value::unknown();
+                }
+                data.reverse();
+                Option::<C::Scalar>::from(C::Scalar::from_repr(data))
+                    .map(circuit::Value::known)
+                    .unwrap_or_else(circuit::Value::unknown)
+            });
+            let scalar = self.loader.assign_scalar(scalar);
+            self.num_read += 1;
+            self.common_scalar(&scalar)?;
+            Ok(scalar)
+        }
+
+        fn read_ec_point(&mut self) -> Result<EcPoint<'a, 'b, C>, Error> {
+            let compressed = self.compressed;
+            let ec_point = self.stream.as_mut().and_then(|stream| {
+                let mut read_base = || {
+                    let mut repr = <C::Base as PrimeField>::Repr::default();
+                    stream.read_exact(repr.as_mut()).ok()?;
+                    repr.as_mut().reverse();
+                    Some(repr)
+                };
+                let ec_point = if compressed {
+                    read_base().and_then(|mut x| {
+                        let is_odd = x.as_ref()[31] >> 7 == 1;
+                        x.as_mut()[31] &= 0x7f;
+                        Option::from(<C::Base as PrimeField>::from_repr(x))
+                            .and_then(|x| decompress::<C>(x, is_odd))
+                    })
+                } else {
+                    read_base().zip(read_base()).and_then(|(x, y)| {
+                        let x = Option::from(<C::Base as PrimeField>::from_repr(x));
+                        let y = Option::from(<C::Base as PrimeField>::from_repr(y));
+                        x.zip(y).and_then(|(x, y)| Option::from(C::from_xy(x, y)))
+                    })
+                };
+                ec_point.map(circuit::Value::known).unwrap_or_else(circuit::Value::unknown)
+            });
+            let ec_point = self.loader.assign_ec_point(ec_point);
+            self.num_read += 1;
+            self.common_ec_point(&ec_point)?;
+            Ok(ec_point)
+        }
+    }
+}
@@ -1,15 +1,18 @@
 use crate::{
     loader::{
         halo2::{
+            bits::{self, Bit},
+            blake2b_chip::Blake2bChip,
             loader::{AssignedEcPoint, EcPoint, Halo2Loader, Scalar, Value},
             poseidon_chip::PoseidonChip,
         },
         native::NativeLoader,
-        Loader,
+        Loader, ScalarLoader,
     },
     util::{
-        arithmetic::{Coordinates, CurveAffine, PrimeField},
-        transcript::{Transcript, TranscriptRead, TranscriptWrite},
+        arithmetic::{fe_to_fe, Coordinates, CurveAffine, PrimeField},
+        hash::TranscriptHash,
+        transcript::{label_to_fe, Transcript, TranscriptRead, TranscriptWrite},
     },
     Error,
 };
@@ -37,9 +40,30 @@ pub struct PoseidonTranscript<
     loader: L,
     stream: S,
     buf: B,
+    domain_separation: bool,
     _marker: PhantomData<C>,
 }
 
+impl<
+        C: CurveAffine,
+        L: Loader<C>,
+        S,
+        B,
+        const T: usize,
+        const RATE: usize,
+        const R_F: usize,
+        const R_P: usize,
+    > PoseidonTranscript<C, L, S, B, T, RATE, R_F, R_P>
+{
+    /// Sets whether labels passed to [`Transcript::common_label`] are absorbed
+    /// as [`label_to_fe`], which is disabled by default. Prover and verifier
+    /// should agree on it.
+    pub fn set_domain_separation(mut self, domain_separation: bool) -> Self {
+        self.domain_separation = domain_separation;
+        self
+    }
+}
+
 impl<
         'a,
         'b,
@@ -66,6 +90,7 @@ impl<
             loader: loader.clone(),
             stream,
             buf: PoseidonChip::new(loader.clone(), R_F, R_P),
+            domain_separation: false,
             _marker: PhantomData,
         }
     }
@@ -118,6 +143,12 @@ impl<
         self.buf.update(&self.encode_point(&ec_point.assigned())[..]);
         Ok(())
     }
+
+    fn common_label(&mut self, label: &[u8]) {
+        if self.domain_separation {
+            self.buf.update(&[self.loader.load_const(&label_to_fe::<C::Scalar>(label))]);
+        }
+    }
 }
 
 impl<
@@ -176,7 +207,13 @@ impl<C: CurveAffine, S, const T: usize, const RATE: usize, const R_F: usize, con
     PoseidonTranscript<C, NativeLoader, S, Poseidon<C::Scalar, T, RATE>, T, RATE, R_F, R_P>
 {
     pub fn new(stream: S) -> Self {
-        Self { loader: NativeLoader, stream, buf: Poseidon::new(R_F, R_P), _marker: PhantomData }
+        Self {
+            loader: NativeLoader,
+            stream,
+            buf: Poseidon::new(R_F, R_P),
+            domain_separation: false,
+            _marker: PhantomData,
+        }
     }
 }
 
@@ -209,6 +246,12 @@ impl<C: CurveAffine, S, const T: usize, const RATE: usize, const R_F: usize, con
         self.buf.update(&[x, y]);
         Ok(())
     }
+
+    fn common_label(&mut self, label: &[u8]) {
+        if self.domain_separation {
+            self.buf.update(&[label_to_fe::<C::Scalar>(label)]);
+        }
+    }
 }
 
 impl<
@@ -422,3 +465,645 @@ impl<
         self.finalize()
     }
 }
+
+const BLAKE2B_PERSONALIZATION: &[u8; 16] = b"Halo2-Transcript";
+const BLAKE2B_PREFIX_CHALLENGE: u8 = 0;
+const BLAKE2B_PREFIX_POINT: u8 = 1;
+const BLAKE2B_PREFIX_SCALAR: u8 = 2;
+
+/// In-circuit counterpart of halo2's `Blake2bRead` with `Challenge255`, to
+/// verify proofs created with the default halo2 transcript.
+pub struct Blake2bTranscript<C: CurveAffine, L: Loader<C>, S, B> {
+    loader: L,
+    stream: S,
+    buf: B,
+    _marker: PhantomData<C>,
+}
+
+impl<'a, 'b, R: Read, C: CurveAffine>
+    Blake2bTranscript<
+        C,
+        Rc<Halo2Loader<'a, 'b, C>>,
+        circuit::Value<R>,
+        Blake2bChip<C::Scalar, Scalar<'a, 'b, C>>,
+    >
+{
+    pub fn new(loader: &Rc<Halo2Loader<'a, 'b, C>>, stream: circuit::Value<R>) -> Self {
+        Self {
+            loader: loader.clone(),
+            stream,
+            buf: Blake2bChip::new(BLAKE2B_PERSONALIZATION),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, 'b, R: Read, C: CurveAffine> Transcript<C, Rc<Halo2Loader<'a, 'b, C>>>
+    for Blake2bTranscript<
+        C,
+        Rc<Halo2Loader<'a, 'b, C>>,
+        circuit::Value<R>,
+        Blake2bChip<C::Scalar, Scalar<'a, 'b, C>>,
+    >
+{
+    fn loader(&self) -> &Rc<Halo2Loader<'a, 'b, C>> {
+        &self.loader
+    }
+
+    fn squeeze_challenge(&mut self) -> Scalar<'a, 'b, C> {
+        self.buf.update(&Bit::from_u64(BLAKE2B_PREFIX_CHALLENGE as u64, 8));
+        // Same as `Challenge255`, which interprets 64 bytes of digest as
+        // little-endian integer and reduces it by the modulus.
+        bits::compose(&self.loader, &self.buf.squeeze())
+    }
+
+    fn common_scalar(&mut self, scalar: &Scalar<'a, 'b, C>) -> Result<(), Error> {
+        let mut bits = self.loader.assign_canonical_scalar_bits(scalar);
+        bits.resize(
+            <C::Scalar as PrimeField>::Repr::default().as_ref().len() * 8,
+            Bit::Constant(false),
+        );
+        self.buf.update(&Bit::from_u64(BLAKE2B_PREFIX_SCALAR as u64, 8));
+        self.buf.update(&bits);
+        Ok(())
+    }
+
+    fn common_ec_point(&mut self, ec_point: &EcPoint<'a, 'b, C>) -> Result<(), Error> {
+        self.buf.update(&Bit::from_u64(BLAKE2B_PREFIX_POINT as u64, 8));
+        for mut bits in self.loader.assign_canonical_ec_point_bits(ec_point) {
+            bits.resize(
+                <C::Base as PrimeField>::Repr::default().as_ref().len() * 8,
+                Bit::Constant(false),
+            );
+            self.buf.update(&bits);
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'b, R: Read, C: CurveAffine> TranscriptRead<C, Rc<Halo2Loader<'a, 'b, C>>>
+    for Blake2bTranscript<
+        C,
+        Rc<Halo2Loader<'a, 'b, C>>,
+        circuit::Value<R>,
+        Blake2bChip<C::Scalar, Scalar<'a, 'b, C>>,
+    >
+{
+    fn read_scalar(&mut self) -> Result<Scalar<'a, 'b, C>, Error> {
+        let scalar = self.stream.as_mut().and_then(|stream| {
+            let mut data = <C::Scalar as PrimeField>::Repr::default();
+            if stream.read_exact(data.as_mut()).is_err() {
+                return circuit::Value::unknown();
+            }
+            Option::<C::Scalar>::from(C::Scalar::from_repr(data))
+                .map(circuit::Value::known)
+                .unwrap_or_else(circuit::Value::unknown)
+        });
+        let scalar = self.loader.assign_scalar(scalar);
+        self.common_scalar(&scalar)?;
+        Ok(scalar)
+    }
+
+    fn read_ec_point(&mut self) -> Result<EcPoint<'a, 'b, C>, Error> {
+        let ec_point = self.stream.as_mut().and_then(|stream| {
+            let mut compressed = C::Repr::default();
+            if stream.read_exact(compressed.as_mut()).is_err() {
+                return circuit::Value::unknown();
+            }
+            Option::<C>::from(C::from_bytes(&compressed))
+                .map(circuit::Value::known)
+                .unwrap_or_else(circuit::Value::unknown)
+        });
+        let ec_point = self.loader.assign_ec_point(ec_point);
+        self.common_ec_point(&ec_point)?;
+        Ok(ec_point)
+    }
+}
+
+/// Transcript over any [`TranscriptHash`], e.g. [`Sponge`] with parameters
+/// selected at runtime, in the same encoding as [`PoseidonTranscript`].
+///
+/// Since halo2's `TranscriptReadBuffer` and `TranscriptWriterBuffer` can't
+/// take the hash, native one should be created by `new` and passed to
+/// `create_proof` and `verify_proof` directly.
+///
+/// [`Sponge`]: crate::util::hash::Sponge
+pub struct HashTranscript<C: CurveAffine, L: Loader<C>, S, H> {
+    loader: L,
+    stream: S,
+    buf: H,
+    domain_separation: bool,
+    _marker: PhantomData<C>,
+}
+
+impl<C: CurveAffine, L: Loader<C>, S, H> HashTranscript<C, L, S, H> {
+    /// Sets whether labels passed to [`Transcript::common_label`] are absorbed
+    /// as [`label_to_fe`], which is disabled by default. Prover and verifier
+    /// should agree on it.
+    pub fn set_domain_separation(mut self, domain_separation: bool) -> Self {
+        self.domain_separation = domain_separation;
+        self
+    }
+}
+
+impl<'a, 'b, R: Read, C: CurveAffine, H: TranscriptHash<C::Scalar, Scalar<'a, 'b, C>>>
+    HashTranscript<C, Rc<Halo2Loader<'a, 'b, C>>, circuit::Value<R>, H>
+{
+    pub fn new(loader: &Rc<Halo2Loader<'a, 'b, C>>, stream: circuit::Value<R>, hash: H) -> Self {
+        Self {
+            loader: loader.clone(),
+            stream,
+            buf: hash,
+            domain_separation: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, 'b, R: Read, C: CurveAffine, H: TranscriptHash<C::Scalar, Scalar<'a, 'b, C>>>
+    Transcript<C, Rc<Halo2Loader<'a, 'b, C>>>
+    for HashTranscript<C, Rc<Halo2Loader<'a, 'b, C>>, circuit::Value<R>, H>
+{
+    fn loader(&self) -> &Rc<Halo2Loader<'a, 'b, C>> {
+        &self.loader
+    }
+
+    fn squeeze_challenge(&mut self) -> Scalar<'a, 'b, C> {
+        self.buf.squeeze()
+    }
+
+    fn common_scalar(&mut self, scalar: &Scalar<'a, 'b, C>) -> Result<(), Error> {
+        self.buf.update(from_ref(scalar));
+        Ok(())
+    }
+
+    fn common_ec_point(&mut self, ec_point: &EcPoint<'a, 'b, C>) -> Result<(), Error> {
+        let assigned = ec_point.assigned();
+        let encoded = [assigned.x.native.clone(), assigned.y.native.clone()]
+            .map(|native| self.loader.scalar_from_assigned(native));
+        self.buf.update(&encoded);
+        Ok(())
+    }
+
+    fn common_label(&mut self, label: &[u8]) {
+        if self.domain_separation {
+            self.buf.update(&[self.loader.load_const(&label_to_fe::<C::Scalar>(label))]);
+        }
+    }
+}
+
+impl<'a, 'b, R: Read, C: CurveAffine, H: TranscriptHash<C::Scalar, Scalar<'a, 'b, C>>>
+    TranscriptRead<C, Rc<Halo2Loader<'a, 'b, C>>>
+    for HashTranscript<C, Rc<Halo2Loader<'a, 'b, C>>, circuit::Value<R>, H>
+{
+    fn read_scalar(&mut self) -> Result<Scalar<'a, 'b, C>, Error> {
+        let scalar = self.stream.as_mut().and_then(|stream| {
+            let mut data = <C::Scalar as PrimeField>::Repr::default();
+            if stream.read_exact(data.as_mut()).is_err() {
+                return circuit::Value::unknown();
+            }
+            Option::<C::Scalar>::from(C::Scalar::from_repr(data))
+                .map(circuit::Value::known)
+                .unwrap_or_else(circuit::Value::unknown)
+        });
+        let scalar = self.loader.assign_scalar(scalar);
+        self.common_scalar(&scalar)?;
+        Ok(scalar)
+    }
+
+    fn read_ec_point(&mut self) -> Result<EcPoint<'a, 'b, C>, Error> {
+        let ec_point = self.stream.as_mut().and_then(|stream| {
+            let mut compressed = C::Repr::default();
+            if stream.read_exact(compressed.as_mut()).is_err() {
+                return circuit::Value::unknown();
+            }
+            Option::<C>::from(C::from_bytes(&compressed))
+                .map(circuit::Value::known)
+                .unwrap_or_else(circuit::Value::unknown)
+        });
+        let ec_point = self.loader.assign_ec_point(ec_point);
+        self.common_ec_point(&ec_point)?;
+        Ok(ec_point)
+    }
+}
+
+impl<C: CurveAffine, S, H: TranscriptHash<C::Scalar, C::Scalar>>
+    HashTranscript<C, NativeLoader, S, H>
+{
+    pub fn new(stream: S, hash: H) -> Self {
+        Self {
+            loader: NativeLoader,
+            stream,
+            buf: hash,
+            domain_separation: false,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn stream_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn finalize(self) -> S {
+        self.stream
+    }
+}
+
+impl<C: CurveAffine, S, H: TranscriptHash<C::Scalar, C::Scalar>> Transcript<C, NativeLoader>
+    for HashTranscript<C, NativeLoader, S, H>
+{
+    fn loader(&self) -> &NativeLoader {
+        &self.loader
+    }
+
+    fn squeeze_challenge(&mut self) -> C::Scalar {
+        self.buf.squeeze()
+    }
+
+    fn common_scalar(&mut self, scalar: &C::Scalar) -> Result<(), Error> {
+        self.buf.update(&[*scalar]);
+        Ok(())
+    }
+
+    fn common_ec_point(&mut self, ec_point: &C) -> Result<(), Error> {
+        let coords: Coordinates<C> = Option::from(ec_point.coordinates()).ok_or_else(|| {
+            Error::Transcript(
+                io::ErrorKind::Other,
+                "Cannot write points at infinity to the transcript".to_string(),
+            )
+        })?;
+        self.buf.update(&[fe_to_fe(*coords.x()), fe_to_fe(*coords.y())]);
+        Ok(())
+    }
+
+    fn common_label(&mut self, label: &[u8]) {
+        if self.domain_separation {
+            self.buf.update(&[label_to_fe::<C::Scalar>(label)]);
+        }
+    }
+}
+
+impl<C: CurveAffine, R: Read, H: TranscriptHash<C::Scalar, C::Scalar>>
+    TranscriptRead<C, NativeLoader> for HashTranscript<C, NativeLoader, R, H>
+{
+    fn read_scalar(&mut self) -> Result<C::Scalar, Error> {
+        let mut data = <C::Scalar as PrimeField>::Repr::default();
+        self.stream
+            .read_exact(data.as_mut())
+            .map_err(|err| Error::Transcript(err.kind(), err.to_string()))?;
+        let scalar = C::Scalar::from_repr_vartime(data).ok_or_else(|| {
+            Error::Transcript(io::ErrorKind::Other, "Invalid scalar encoding in proof".to_string())
+        })?;
+        self.common_scalar(&scalar)?;
+        Ok(scalar)
+    }
+
+    fn read_ec_point(&mut self) -> Result<C, Error> {
+        let mut data = C::Repr::default();
+        self.stream
+            .read_exact(data.as_mut())
+            .map_err(|err| Error::Transcript(err.kind(), err.to_string()))?;
+        let ec_point =
+            Option::<C>::from(<C as GroupEncoding>::from_bytes(&data)).ok_or_else(|| {
+                Error::Transcript(
+                    io::ErrorKind::Other,
+                    "Invalid elliptic curve point encoding in proof".to_string(),
+                )
+            })?;
+        self.common_ec_point(&ec_point)?;
+        Ok(ec_point)
+    }
+}
+
+impl<C: CurveAffine, W: Write, H: TranscriptHash<C::Scalar, C::Scalar>> TranscriptWrite<C>
+    for HashTranscript<C, NativeLoader, W, H>
+{
+    fn write_scalar(&mut self, scalar: C::Scalar) -> Result<(), Error> {
+        self.common_scalar(&scalar)?;
+        let data = scalar.to_repr();
+        self.stream_mut().write_all(data.as_ref()).map_err(|err| {
+            Error::Transcript(err.kind(), "Failed to write scalar to transcript".to_string())
+        })
+    }
+
+    fn write_ec_point(&mut self, ec_point: C) -> Result<(), Error> {
+        self.common_ec_point(&ec_point)?;
+        let data = ec_point.to_bytes();
+        self.stream_mut().write_all(data.as_ref()).map_err(|err| {
+            Error::Transcript(
+                err.kind(),
+                "Failed to write elliptic curve to transcript".to_string(),
+            )
+        })
+    }
+}
+
+impl<C: CurveAffine, S, H: TranscriptHash<C::Scalar, C::Scalar>>
+    halo2_proofs::transcript::Transcript<C, ChallengeScalar<C>>
+    for HashTranscript<C, NativeLoader, S, H>
+{
+    fn squeeze_challenge(&mut self) -> ChallengeScalar<C> {
+        ChallengeScalar::new(&Transcript::squeeze_challenge(self))
+    }
+
+    fn common_point(&mut self, ec_point: C) -> io::Result<()> {
+        match Transcript::common_ec_point(self, &ec_point) {
+            Err(Error::Transcript(kind, msg)) => Err(io::Error::new(kind, msg)),
+            Err(_) => unreachable!(),
+            _ => Ok(()),
+        }
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        match Transcript::common_scalar(self, &scalar) {
+            Err(Error::Transcript(kind, msg)) => Err(io::Error::new(kind, msg)),
+            Err(_) => unreachable!(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<C: CurveAffine, R: Read, H: TranscriptHash<C::Scalar, C::Scalar>>
+    halo2_proofs::transcript::TranscriptRead<C, ChallengeScalar<C>>
+    for HashTranscript<C, NativeLoader, R, H>
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        match TranscriptRead::read_ec_point(self) {
+            Err(Error::Transcript(kind, msg)) => Err(io::Error::new(kind, msg)),
+            Err(_) => unreachable!(),
+            Ok(value) => Ok(value),
+        }
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        match TranscriptRead::read_scalar(self) {
+            Err(Error::Transcript(kind, msg)) => Err(io::Error::new(kind, msg)),
+            Err(_) => unreachable!(),
+            Ok(value) => Ok(value),
+        }
+    }
+}
+
+impl<C: CurveAffine, W: Write, H: TranscriptHash<C::Scalar, C::Scalar>>
+    halo2_proofs::transcript::TranscriptWrite<C, ChallengeScalar<C>>
+    for HashTranscript<C, NativeLoader, W, H>
+{
+    fn write_point(&mut self, ec_point: C) -> io::Result<()> {
+        halo2_proofs::transcript::Transcript::<C, ChallengeScalar<C>>::common_point(
+            self, ec_point,
+        )?;
+        let data = ec_point.to_bytes();
+        self.stream_mut().write_all(data.as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        halo2_proofs::transcript::Transcript::<C, ChallengeScalar<C>>::common_scalar(self, scalar)?;
+        let data = scalar.to_repr();
+        self.stream_mut().write_all(data.as_ref())
+    }
+}
+
+#[cfg(feature = "loader_evm")]
+mod evm {
+    use super::{HashTranscript, PoseidonTranscript};
+    use crate::{
+        loader::{
+            evm::{loader::Value, EcPoint, EvmLoader, PoseidonSponge, Scalar},
+            ScalarLoader,
+        },
+        util::{
+            arithmetic::{CurveAffine, FieldExt, PrimeField},
+            hash::TranscriptHash,
+            transcript::{label_to_fe, Transcript, TranscriptRead},
+            Itertools,
+        },
+        Error,
+    };
+    use std::{marker::PhantomData, rc::Rc};
+
+    fn load_instances(
+        loader: &Rc<EvmLoader>,
+        stream: &mut usize,
+        num_instance: Vec<usize>,
+    ) -> Vec<Vec<Scalar>> {
+        num_instance
+            .into_iter()
+            .map(|len| {
+                (0..len)
+                    .map(|_| {
+                        let scalar = loader.calldataload_scalar(*stream);
+                        *stream += 0x20;
+                        scalar
+                    })
+                    .collect_vec()
+            })
+            .collect()
+    }
+
+    /// Returns coordinates of `ec_point` reduced into scalar field, like the
+    /// native limb of the assigned point in circuit.
+    fn encode_point(loader: &Rc<EvmLoader>, ec_point: &EcPoint) -> [Scalar; 2] {
+        let scalar_modulus = loader.scalar_modulus();
+        match ec_point.value() {
+            Value::Constant((x, y)) => {
+                [x, y].map(|coordinate| loader.scalar(Value::Constant(coordinate % scalar_modulus)))
+            }
+            Value::Memory(ptr) => [ptr, ptr + 0x20].map(|coordinate_ptr| {
+                let ptr = loader.allocate(0x20);
+                loader
+                    .code_mut()
+                    .push(scalar_modulus)
+                    .push(coordinate_ptr)
+                    .mload()
+                    .r#mod()
+                    .push(ptr)
+                    .mstore();
+                loader.scalar(Value::Memory(ptr))
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    impl<C, const T: usize, const RATE: usize, const R_F: usize, const R_P: usize>
+        PoseidonTranscript<
+            C,
+            Rc<EvmLoader>,
+            usize,
+            PoseidonSponge<C::Scalar, T, RATE>,
+            T,
+            RATE,
+            R_F,
+            R_P,
+        >
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]> + FieldExt,
+    {
+        /// Returns a transcript reading proof from calldata in the same format
+        /// as the native one, which is scalars in little-endian and points in
+        /// compressed form of `halo2curves`, see
+        /// [`EvmLoader::calldataload_ec_point_compressed_le`].
+        pub fn new(loader: Rc<EvmLoader>) -> Self {
+            Self {
+                loader: loader.clone(),
+                stream: 0,
+                buf: PoseidonSponge::new(loader, R_F, R_P),
+                domain_separation: false,
+                _marker: PhantomData,
+            }
+        }
+
+        /// Loads instances in big-endian from calldata, as encoded by
+        /// [`encode_calldata`](crate::loader::evm::encode_calldata).
+        pub fn load_instances(&mut self, num_instance: Vec<usize>) -> Vec<Vec<Scalar>> {
+            load_instances(&self.loader, &mut self.stream, num_instance)
+        }
+    }
+
+    impl<C, const T: usize, const RATE: usize, const R_F: usize, const R_P: usize>
+        Transcript<C, Rc<EvmLoader>>
+        for PoseidonTranscript<
+            C,
+            Rc<EvmLoader>,
+            usize,
+            PoseidonSponge<C::Scalar, T, RATE>,
+            T,
+            RATE,
+            R_F,
+            R_P,
+        >
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]> + FieldExt,
+    {
+        fn loader(&self) -> &Rc<EvmLoader> {
+            &self.loader
+        }
+
+        fn squeeze_challenge(&mut self) -> Scalar {
+            self.buf.squeeze()
+        }
+
+        fn common_scalar(&mut self, scalar: &Scalar) -> Result<(), Error> {
+            self.buf.update(&[scalar.clone()]);
+            Ok(())
+        }
+
+        fn common_ec_point(&mut self, ec_point: &EcPoint) -> Result<(), Error> {
+            let encoded = encode_point(&self.loader, ec_point);
+            self.buf.update(&encoded);
+            Ok(())
+        }
+
+        fn common_label(&mut self, label: &[u8]) {
+            if self.domain_separation {
+                let label = self.loader.load_const(&label_to_fe::<C::Scalar>(label));
+                self.buf.update(&[label]);
+            }
+        }
+    }
+
+    impl<C, const T: usize, const RATE: usize, const R_F: usize, const R_P: usize>
+        TranscriptRead<C, Rc<EvmLoader>>
+        for PoseidonTranscript<
+            C,
+            Rc<EvmLoader>,
+            usize,
+            PoseidonSponge<C::Scalar, T, RATE>,
+            T,
+            RATE,
+            R_F,
+            R_P,
+        >
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]> + FieldExt,
+    {
+        fn read_scalar(&mut self) -> Result<Scalar, Error> {
+            let scalar = self.loader.calldataload_scalar_le(self.stream);
+            self.stream += 0x20;
+            self.common_scalar(&scalar)?;
+            Ok(scalar)
+        }
+
+        fn read_ec_point(&mut self) -> Result<EcPoint, Error> {
+            let ec_point = self.loader.calldataload_ec_point_compressed_le(self.stream);
+            self.stream += 0x20;
+            self.common_ec_point(&ec_point)?;
+            Ok(ec_point)
+        }
+    }
+
+    impl<C, H> HashTranscript<C, Rc<EvmLoader>, usize, H>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        H: TranscriptHash<C::Scalar, Scalar>,
+    {
+        /// Returns a transcript reading proof from calldata in the same format
+        /// as the native one, see [`PoseidonTranscript::new`] of `EvmLoader`.
+        pub fn new(loader: Rc<EvmLoader>, hash: H) -> Self {
+            Self { loader, stream: 0, buf: hash, domain_separation: false, _marker: PhantomData }
+        }
+
+        /// Loads instances in big-endian from calldata, as encoded by
+        /// [`encode_calldata`](crate::loader::evm::encode_calldata).
+        pub fn load_instances(&mut self, num_instance: Vec<usize>) -> Vec<Vec<Scalar>> {
+            load_instances(&self.loader, &mut self.stream, num_instance)
+        }
+    }
+
+    impl<C, H> Transcript<C, Rc<EvmLoader>> for HashTranscript<C, Rc<EvmLoader>, usize, H>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        H: TranscriptHash<C::Scalar, Scalar>,
+    {
+        fn loader(&self) -> &Rc<EvmLoader> {
+            &self.loader
+        }
+
+        fn squeeze_challenge(&mut self) -> Scalar {
+            self.buf.squeeze()
+        }
+
+        fn common_scalar(&mut self, scalar: &Scalar) -> Result<(), Error> {
+            self.buf.update(&[scalar.clone()]);
+            Ok(())
+        }
+
+        fn common_ec_point(&mut self, ec_point: &EcPoint) -> Result<(), Error> {
+            let encoded = encode_point(&self.loader, ec_point);
+            self.buf.update(&encoded);
+            Ok(())
+        }
+
+        fn common_label(&mut self, label: &[u8]) {
+            if self.domain_separation {
+                let label = self.loader.load_const(&label_to_fe::<C::Scalar>(label));
+                self.buf.update(&[label]);
+            }
+        }
+    }
+
+    impl<C, H> TranscriptRead<C, Rc<EvmLoader>> for HashTranscript<C, Rc<EvmLoader>, usize, H>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        H: TranscriptHash<C::Scalar, Scalar>,
+    {
+        fn read_scalar(&mut self) -> Result<Scalar, Error> {
+            let scalar = self.loader.calldataload_scalar_le(self.stream);
+            self.stream += 0x20;
+            self.common_scalar(&scalar)?;
+            Ok(scalar)
+        }
+
+        fn read_ec_point(&mut self) -> Result<EcPoint, Error> {
+            let ec_point = self.loader.calldataload_ec_point_compressed_le(self.stream);
+            self.stream += 0x20;
+            self.common_ec_point(&ec_point)?;
+            Ok(ec_point)
+        }
+    }
+}
@@ -0,0 +1,590 @@
+use crate::{
+    loader::{
+        native::{self, NativeLoader},
+        Loader,
+    },
+    util::{
+        arithmetic::{fe_from_big, modulus, Coordinates, CurveAffine, PrimeField},
+        transcript::{label_to_fe, Transcript, TranscriptRead},
+        Itertools,
+    },
+    Error,
+};
+use halo2_proofs::transcript::EncodedChallenge;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::{
+    io::{self, Read, Write},
+    marker::PhantomData,
+};
+
+/// Transcript with the same encoding as [`EvmTranscript`] but hashed by
+/// SHA-256, for targets where SHA-256 is cheap but keccak256 is not.
+///
+/// Scalars and points are absorbed as 32 bytes big-endian words, points
+/// uncompressed as `x || y`. Challenge is the digest modulo scalar field
+/// modulus, and the digest is kept as the only content of the buffer, which
+/// is padded with `0x01` when squeezing without anything else absorbed.
+///
+/// [`EvmTranscript`]: crate::system::halo2::transcript::evm::EvmTranscript
+pub struct Sha256Transcript<C: CurveAffine, L: Loader<C>, S, B> {
+    loader: L,
+    stream: S,
+    buf: B,
+    domain_separation: bool,
+    _marker: PhantomData<C>,
+}
+
+impl<C: CurveAffine, L: Loader<C>, S, B> Sha256Transcript<C, L, S, B> {
+    /// Sets whether labels passed to [`Transcript::common_label`] are absorbed
+    /// as a word of [`label_to_fe`], which is disabled by default. Prover and
+    /// verifier should agree on it.
+    pub fn set_domain_separation(mut self, domain_separation: bool) -> Self {
+        self.domain_separation = domain_separation;
+        self
+    }
+}
+
+fn digest_to_fe<F: PrimeField>(digest: &[u8; 32]) -> F {
+    fe_from_big(BigUint::from_bytes_be(digest) % modulus::<F>())
+}
+
+impl<C, S> Sha256Transcript<C, NativeLoader, S, Vec<u8>>
+where
+    C: CurveAffine,
+{
+    pub fn new(stream: S) -> Self {
+        Self {
+            loader: NativeLoader,
+            stream,
+            buf: Vec::new(),
+            domain_separation: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C, S> Transcript<C, NativeLoader> for Sha256Transcript<C, NativeLoader, S, Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+{
+    fn loader(&self) -> &NativeLoader {
+        &native::LOADER
+    }
+
+    fn squeeze_challenge(&mut self) -> C::Scalar {
+        let data = self
+            .buf
+            .iter()
+            .cloned()
+            .chain(if self.buf.len() == 0x20 { Some(1) } else { None })
+            .collect_vec();
+        let hash: [u8; 32] = Sha256::digest(data).into();
+        self.buf = hash.to_vec();
+        digest_to_fe(&hash)
+    }
+
+    fn common_ec_point(&mut self, ec_point: &C) -> Result<(), Error> {
+        let coordinates =
+            Option::<Coordinates<C>>::from(ec_point.coordinates()).ok_or_else(|| {
+                Error::Transcript(
+                    io::ErrorKind::Other,
+                    "Cannot write points at infinity to the transcript".to_string(),
+                )
+            })?;
+
+        [coordinates.x(), coordinates.y()].map(|coordinate| {
+            self.buf.extend(coordinate.to_repr().as_ref().iter().rev().cloned());
+        });
+
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: &C::Scalar) -> Result<(), Error> {
+        self.buf.extend(scalar.to_repr().as_ref().iter().rev());
+
+        Ok(())
+    }
+
+    fn common_label(&mut self, label: &[u8]) {
+        if self.domain_separation {
+            self.buf.extend(label_to_fe::<C::Scalar>(label).to_repr().as_ref().iter().rev());
+        }
+    }
+}
+
+impl<C, S> TranscriptRead<C, NativeLoader> for Sha256Transcript<C, NativeLoader, S, Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    S: Read,
+{
+    fn read_scalar(&mut self) -> Result<C::Scalar, Error> {
+        let mut data = [0; 32];
+        self.stream
+            .read_exact(data.as_mut())
+            .map_err(|err| Error::Transcript(err.kind(), err.to_string()))?;
+        data.reverse();
+        let scalar = C::Scalar::from_repr_vartime(data).ok_or_else(|| {
+            Error::Transcript(io::ErrorKind::Other, "Invalid scalar encoding in proof".to_string())
+        })?;
+        self.common_scalar(&scalar)?;
+        Ok(scalar)
+    }
+
+    fn read_ec_point(&mut self) -> Result<C, Error> {
+        let [mut x, mut y] = [<C::Base as PrimeField>::Repr::default(); 2];
+        for repr in [&mut x, &mut y] {
+            self.stream
+                .read_exact(repr.as_mut())
+                .map_err(|err| Error::Transcript(err.kind(), err.to_string()))?;
+            repr.as_mut().reverse();
+        }
+        let x = Option::from(<C::Base as PrimeField>::from_repr(x));
+        let y = Option::from(<C::Base as PrimeField>::from_repr(y));
+        let ec_point =
+            x.zip(y).and_then(|(x, y)| Option::from(C::from_xy(x, y))).ok_or_else(|| {
+                Error::Transcript(
+                    io::ErrorKind::Other,
+                    "Invalid elliptic curve point encoding in proof".to_string(),
+                )
+            })?;
+        self.common_ec_point(&ec_point)?;
+        Ok(ec_point)
+    }
+}
+
+impl<C, S> Sha256Transcript<C, NativeLoader, S, Vec<u8>>
+where
+    C: CurveAffine,
+    S: Write,
+{
+    pub fn stream_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn finalize(self) -> S {
+        self.stream
+    }
+}
+
+pub struct ChallengeSha256<C>(C::Scalar)
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>;
+
+impl<C> EncodedChallenge<C> for ChallengeSha256<C>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    type Input = [u8; 32];
+
+    fn new(challenge_input: &[u8; 32]) -> Self {
+        ChallengeSha256(digest_to_fe(challenge_input))
+    }
+
+    fn get_scalar(&self) -> C::Scalar {
+        self.0
+    }
+}
+
+impl<C, S> halo2_proofs::transcript::Transcript<C, ChallengeSha256<C>>
+    for Sha256Transcript<C, NativeLoader, S, Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn squeeze_challenge(&mut self) -> ChallengeSha256<C> {
+        ChallengeSha256(Transcript::squeeze_challenge(self))
+    }
+
+    fn common_point(&mut self, ec_point: C) -> io::Result<()> {
+        match Transcript::common_ec_point(self, &ec_point) {
+            Err(Error::Transcript(kind, msg)) => Err(io::Error::new(kind, msg)),
+            Err(_) => unreachable!(),
+            _ => Ok(()),
+        }
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        match Transcript::common_scalar(self, &scalar) {
+            Err(Error::Transcript(kind, msg)) => Err(io::Error::new(kind, msg)),
+            Err(_) => unreachable!(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<C, R: Read> halo2_proofs::transcript::TranscriptRead<C, ChallengeSha256<C>>
+    for Sha256Transcript<C, NativeLoader, R, Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        match TranscriptRead::read_ec_point(self) {
+            Err(Error::Transcript(kind, msg)) => Err(io::Error::new(kind, msg)),
+            Err(_) => unreachable!(),
+            Ok(value) => Ok(value),
+        }
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        match TranscriptRead::read_scalar(self) {
+            Err(Error::Transcript(kind, msg)) => Err(io::Error::new(kind, msg)),
+            Err(_) => unreachable!(),
+            Ok(value) => Ok(value),
+        }
+    }
+}
+
+impl<C, R: Read> halo2_proofs::transcript::TranscriptReadBuffer<R, C, ChallengeSha256<C>>
+    for Sha256Transcript<C, NativeLoader, R, Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn init(reader: R) -> Self {
+        Self::new(reader)
+    }
+}
+
+impl<C, W: Write> halo2_proofs::transcript::TranscriptWrite<C, ChallengeSha256<C>>
+    for Sha256Transcript<C, NativeLoader, W, Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn write_point(&mut self, ec_point: C) -> io::Result<()> {
+        halo2_proofs::transcript::Transcript::<C, ChallengeSha256<C>>::common_point(
+            self, ec_point,
+        )?;
+        let coords: Coordinates<C> = Option::from(ec_point.coordinates()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "Cannot write points at infinity to the transcript",
+            )
+        })?;
+        let mut x = coords.x().to_repr();
+        let mut y = coords.y().to_repr();
+        x.as_mut().reverse();
+        y.as_mut().reverse();
+        self.stream_mut().write_all(x.as_ref())?;
+        self.stream_mut().write_all(y.as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        halo2_proofs::transcript::Transcript::<C, ChallengeSha256<C>>::common_scalar(self, scalar)?;
+        let mut data = scalar.to_repr();
+        data.as_mut().reverse();
+        self.stream_mut().write_all(data.as_ref())
+    }
+}
+
+impl<C, W: Write> halo2_proofs::transcript::TranscriptWriterBuffer<W, C, ChallengeSha256<C>>
+    for Sha256Transcript<C, NativeLoader, W, Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 32]>,
+{
+    fn init(writer: W) -> Self {
+        Self::new(writer)
+    }
+
+    fn finalize(self) -> W {
+        self.finalize()
+    }
+}
+
+#[cfg(feature = "loader_evm")]
+mod evm {
+    use super::Sha256Transcript;
+    use crate::{
+        loader::evm::{fe_to_u256, loader::Value, EcPoint, EvmLoader, MemoryChunk, Scalar},
+        util::{
+            arithmetic::{CurveAffine, PrimeField},
+            transcript::{label_to_fe, Transcript, TranscriptRead},
+            Itertools,
+        },
+        Error,
+    };
+    use std::{marker::PhantomData, rc::Rc};
+
+    impl<C> Sha256Transcript<C, Rc<EvmLoader>, usize, MemoryChunk>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        pub fn new(loader: Rc<EvmLoader>) -> Self {
+            let buf = MemoryChunk::new(loader.ptr());
+            Self { loader, stream: 0, buf, domain_separation: false, _marker: PhantomData }
+        }
+
+        pub fn load_instances(&mut self, num_instance: Vec<usize>) -> Vec<Vec<Scalar>> {
+            num_instance
+                .into_iter()
+                .map(|len| {
+                    (0..len)
+                        .map(|_| {
+                            let scalar = self.loader.calldataload_scalar(self.stream);
+                            self.stream += 0x20;
+                            scalar
+                        })
+                        .collect_vec()
+                })
+                .collect()
+        }
+
+        /// Moves buffer to the end of memory if something has been allocated
+        /// since last absorbing, since the precompile hashes a contiguous
+        /// region.
+        fn move_buf_to_end(&mut self) {
+            if self.loader.ptr() == self.buf.end() {
+                return;
+            }
+            let (ptr, len) = (self.loader.allocate(self.buf.len()), self.buf.len());
+            for offset in (0..len).step_by(0x20) {
+                self.loader
+                    .code_mut()
+                    .push(self.buf.ptr() + offset)
+                    .mload()
+                    .push(ptr + offset)
+                    .mstore();
+            }
+            self.buf.reset(ptr);
+            self.buf.extend(len);
+        }
+    }
+
+    impl<C> Transcript<C, Rc<EvmLoader>> for Sha256Transcript<C, Rc<EvmLoader>, usize, MemoryChunk>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        fn loader(&self) -> &Rc<EvmLoader> {
+            &self.loader
+        }
+
+        fn squeeze_challenge(&mut self) -> Scalar {
+            let len = if self.buf.len() == 0x20 {
+                self.move_buf_to_end();
+                self.loader.allocate(0x20);
+                self.loader.code_mut().push(1).push(self.buf.end()).mstore8();
+                0x21
+            } else {
+                self.buf.len()
+            };
+            let hash_ptr = self.loader.sha256(self.buf.ptr(), len);
+
+            let challenge_ptr = self.loader.allocate(0x20);
+            let dup_hash_ptr = self.loader.allocate(0x20);
+            self.loader
+                .code_mut()
+                .push(hash_ptr)
+                .mload()
+                .push(self.loader.scalar_modulus())
+                .dup(1)
+                .r#mod()
+                .push(challenge_ptr)
+                .mstore()
+                .push(dup_hash_ptr)
+                .mstore();
+
+            self.buf.reset(dup_hash_ptr);
+            self.buf.extend(0x20);
+
+            self.loader.scalar(Value::Memory(challenge_ptr))
+        }
+
+        fn common_ec_point(&mut self, ec_point: &EcPoint) -> Result<(), Error> {
+            match ec_point.value() {
+                Value::Memory(ptr) if ptr == self.buf.end() => {}
+                _ => {
+                    self.move_buf_to_end();
+                    self.loader.dup_ec_point(ec_point);
+                }
+            }
+            self.buf.extend(0x40);
+            Ok(())
+        }
+
+        fn common_scalar(&mut self, scalar: &Scalar) -> Result<(), Error> {
+            match scalar.value() {
+                Value::Memory(ptr) if ptr == self.buf.end() => {}
+                _ => {
+                    self.move_buf_to_end();
+                    self.loader.dup_scalar(scalar);
+                }
+            }
+            self.buf.extend(0x20);
+            Ok(())
+        }
+
+        fn common_label(&mut self, label: &[u8]) {
+            if self.domain_separation {
+                self.move_buf_to_end();
+                let ptr = self.loader.allocate(0x20);
+                let label = fe_to_u256(label_to_fe::<C::Scalar>(label));
+                self.loader.code_mut().push(label).push(ptr).mstore();
+                self.buf.extend(0x20);
+            }
+        }
+    }
+
+    impl<C> TranscriptRead<C, Rc<EvmLoader>> for Sha256Transcript<C, Rc<EvmLoader>, usize, MemoryChunk>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        fn read_scalar(&mut self) -> Result<Scalar, Error> {
+            let scalar = self.loader.calldataload_scalar(self.stream);
+            self.stream += 0x20;
+            self.common_scalar(&scalar)?;
+            Ok(scalar)
+        }
+
+        fn read_ec_point(&mut self) -> Result<EcPoint, Error> {
+            let ec_point = self.loader.calldataload_ec_point(self.stream);
+            self.stream += 0x40;
+            self.common_ec_point(&ec_point)?;
+            Ok(ec_point)
+        }
+    }
+}
+
+#[cfg(feature = "loader_halo2")]
+mod halo2 {
+    use super::Sha256Transcript;
+    use crate::{
+        loader::halo2::{
+            bits::{self, Bit},
+            sha256_chip::Sha256Chip,
+            EcPoint, Halo2Loader, Scalar,
+        },
+        util::{
+            arithmetic::{CurveAffine, PrimeField},
+            transcript::{label_to_fe, Transcript, TranscriptRead},
+        },
+        Error,
+    };
+    use halo2_proofs::circuit;
+    use std::{io::Read, marker::PhantomData, rc::Rc};
+
+    type Bits<'a, 'b, C> = Vec<Bit<Scalar<'a, 'b, C>>>;
+
+    /// Converts between little-endian bits of an integer and its big-endian
+    /// bytes in bits, which is an involution.
+    fn reverse_bytes<T: Clone>(bits: &[T]) -> Vec<T> {
+        bits.chunks(8).rev().flatten().cloned().collect()
+    }
+
+    impl<'a, 'b, C, R>
+        Sha256Transcript<C, Rc<Halo2Loader<'a, 'b, C>>, circuit::Value<R>, Bits<'a, 'b, C>>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        pub fn new(loader: &Rc<Halo2Loader<'a, 'b, C>>, stream: circuit::Value<R>) -> Self {
+            Self {
+                loader: loader.clone(),
+                stream,
+                buf: Vec::new(),
+                domain_separation: false,
+                _marker: PhantomData,
+            }
+        }
+
+        /// Absorbs little-endian `bits` of a 256-bit word in big-endian.
+        fn absorb(&mut self, mut bits: Bits<'a, 'b, C>) {
+            bits.resize(0x100, Bit::Constant(false));
+            self.buf.extend(reverse_bytes(&bits));
+        }
+    }
+
+    impl<'a, 'b, C, R> Transcript<C, Rc<Halo2Loader<'a, 'b, C>>>
+        for Sha256Transcript<C, Rc<Halo2Loader<'a, 'b, C>>, circuit::Value<R>, Bits<'a, 'b, C>>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        fn loader(&self) -> &Rc<Halo2Loader<'a, 'b, C>> {
+            &self.loader
+        }
+
+        fn squeeze_challenge(&mut self) -> Scalar<'a, 'b, C> {
+            let mut sha256 = Sha256Chip::new();
+            sha256.update(&self.buf);
+            if self.buf.len() == 0x100 {
+                sha256.update(&Bit::from_u64(1, 8));
+            }
+            let hash = sha256.squeeze();
+            self.buf = hash.clone();
+            bits::compose(&self.loader, &reverse_bytes(&hash))
+        }
+
+        fn common_scalar(&mut self, scalar: &Scalar<'a, 'b, C>) -> Result<(), Error> {
+            let bits = self.loader.assign_canonical_scalar_bits(scalar);
+            self.absorb(bits);
+            Ok(())
+        }
+
+        fn common_ec_point(&mut self, ec_point: &EcPoint<'a, 'b, C>) -> Result<(), Error> {
+            for bits in self.loader.assign_canonical_ec_point_bits(ec_point) {
+                self.absorb(bits);
+            }
+            Ok(())
+        }
+
+        fn common_label(&mut self, label: &[u8]) {
+            if self.domain_separation {
+                let label = label_to_fe::<C::Scalar>(label);
+                self.absorb(Bit::from_bytes(label.to_repr().as_ref()));
+            }
+        }
+    }
+
+    impl<'a, 'b, C, R> TranscriptRead<C, Rc<Halo2Loader<'a, 'b, C>>>
+        for Sha256Transcript<C, Rc<Halo2Loader<'a, 'b, C>>, circuit::Value<R>, Bits<'a, 'b, C>>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        R: Read,
+    {
+        fn read_scalar(&mut self) -> Result<Scalar<'a, 'b, C>, Error> {
+            let scalar = self.stream.as_mut().and_then(|stream| {
+                let mut data = [0; 0x20];
+                if stream.read_exact(data.as_mut()).is_err() {
+                    return circuit::Value::unknown();
+                }
+                data.reverse();
+                Option::<C::Scalar>::from(C::Scalar::from_repr(data))
+                    .map(circuit::Value::known)
+                    .unwrap_or_else(circuit::Value::unknown)
+            });
+            let scalar = self.loader.assign_scalar(scalar);
+            self.common_scalar(&scalar)?;
+            Ok(scalar)
+        }
+
+        fn read_ec_point(&mut self) -> Result<EcPoint<'a, 'b, C>, Error> {
+            let ec_point = self.stream.as_mut().and_then(|stream| {
+                let mut read_base = || {
+                    let mut repr = <C::Base as PrimeField>::Repr::default();
+                    stream.read_exact(repr.as_mut()).ok()?;
+                    repr.as_mut().reverse();
+                    Option::<C::Base>::from(<C::Base as PrimeField>::from_repr(repr))
+                };
+                read_base()
+                    .zip(read_base())
+                    .and_then(|(x, y)| Option::from(C::from_xy(x, y)))
+                    .map(circuit::Value::known)
+                    .unwrap_or_else(circuit::Value::unknown)
+            });
+            let ec_point = self.loader.assign_ec_point(ec_point);
+            self.common_ec_point(&ec_point)?;
+            Ok(ec_point)
+        }
+    }
+}
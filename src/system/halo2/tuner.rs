@@ -0,0 +1,179 @@
+use crate::{
+    loader::halo2::CircuitStats,
+    system::halo2::{Halo2VerifierCircuitConfig, Halo2VerifierCircuitConfigParams},
+    util::arithmetic::LimbsDescriptor,
+};
+use halo2_curves::bn256::Fr;
+use halo2_ecc::fields::fp::FpStrategy;
+use halo2_proofs::{
+    circuit::{SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error,
+        Fixed, FloorPlanner, Instance, Selector,
+    },
+};
+
+/// Number of each kind of columns when counting, which only needs to be large
+/// enough for the synthesis not to run out of columns.
+const NUM_COUNTING_COLUMNS: usize = 256;
+
+/// `Assignment` that discards everything, so synthesis only costs witness
+/// generation and `Halo2Loader` could count cells without a real layout.
+struct Counting;
+
+impl Assignment<Fr> for Counting {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, _: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<Fr>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        _: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fr>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // Keep witness known for cells derived from it
+        to();
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        _: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fr>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        to();
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<Fr>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<Fr> {
+        Value::unknown()
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+/// Synthesizes `circuit` configured by `params` without assigning anything,
+/// and returns number of usable rows of the configuration.
+pub fn count<C>(circuit: &C, params: &Halo2VerifierCircuitConfigParams) -> Result<usize, Error>
+where
+    C: Circuit<Fr, Config = Halo2VerifierCircuitConfig>,
+{
+    let mut meta = ConstraintSystem::default();
+    let config = Halo2VerifierCircuitConfig::configure(&mut meta, params.clone());
+    SimpleFloorPlanner::synthesize(&mut Counting, circuit, config, meta.constants().clone())?;
+    Ok((1 << params.degree) - meta.minimum_rows())
+}
+
+/// Returns the minimal `Halo2VerifierCircuitConfigParams` for `circuit` to
+/// fit in `2^k` rows with base field elements split as `limbs`, with `stats`
+/// returning `CircuitStats` of the last synthesis of `circuit`.
+///
+/// Number of columns are first derived from cells counted with plenty of
+/// columns, then bumped until nothing overflows the usable rows, since gates
+/// could not span across columns.
+pub fn tune_config<C>(
+    circuit: &C,
+    k: u32,
+    strategy: FpStrategy,
+    limbs: LimbsDescriptor,
+    stats: impl Fn(&C) -> Option<CircuitStats>,
+) -> Halo2VerifierCircuitConfigParams
+where
+    C: Circuit<Fr, Config = Halo2VerifierCircuitConfig>,
+{
+    let synthesize = |params: &Halo2VerifierCircuitConfigParams| {
+        let usable_rows = count(circuit, params).expect("synthesis should not fail");
+        (usable_rows, stats(circuit).expect("circuit should record stats when synthesized"))
+    };
+
+    // Lookup table of `lookup_bits` needs to fit in usable rows
+    let mut params = Halo2VerifierCircuitConfigParams {
+        strategy,
+        degree: k,
+        num_advice: NUM_COUNTING_COLUMNS,
+        num_lookup_advice: NUM_COUNTING_COLUMNS,
+        num_fixed: NUM_COUNTING_COLUMNS,
+        lookup_bits: k as usize - 1,
+        limb_bits: limbs.limb_bits,
+        num_limbs: limbs.num_limbs,
+    };
+    let (usable_rows, stats) = synthesize(&params);
+    assert!(1 << params.lookup_bits <= usable_rows);
+
+    let num_columns = |cells: usize| ((cells + usable_rows - 1) / usable_rows).max(1);
+    params.num_advice = num_columns(stats.advice_cells);
+    params.num_lookup_advice = num_columns(stats.lookup_cells);
+    params.num_fixed = num_columns(stats.fixed_cells);
+    loop {
+        let (_, stats) = synthesize(&params);
+        let overflows = [stats.max_advice_rows, stats.max_lookup_rows, stats.max_fixed_rows]
+            .map(|max_rows| max_rows > usable_rows);
+        if !overflows.contains(&true) {
+            return params;
+        }
+
+        for (overflow, num_columns) in overflows.into_iter().zip([
+            &mut params.num_advice,
+            &mut params.num_lookup_advice,
+            &mut params.num_fixed,
+        ]) {
+            if overflow {
+                assert!(*num_columns < NUM_COUNTING_COLUMNS, "Circuit doesn't fit in 2^{} rows", k);
+                *num_columns += 1;
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod arithmetic;
+pub mod hash;
 pub mod msm;
 pub mod protocol;
 pub mod transcript;
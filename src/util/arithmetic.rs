@@ -50,8 +50,13 @@ pub fn batch_invert_and_mul<F: PrimeField>(values: &mut [F], coeff: &F) {
             Some(*acc)
         })
         .collect_vec();
+    // Nothing to invert when all values are zero
+    let all_product = match products.last() {
+        Some(all_product) => all_product,
+        None => return,
+    };
 
-    let mut all_product_inv = products.last().unwrap().invert().unwrap() * coeff;
+    let mut all_product_inv = all_product.invert().unwrap() * coeff;
 
     for (value, product) in values
         .iter_mut()
@@ -203,32 +208,65 @@ pub fn fe_to_fe<F1: PrimeField, F2: PrimeField>(fe: F1) -> F2 {
     fe_from_big(fe_to_big(fe) % modulus::<F2>())
 }
 
+/// Descriptor of how a base field element is split into limbs in scalar
+/// field, which is `num_limbs` limbs in little-endian with `limb_bits` bits
+/// each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimbsDescriptor {
+    pub num_limbs: usize,
+    pub limb_bits: usize,
+}
+
+impl LimbsDescriptor {
+    pub const fn new(num_limbs: usize, limb_bits: usize) -> Self {
+        Self { num_limbs, limb_bits }
+    }
+
+    /// Returns number of limbs of an accumulator, which is 2 ec points.
+    pub fn num_accumulator_limbs(&self) -> usize {
+        4 * self.num_limbs
+    }
+
+    /// Returns indices of limbs of an accumulator placed at the beginning of
+    /// the first instance column.
+    pub fn accumulator_indices(&self) -> Vec<(usize, usize)> {
+        (0..self.num_accumulator_limbs()).map(|idx| (0, idx)).collect()
+    }
+
+    pub fn fe_from_limbs<F1: PrimeField, F2: PrimeField>(&self, limbs: &[F1]) -> F2 {
+        assert_eq!(limbs.len(), self.num_limbs);
+        fe_from_big(
+            limbs
+                .iter()
+                .map(|limb| BigUint::from_bytes_le(limb.to_repr().as_ref()))
+                .zip((0usize..).step_by(self.limb_bits))
+                .map(|(limb, shift)| limb << shift)
+                .reduce(|acc, shifted| acc + shifted)
+                .unwrap(),
+        )
+    }
+
+    pub fn fe_to_limbs<F1: PrimeField, F2: PrimeField>(&self, fe: F1) -> Vec<F2> {
+        let big = BigUint::from_bytes_le(fe.to_repr().as_ref());
+        let mask = (BigUint::one() << self.limb_bits) - 1usize;
+        (0usize..)
+            .step_by(self.limb_bits)
+            .take(self.num_limbs)
+            .map(move |shift| fe_from_big((&big >> shift) & &mask))
+            .collect()
+    }
+}
+
 pub fn fe_from_limbs<F1: PrimeField, F2: PrimeField, const LIMBS: usize, const BITS: usize>(
     limbs: [F1; LIMBS],
 ) -> F2 {
-    fe_from_big(
-        limbs
-            .iter()
-            .map(|limb| BigUint::from_bytes_le(limb.to_repr().as_ref()))
-            .zip((0usize..).step_by(BITS))
-            .map(|(limb, shift)| limb << shift)
-            .reduce(|acc, shifted| acc + shifted)
-            .unwrap(),
-    )
+    LimbsDescriptor::new(LIMBS, BITS).fe_from_limbs(&limbs)
 }
 
 pub fn fe_to_limbs<F1: PrimeField, F2: PrimeField, const LIMBS: usize, const BITS: usize>(
     fe: F1,
 ) -> [F2; LIMBS] {
-    let big = BigUint::from_bytes_le(fe.to_repr().as_ref());
-    let mask = (BigUint::one() << BITS) - 1usize;
-    (0usize..)
-        .step_by(BITS)
-        .take(LIMBS)
-        .map(move |shift| fe_from_big((&big >> shift) & &mask))
-        .collect_vec()
-        .try_into()
-        .unwrap()
+    LimbsDescriptor::new(LIMBS, BITS).fe_to_limbs(fe).try_into().unwrap()
 }
 
 pub fn powers<F>(scalar: F) -> impl Iterator<Item = F>
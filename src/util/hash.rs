@@ -0,0 +1,370 @@
+//! Algebraic hashes for transcripts, with parameters selected at runtime so a
+//! transcript could match the one used by other ecosystems.
+
+use crate::{
+    loader::{LoadedScalar, ScalarLoader},
+    util::{
+        arithmetic::{fe_from_big, modulus, PrimeField},
+        Itertools,
+    },
+};
+use num_bigint::BigUint;
+use std::{collections::VecDeque, marker::PhantomData};
+
+/// Sponge-like hash used by transcripts to absorb elements and squeeze
+/// challenges.
+pub trait TranscriptHash<F: PrimeField, L: LoadedScalar<F>> {
+    fn update(&mut self, elements: &[L]);
+
+    fn squeeze(&mut self) -> L;
+}
+
+/// Grain LFSR in self-shrinking mode, which is used by reference
+/// implementations of Poseidon and Poseidon2 to generate constants.
+struct Grain<F: PrimeField> {
+    state: VecDeque<bool>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> Grain<F> {
+    fn new(t: usize, r_f: usize, r_p: usize) -> Self {
+        let bits =
+            |value: usize, len: usize| (0..len).rev().map(move |idx| (value >> idx) & 1 == 1);
+        // Prime field, S-box `x^α` and the parameters, padded with ones
+        let state = bits(1, 2)
+            .chain(bits(0, 4))
+            .chain(bits(F::NUM_BITS as usize, 12))
+            .chain(bits(t, 12))
+            .chain(bits(r_f, 10))
+            .chain(bits(r_p, 10))
+            .chain(bits((1 << 30) - 1, 30))
+            .collect();
+        let mut grain = Self { state, _marker: PhantomData };
+        for _ in 0..160 {
+            grain.next_raw_bit();
+        }
+        grain
+    }
+
+    fn next_raw_bit(&mut self) -> bool {
+        let bit = [62, 51, 38, 23, 13, 0].into_iter().fold(false, |acc, idx| acc ^ self.state[idx]);
+        self.state.pop_front();
+        self.state.push_back(bit);
+        bit
+    }
+
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let (keep, bit) = (self.next_raw_bit(), self.next_raw_bit());
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    fn next_big(&mut self) -> BigUint {
+        (0..F::NUM_BITS).fold(BigUint::default(), |acc, _| (acc << 1) + self.next_bit() as u8)
+    }
+
+    fn next_field_element(&mut self) -> F {
+        loop {
+            let big = self.next_big();
+            if big < modulus::<F>() {
+                return fe_from_big(big);
+            }
+        }
+    }
+
+    fn next_field_element_without_rejection(&mut self) -> F {
+        fe_from_big(self.next_big() % modulus::<F>())
+    }
+}
+
+fn x_power5<F: PrimeField, L: LoadedScalar<F>>(x: &L) -> L {
+    let x2 = x.clone() * x;
+    let x4 = x2.clone() * &x2;
+    x4 * x
+}
+
+/// Parameters of Poseidon with S-box `x^5`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoseidonParams<F> {
+    t: usize,
+    r_f: usize,
+    r_p: usize,
+    round_constants: Vec<Vec<F>>,
+    mds: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField> PoseidonParams<F> {
+    /// Returns parameters with round constants and Cauchy MDS matrix generated
+    /// by Grain LFSR like the reference implementation.
+    pub fn new(t: usize, r_f: usize, r_p: usize) -> Self {
+        assert!(t >= 2 && r_f % 2 == 0);
+
+        let mut grain = Grain::<F>::new(t, r_f, r_p);
+        let round_constants = (0..r_f + r_p)
+            .map(|_| (0..t).map(|_| grain.next_field_element()).collect_vec())
+            .collect_vec();
+        let mds = loop {
+            let xs_ys =
+                (0..2 * t).map(|_| grain.next_field_element_without_rejection()).collect_vec();
+            if xs_ys.iter().tuple_combinations().any(|(lhs, rhs)| lhs == rhs) {
+                continue;
+            }
+            let (xs, ys) = xs_ys.split_at(t);
+            let mds = xs
+                .iter()
+                .map(|x| {
+                    ys.iter()
+                        .map(|y| Option::<F>::from((*x + y).invert()))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(mds) = mds {
+                break mds;
+            }
+        };
+
+        Self { t, r_f, r_p, round_constants, mds }
+    }
+
+    /// Returns parameters of circomlib's `Poseidon(t - 1)`, which has `R_F = 8`
+    /// and `R_P` chosen by `t` for BN254.
+    pub fn circom(t: usize) -> Self {
+        const R_P: [usize; 16] = [56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68];
+        assert!((2..=17).contains(&t), "circomlib only supports 1 to 16 inputs");
+        Self::new(t, 8, R_P[t - 2])
+    }
+
+    pub fn t(&self) -> usize {
+        self.t
+    }
+
+    pub fn r_f(&self) -> usize {
+        self.r_f
+    }
+
+    pub fn r_p(&self) -> usize {
+        self.r_p
+    }
+
+    pub fn round_constants(&self) -> &[Vec<F>] {
+        &self.round_constants
+    }
+
+    pub fn mds(&self) -> &[Vec<F>] {
+        &self.mds
+    }
+
+    pub fn permute<L: LoadedScalar<F>>(&self, state: &mut [L]) {
+        assert_eq!(state.len(), self.t);
+
+        let half_r_f = self.r_f / 2;
+        for (round, constants) in self.round_constants.iter().enumerate() {
+            for (x, constant) in state.iter_mut().zip(constants.iter()) {
+                *x = x.loader().sum_with_const(&[&*x], *constant);
+            }
+            if round < half_r_f || round >= half_r_f + self.r_p {
+                for x in state.iter_mut() {
+                    *x = x_power5(x);
+                }
+            } else {
+                state[0] = x_power5(&state[0]);
+            }
+            let mixed = self
+                .mds
+                .iter()
+                .map(|row| {
+                    state[0]
+                        .loader()
+                        .sum_with_coeff(&row.iter().cloned().zip(state.iter()).collect_vec())
+                })
+                .collect_vec();
+            state.clone_from_slice(&mixed);
+        }
+    }
+}
+
+/// Parameters of Poseidon2 with S-box `x^5`, for state size 2 or 3 where the
+/// external matrix is `circ(2, 1)` or `circ(2, 1, 1)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Poseidon2Params<F> {
+    t: usize,
+    r_f: usize,
+    r_p: usize,
+    external_constants: Vec<Vec<F>>,
+    internal_constants: Vec<F>,
+    /// Diagonal of internal matrix minus one, so the internal matrix is
+    /// `1 + diag(internal_diagonal)`.
+    internal_diagonal: Vec<F>,
+}
+
+impl<F: PrimeField> Poseidon2Params<F> {
+    /// Returns parameters with round constants generated by Grain LFSR like
+    /// the reference implementation, where internal rounds take only one.
+    pub fn new(t: usize, r_f: usize, r_p: usize, internal_diagonal: Vec<F>) -> Self {
+        assert!((2..=3).contains(&t) && r_f % 2 == 0 && internal_diagonal.len() == t);
+
+        let mut grain = Grain::<F>::new(t, r_f, r_p);
+        let half_r_f = r_f / 2;
+        let mut external_constants = Vec::with_capacity(r_f);
+        let mut internal_constants = Vec::with_capacity(r_p);
+        for round in 0..r_f + r_p {
+            if round < half_r_f || round >= half_r_f + r_p {
+                external_constants.push((0..t).map(|_| grain.next_field_element()).collect());
+            } else {
+                internal_constants.push(grain.next_field_element());
+            }
+        }
+
+        Self { t, r_f, r_p, external_constants, internal_constants, internal_diagonal }
+    }
+
+    /// Returns parameters of Poseidon2 for BN254 with `t = 3`, `R_F = 8` and
+    /// `R_P = 56` by Horizen Labs.
+    pub fn bn254_t3() -> Self {
+        Self::new(3, 8, 56, vec![F::one(), F::one(), F::from(2)])
+    }
+
+    pub fn t(&self) -> usize {
+        self.t
+    }
+
+    pub fn r_f(&self) -> usize {
+        self.r_f
+    }
+
+    pub fn r_p(&self) -> usize {
+        self.r_p
+    }
+
+    pub fn permute<L: LoadedScalar<F>>(&self, state: &mut [L]) {
+        assert_eq!(state.len(), self.t);
+
+        // Matrix `1 + diag(diagonal)` costs only `2t` additions
+        let mix = |state: &mut [L], diagonal: &[F]| {
+            let sum = state[0].loader().sum(&state.iter().collect_vec());
+            for (x, coeff) in state.iter_mut().zip(diagonal.iter()) {
+                *x = x.loader().sum_with_coeff(&[(F::one(), &sum), (*coeff, &*x)]);
+            }
+        };
+        let external_diagonal = vec![F::one(); self.t];
+
+        mix(state, &external_diagonal);
+        let (first, last) = self.external_constants.split_at(self.r_f / 2);
+        for constants in first {
+            for (x, constant) in state.iter_mut().zip(constants.iter()) {
+                *x = x_power5(&x.loader().sum_with_const(&[&*x], *constant));
+            }
+            mix(state, &external_diagonal);
+        }
+        for constant in self.internal_constants.iter() {
+            state[0] = x_power5(&state[0].loader().sum_with_const(&[&state[0]], *constant));
+            mix(state, &self.internal_diagonal);
+        }
+        for constants in last {
+            for (x, constant) in state.iter_mut().zip(constants.iter()) {
+                *x = x_power5(&x.loader().sum_with_const(&[&*x], *constant));
+            }
+            mix(state, &external_diagonal);
+        }
+    }
+}
+
+/// Permutation selected at runtime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HashParams<F> {
+    Poseidon(PoseidonParams<F>),
+    Poseidon2(Poseidon2Params<F>),
+}
+
+impl<F: PrimeField> HashParams<F> {
+    pub fn t(&self) -> usize {
+        match self {
+            HashParams::Poseidon(params) => params.t(),
+            HashParams::Poseidon2(params) => params.t(),
+        }
+    }
+
+    pub fn permute<L: LoadedScalar<F>>(&self, state: &mut [L]) {
+        match self {
+            HashParams::Poseidon(params) => params.permute(state),
+            HashParams::Poseidon2(params) => params.permute(state),
+        }
+    }
+}
+
+impl<F> From<PoseidonParams<F>> for HashParams<F> {
+    fn from(params: PoseidonParams<F>) -> Self {
+        HashParams::Poseidon(params)
+    }
+}
+
+impl<F> From<Poseidon2Params<F>> for HashParams<F> {
+    fn from(params: Poseidon2Params<F>) -> Self {
+        HashParams::Poseidon2(params)
+    }
+}
+
+/// Duplex sponge over a permutation, with the first element as capacity and
+/// output. Squeezing right after absorbing `t - 1` elements from a fresh
+/// sponge gives the same output as circomlib's `Poseidon(t - 1)`.
+///
+/// Absorbing is not padded since transcript always absorbs elements of fixed
+/// layout, but squeezing without absorbing still permutes so consecutive
+/// challenges are different.
+#[derive(Clone, Debug)]
+pub struct Sponge<F: PrimeField, L: LoadedScalar<F>> {
+    params: HashParams<F>,
+    state: Vec<L>,
+    absorbing: Vec<L>,
+}
+
+impl<F: PrimeField, L: LoadedScalar<F>> Sponge<F, L> {
+    pub fn new(loader: &L::Loader, params: impl Into<HashParams<F>>) -> Self {
+        let params = params.into();
+        let state = (0..params.t()).map(|_| loader.load_zero()).collect();
+        Self { params, state, absorbing: Vec::new() }
+    }
+
+    pub fn params(&self) -> &HashParams<F> {
+        &self.params
+    }
+}
+
+impl<F: PrimeField, L: LoadedScalar<F>> TranscriptHash<F, L> for Sponge<F, L> {
+    fn update(&mut self, elements: &[L]) {
+        self.absorbing.extend_from_slice(elements);
+    }
+
+    fn squeeze(&mut self) -> L {
+        let absorbing = std::mem::take(&mut self.absorbing);
+        let rate = self.params.t() - 1;
+
+        if absorbing.is_empty() {
+            self.params.permute(&mut self.state);
+        }
+        for chunk in absorbing.chunks(rate) {
+            for (x, element) in self.state.iter_mut().skip(1).zip(chunk.iter()) {
+                *x = x.loader().sum(&[&*x, element]);
+            }
+            self.params.permute(&mut self.state);
+        }
+
+        self.state[0].clone()
+    }
+}
+
+#[cfg(feature = "loader_halo2")]
+impl<F: crate::util::arithmetic::FieldExt, const T: usize, const RATE: usize> TranscriptHash<F, F>
+    for poseidon::Poseidon<F, T, RATE>
+{
+    fn update(&mut self, elements: &[F]) {
+        poseidon::Poseidon::update(self, elements)
+    }
+
+    fn squeeze(&mut self) -> F {
+        poseidon::Poseidon::squeeze(self)
+    }
+}
@@ -1,7 +1,15 @@
 use crate::{
     loader::{LoadedEcPoint, Loader},
-    util::arithmetic::CurveAffine,
+    util::{
+        arithmetic::{
+            batch_invert, Coordinates, Curve, CurveAffine, Field, Group, PrimeCurveAffine,
+            PrimeField,
+        },
+        Itertools,
+    },
 };
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::{
     default::Default,
     iter::{self, Sum},
@@ -184,3 +192,122 @@ where
         iter.reduce(|acc, item| acc + item).unwrap_or_default()
     }
 }
+
+/// Returns `sum(scalar_i * base_i)` of native points by bucketed Pippenger's
+/// algorithm, where points falling into the same bucket are summed pairwise
+/// in affine with one batched inversion per round.
+///
+/// Windows are processed in parallel when feature `parallel` is enabled.
+pub fn multi_scalar_multiplication<C: CurveAffine>(scalars: &[C::Scalar], bases: &[C]) -> C::Curve {
+    assert_eq!(scalars.len(), bases.len());
+
+    let (reprs, bases): (Vec<_>, Vec<_>) = scalars
+        .iter()
+        .zip(bases.iter())
+        .filter(|(scalar, base)| !scalar.is_zero_vartime() && !bool::from(base.is_identity()))
+        .map(|(scalar, base)| (scalar.to_repr(), *base))
+        .unzip();
+    if bases.is_empty() {
+        return C::Curve::identity();
+    }
+
+    let window_size = window_size(bases.len());
+    let offsets = (0..C::Scalar::NUM_BITS as usize).step_by(window_size).collect_vec();
+    let window_sum = |offset: usize| window_sum(&reprs, &bases, offset, window_size);
+    #[cfg(feature = "parallel")]
+    let window_sums = offsets.into_par_iter().map(window_sum).collect::<Vec<_>>();
+    #[cfg(not(feature = "parallel"))]
+    let window_sums = offsets.into_iter().map(window_sum).collect_vec();
+
+    window_sums.into_iter().rev().fold(C::Curve::identity(), |acc, window_sum| {
+        (0..window_size).fold(acc, |acc, _| acc.double()) + window_sum
+    })
+}
+
+fn window_size(num_bases: usize) -> usize {
+    if num_bases < 4 {
+        1
+    } else if num_bases < 32 {
+        3
+    } else {
+        (num_bases as f64).ln().ceil() as usize
+    }
+}
+
+fn window_digit(repr: &[u8], offset: usize, window_size: usize) -> usize {
+    (offset..offset + window_size).rev().fold(0, |acc, idx| {
+        let bit = repr.get(idx / 8).map(|byte| (byte >> (idx % 8)) & 1).unwrap_or_default();
+        (acc << 1) | bit as usize
+    })
+}
+
+fn window_sum<C: CurveAffine>(
+    reprs: &[<C::Scalar as PrimeField>::Repr],
+    bases: &[C],
+    offset: usize,
+    window_size: usize,
+) -> C::Curve {
+    let mut buckets = vec![Vec::new(); (1 << window_size) - 1];
+    for (repr, base) in reprs.iter().zip(bases.iter()) {
+        let digit = window_digit(repr.as_ref(), offset, window_size);
+        if digit != 0 {
+            buckets[digit - 1].push(*base);
+        }
+    }
+    batch_add(&mut buckets);
+
+    // `sum(i * bucket_i)` as sum of running sums from the top bucket
+    let mut running = C::Curve::identity();
+    let mut sum = C::Curve::identity();
+    for bucket in buckets.into_iter().rev() {
+        if let Some(point) = bucket.first() {
+            running += point;
+        }
+        sum += running;
+    }
+    sum
+}
+
+/// Sums points in each bucket by repeatedly adding adjacent pairs across all
+/// buckets with a single batched inversion, until each bucket has at most one
+/// point left.
+fn batch_add<C: CurveAffine>(buckets: &mut [Vec<C>]) {
+    let coordinates = |point: &C| Option::<Coordinates<C>>::from(point.coordinates());
+    while buckets.iter().any(|bucket| bucket.len() > 1) {
+        // Denominator is left as zero for pairs to be added in projective
+        let mut denoms = buckets
+            .iter()
+            .flat_map(|bucket| bucket.chunks_exact(2))
+            .map(|pair| match (coordinates(&pair[0]), coordinates(&pair[1])) {
+                (Some(lhs), Some(rhs)) => *rhs.x() - lhs.x(),
+                _ => C::Base::zero(),
+            })
+            .collect_vec();
+        batch_invert(&mut denoms);
+
+        let mut denom_invs = denoms.into_iter();
+        for bucket in buckets.iter_mut() {
+            let summed = bucket
+                .chunks(2)
+                .map(|pair| match pair {
+                    [lhs, rhs] => add_affine(lhs, rhs, &denom_invs.next().unwrap()),
+                    [point] => *point,
+                    _ => unreachable!(),
+                })
+                .collect_vec();
+            *bucket = summed;
+        }
+    }
+}
+
+fn add_affine<C: CurveAffine>(lhs: &C, rhs: &C, denom_inv: &C::Base) -> C {
+    if denom_inv.is_zero_vartime() {
+        return (lhs.to_curve() + rhs).to_affine();
+    }
+
+    let (lhs, rhs) = (lhs.coordinates().unwrap(), rhs.coordinates().unwrap());
+    let lambda = (*rhs.y() - lhs.y()) * denom_inv;
+    let x = lambda.square() - lhs.x() - rhs.x();
+    let y = lambda * (*lhs.x() - x) - lhs.y();
+    C::from_xy(x, y).unwrap()
+}
@@ -58,9 +58,15 @@ where
             .iter()
             .map(|&i| loader.load_const(&domain.rotate_scalar(C::Scalar::one(), Rotation(i))))
             .collect_vec();
-        let lagrange_evals = omegas
+        let lagrange_evals = langranges
             .iter()
-            .map(|omega| Fraction::new(numer.clone() * omega, z.clone() - omega))
+            .zip(omegas.iter())
+            .map(|(i, omega)| {
+                loader.start_annotation(&format!("lagrange {}", i));
+                let lagrange_eval = Fraction::new(numer.clone() * omega, z.clone() - omega);
+                loader.end_annotation();
+                lagrange_eval
+            })
             .collect_vec();
 
         Self {
@@ -1,8 +1,24 @@
 use crate::{
     loader::{native::NativeLoader, Loader},
-    {util::arithmetic::CurveAffine, Error},
+    {
+        util::arithmetic::{CurveAffine, PrimeField},
+        Error,
+    },
 };
 
+/// Maximal length of label for domain separation, see [`label_to_fe`].
+pub const MAX_LABEL_LEN: usize = 30;
+
+/// Returns `len || label` as a big-endian integer, which is an injective
+/// encoding of `label` into a field element, since it's less than `2^248`.
+pub fn label_to_fe<F: PrimeField>(label: &[u8]) -> F {
+    assert!(label.len() <= MAX_LABEL_LEN, "Label should be at most {} bytes", MAX_LABEL_LEN);
+    let base = F::from(256);
+    std::iter::once(label.len() as u8)
+        .chain(label.iter().cloned())
+        .fold(F::zero(), |acc, byte| acc * base + F::from(byte as u64))
+}
+
 pub trait Transcript<C, L>
 where
     C: CurveAffine,
@@ -19,6 +35,35 @@ where
     fn common_ec_point(&mut self, ec_point: &L::LoadedEcPoint) -> Result<(), Error>;
 
     fn common_scalar(&mut self, scalar: &L::LoadedScalar) -> Result<(), Error>;
+
+    /// Absorbs `label` for domain separation, Merlin-style, so challenges of
+    /// different sub-protocols sharing a transcript can't be reused across
+    /// them. Transcripts supporting it only absorb labels when enabled to keep
+    /// proofs without labels compatible, so it's a no-op by default.
+    fn common_label(&mut self, _label: &[u8]) {}
+
+    fn squeeze_labeled_challenge(&mut self, label: &[u8]) -> L::LoadedScalar {
+        self.common_label(label);
+        self.squeeze_challenge()
+    }
+
+    fn common_labeled_ec_point(
+        &mut self,
+        label: &[u8],
+        ec_point: &L::LoadedEcPoint,
+    ) -> Result<(), Error> {
+        self.common_label(label);
+        self.common_ec_point(ec_point)
+    }
+
+    fn common_labeled_scalar(
+        &mut self,
+        label: &[u8],
+        scalar: &L::LoadedScalar,
+    ) -> Result<(), Error> {
+        self.common_label(label);
+        self.common_scalar(scalar)
+    }
 }
 
 pub trait TranscriptRead<C, L>: Transcript<C, L>
@@ -45,6 +45,9 @@ where
         instances: &[Vec<L::LoadedScalar>],
         proof: &Self::Proof,
     ) -> Result<Vec<MOS::Accumulator>, Error> {
+        let loader = proof.z.loader();
+
+        loader.start_cost_metering("evaluations");
         let common_poly_eval = {
             let mut common_poly_eval = CommonPolynomialEvaluation::new(
                 &protocol.domain,
@@ -61,8 +64,11 @@ where
         let mut evaluations = proof.evaluations(protocol, instances, &common_poly_eval)?;
         let commitments = proof.commitments(protocol, &common_poly_eval, &mut evaluations)?;
         let queries = proof.queries(protocol, evaluations);
+        loader.end_cost_metering();
 
+        loader.start_cost_metering("msm");
         let accumulator = MOS::succinct_verify(svk, &commitments, &proof.z, &queries, &proof.pcs)?;
+        loader.end_cost_metering();
 
         let accumulators = iter::empty()
             .chain(Some(accumulator))
@@ -79,6 +85,9 @@ where
         proof: &Self::Proof,
         use_dummy: &L::LoadedScalar,
     ) -> Result<Vec<MOS::Accumulator>, Error> {
+        let loader = proof.z.loader();
+
+        loader.start_cost_metering("evaluations");
         let common_poly_eval = {
             let mut common_poly_eval = CommonPolynomialEvaluation::new(
                 &protocol.domain,
@@ -95,7 +104,9 @@ where
         let mut evaluations = proof.evaluations(protocol, instances, &common_poly_eval)?;
         let commitments = proof.commitments(protocol, &common_poly_eval, &mut evaluations)?;
         let queries = proof.queries(protocol, evaluations);
+        loader.end_cost_metering();
 
+        loader.start_cost_metering("msm");
         let accumulator = MOS::succinct_verify_or_dummy(
             svk,
             &commitments,
@@ -104,6 +115,7 @@ where
             &proof.pcs,
             use_dummy,
         )?;
+        loader.end_cost_metering();
 
         let accumulators = iter::empty()
             .chain(Some(accumulator))
@@ -152,6 +164,7 @@ where
             return Err(Error::InvalidInstances);
         }
 
+        transcript.loader().start_cost_metering("transcript");
         let committed_instances = if let Some(ick) = &protocol.instance_committing_key {
             // this case is synonymous with KZG_QUERY_INSTANCE = true
             let loader = transcript.loader();
@@ -198,13 +211,25 @@ where
             None
         };
 
+        // Challenges are labeled to tell stages apart, but labels are only
+        // absorbed when transcript has domain separation enabled, which should
+        // be kept disabled for proofs created by halo2 prover.
         let (witnesses, challenges) = {
+            let mut num_challenge = 0;
             let (witnesses, challenges) = protocol
                 .num_witness
                 .iter()
                 .zip(protocol.num_challenge.iter())
                 .map(|(&n, &m)| {
-                    Ok((transcript.read_n_ec_points(n)?, transcript.squeeze_n_challenges(m)))
+                    let witnesses = transcript.read_n_ec_points(n)?;
+                    let challenges = (num_challenge..num_challenge + m)
+                        .map(|idx| {
+                            transcript
+                                .squeeze_labeled_challenge(format!("challenge {}", idx).as_bytes())
+                        })
+                        .collect_vec();
+                    num_challenge += m;
+                    Ok((witnesses, challenges))
                 })
                 .collect::<Result<Vec<_>, Error>>()?
                 .into_iter()
@@ -218,7 +243,7 @@ where
 
         let quotients = transcript.read_n_ec_points(protocol.quotient.num_chunk())?;
 
-        let z = transcript.squeeze_challenge();
+        let z = transcript.squeeze_labeled_challenge(b"z");
         let evaluations = transcript.read_n_scalars(protocol.evaluations.len())?;
 
         let pcs = MOS::read_proof(svk, &Self::empty_queries(protocol), transcript)?;
@@ -231,6 +256,7 @@ where
             })
             .map(AE::from_repr)
             .collect::<Result<Vec<_>, _>>()?;
+        transcript.loader().end_cost_metering();
 
         Ok(Self {
             committed_instances,